@@ -0,0 +1,285 @@
+//! # Commands.
+//! Named actions of the app, listed in the command palette and bound to keys in the keymap.
+
+use chrono::Local;
+
+use crate::data::{
+	io_utils::save_file,
+	line_editing::LineOperation,
+	stores::{
+		CURRENT_TAB, close_tab, current_file, cycle_tab, delete_tab, new_tab, open_adjacent_daily_note, open_daily_note,
+		open_template_picker, redo_edit, reload_keymap, reset_ui_zoom, reset_zoom, run_line_operation_at_cursor,
+		run_table_command_at_cursor, toggle_command_palette, toggle_persistent_undo_history, toggle_properties, toggle_shortcuts,
+		toggle_side_panel, toggle_task_at_cursor, toggle_vim_mode, undo_edit, undo_to_last_save, zoom_editor, zoom_ui,
+	},
+	tables::{Alignment, TableCommand},
+	types::{SidePanel, TemplateTarget},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+	NewNote,
+	SaveNote,
+	DeleteNote,
+	CloseTab,
+	NextTab,
+	ShowCommandPalette,
+	ShowKeyboardShortcuts,
+	NewNoteFromTemplate,
+	InsertTemplate,
+	TodaysNote,
+	PreviousDailyNote,
+	NextDailyNote,
+	ToggleCalendar,
+	ToggleTask,
+	ToggleTasksPanel,
+	ToggleTagBrowser,
+	ToggleOutline,
+	ToggleProperties,
+	ZoomIn,
+	ZoomOut,
+	ResetZoom,
+	ZoomInInterface,
+	ZoomOutInterface,
+	ResetInterfaceZoom,
+	Undo,
+	Redo,
+	UndoToLastSave,
+	ToggleHistoryPanel,
+	TogglePersistentUndoHistory,
+	ToggleVimMode,
+	ReloadKeybindings,
+	Line(LineOperation),
+	Table(TableCommand),
+}
+
+impl Command {
+	/// Every command, in the order they are listed in the command palette.
+	pub const ALL: [Command; 59] = [
+		Command::NewNote,
+		Command::SaveNote,
+		Command::DeleteNote,
+		Command::CloseTab,
+		Command::NextTab,
+		Command::ShowCommandPalette,
+		Command::ShowKeyboardShortcuts,
+		Command::NewNoteFromTemplate,
+		Command::InsertTemplate,
+		Command::TodaysNote,
+		Command::PreviousDailyNote,
+		Command::NextDailyNote,
+		Command::ToggleCalendar,
+		Command::ToggleTask,
+		Command::ToggleTasksPanel,
+		Command::ToggleTagBrowser,
+		Command::ToggleOutline,
+		Command::ToggleProperties,
+		Command::ZoomIn,
+		Command::ZoomOut,
+		Command::ResetZoom,
+		Command::ZoomInInterface,
+		Command::ZoomOutInterface,
+		Command::ResetInterfaceZoom,
+		Command::Undo,
+		Command::Redo,
+		Command::UndoToLastSave,
+		Command::ToggleHistoryPanel,
+		Command::TogglePersistentUndoHistory,
+		Command::ToggleVimMode,
+		Command::ReloadKeybindings,
+		Command::Line(LineOperation::MoveUp),
+		Command::Line(LineOperation::MoveDown),
+		Command::Line(LineOperation::Duplicate),
+		Command::Line(LineOperation::Delete),
+		Command::Line(LineOperation::Join),
+		Command::Line(LineOperation::Sort),
+		Command::Line(LineOperation::Unique),
+		Command::Line(LineOperation::UpperCase),
+		Command::Line(LineOperation::LowerCase),
+		Command::Line(LineOperation::TitleCase),
+		Command::Line(LineOperation::ToggleComment),
+		Command::Line(LineOperation::Indent),
+		Command::Line(LineOperation::Outdent),
+		Command::Table(TableCommand::Format),
+		Command::Table(TableCommand::InsertRowAbove),
+		Command::Table(TableCommand::InsertRowBelow),
+		Command::Table(TableCommand::DeleteRow),
+		Command::Table(TableCommand::MoveRowUp),
+		Command::Table(TableCommand::MoveRowDown),
+		Command::Table(TableCommand::InsertColumnLeft),
+		Command::Table(TableCommand::InsertColumnRight),
+		Command::Table(TableCommand::DeleteColumn),
+		Command::Table(TableCommand::MoveColumnLeft),
+		Command::Table(TableCommand::MoveColumnRight),
+		Command::Table(TableCommand::Align(Alignment::Left)),
+		Command::Table(TableCommand::Align(Alignment::Center)),
+		Command::Table(TableCommand::Align(Alignment::Right)),
+		Command::Table(TableCommand::Align(Alignment::None)),
+	];
+
+	pub fn title(self) -> &'static str {
+		match self {
+			Command::NewNote => "New Note",
+			Command::SaveNote => "Save Note",
+			Command::DeleteNote => "Delete Note",
+			Command::CloseTab => "Close Tab",
+			Command::NextTab => "Next Tab",
+			Command::ShowCommandPalette => "Show Command Palette",
+			Command::ShowKeyboardShortcuts => "Show Keyboard Shortcuts",
+			Command::NewNoteFromTemplate => "New Note from Template",
+			Command::InsertTemplate => "Insert Template",
+			Command::TodaysNote => "Today's Note",
+			Command::PreviousDailyNote => "Previous Daily Note",
+			Command::NextDailyNote => "Next Daily Note",
+			Command::ToggleCalendar => "Toggle Calendar",
+			Command::ToggleTask => "Toggle Task",
+			Command::ToggleTasksPanel => "Toggle Tasks Panel",
+			Command::ToggleTagBrowser => "Toggle Tag Browser",
+			Command::ToggleOutline => "Toggle Outline",
+			Command::ToggleProperties => "Toggle Properties",
+			Command::ZoomIn => "Zoom In",
+			Command::ZoomOut => "Zoom Out",
+			Command::ResetZoom => "Reset Zoom",
+			Command::ZoomInInterface => "Zoom In Interface",
+			Command::ZoomOutInterface => "Zoom Out Interface",
+			Command::ResetInterfaceZoom => "Reset Interface Zoom",
+			Command::Undo => "Undo",
+			Command::Redo => "Redo",
+			Command::UndoToLastSave => "Undo to Last Save",
+			Command::ToggleHistoryPanel => "Toggle History Panel",
+			Command::TogglePersistentUndoHistory => "Toggle Persistent Undo History",
+			Command::ToggleVimMode => "Toggle Vim Mode",
+			Command::ReloadKeybindings => "Reload Keybindings",
+			Command::Line(operation) => match operation {
+				LineOperation::MoveUp => "Move Line Up",
+				LineOperation::MoveDown => "Move Line Down",
+				LineOperation::Duplicate => "Duplicate Line",
+				LineOperation::Delete => "Delete Line",
+				LineOperation::Join => "Join Lines",
+				LineOperation::Sort => "Sort Lines",
+				LineOperation::Unique => "Unique Lines",
+				LineOperation::UpperCase => "Upper Case",
+				LineOperation::LowerCase => "Lower Case",
+				LineOperation::TitleCase => "Title Case",
+				LineOperation::ToggleComment => "Toggle Comment",
+				LineOperation::Indent => "Indent Lines",
+				LineOperation::Outdent => "Outdent Lines",
+			},
+			Command::Table(command) => match command {
+				TableCommand::Format => "Table: Format",
+				TableCommand::InsertRowAbove => "Table: Insert Row Above",
+				TableCommand::InsertRowBelow => "Table: Insert Row Below",
+				TableCommand::DeleteRow => "Table: Delete Row",
+				TableCommand::MoveRowUp => "Table: Move Row Up",
+				TableCommand::MoveRowDown => "Table: Move Row Down",
+				TableCommand::InsertColumnLeft => "Table: Insert Column Left",
+				TableCommand::InsertColumnRight => "Table: Insert Column Right",
+				TableCommand::DeleteColumn => "Table: Delete Column",
+				TableCommand::MoveColumnLeft => "Table: Move Column Left",
+				TableCommand::MoveColumnRight => "Table: Move Column Right",
+				TableCommand::Align(Alignment::Left) => "Table: Align Column Left",
+				TableCommand::Align(Alignment::Center) => "Table: Align Column Center",
+				TableCommand::Align(Alignment::Right) => "Table: Align Column Right",
+				TableCommand::Align(Alignment::None) => "Table: Reset Column Alignment",
+			},
+		}
+	}
+
+	/// Name of the command in the key bindings, its title in snake case.
+	pub fn id(self) -> String {
+		self.title()
+			.replace('\'', "")
+			.split(|ch: char| !ch.is_alphanumeric())
+			.filter(|word| !word.is_empty())
+			.map(str::to_lowercase)
+			.collect::<Vec<String>>()
+			.join("_")
+	}
+
+	pub fn from_id(id: &str) -> Option<Command> {
+		Command::ALL.into_iter().find(|command| command.id() == id)
+	}
+
+	pub fn is_id(id: &str) -> bool {
+		Command::from_id(id).is_some()
+	}
+
+	/// Group of the command in the keyboard shortcuts overlay.
+	pub fn category(self) -> &'static str {
+		match self {
+			Command::NewNote | Command::SaveNote | Command::DeleteNote | Command::NewNoteFromTemplate | Command::InsertTemplate => "Notes",
+			Command::CloseTab | Command::NextTab => "Tabs",
+			Command::TodaysNote | Command::PreviousDailyNote | Command::NextDailyNote | Command::ToggleCalendar => "Daily Notes",
+			Command::ToggleTask | Command::ToggleTasksPanel => "Tasks",
+			Command::ShowCommandPalette
+			| Command::ShowKeyboardShortcuts
+			| Command::ToggleTagBrowser
+			| Command::ToggleOutline
+			| Command::ToggleProperties
+			| Command::ZoomIn
+			| Command::ZoomOut
+			| Command::ResetZoom
+			| Command::ZoomInInterface
+			| Command::ZoomOutInterface
+			| Command::ResetInterfaceZoom => "View",
+			Command::Undo
+			| Command::Redo
+			| Command::UndoToLastSave
+			| Command::ToggleHistoryPanel
+			| Command::TogglePersistentUndoHistory => "History",
+			Command::ToggleVimMode | Command::Line(_) => "Editing",
+			Command::Table(_) => "Tables",
+			Command::ReloadKeybindings => "App",
+		}
+	}
+
+	pub async fn run(self) {
+		match self {
+			Command::NewNote => new_tab().await,
+			Command::SaveNote => {
+				if let Some(file) = current_file() {
+					save_file(file).await;
+				}
+			}
+			Command::DeleteNote => {
+				if let Some(tab_index) = CURRENT_TAB() {
+					delete_tab(tab_index).await;
+				}
+			}
+			Command::CloseTab => {
+				if let Some(tab_index) = CURRENT_TAB() {
+					close_tab(tab_index).await;
+				}
+			}
+			Command::NextTab => cycle_tab().await,
+			Command::ShowCommandPalette => toggle_command_palette(),
+			Command::ShowKeyboardShortcuts => toggle_shortcuts(),
+			Command::NewNoteFromTemplate => open_template_picker(TemplateTarget::NewNote),
+			Command::InsertTemplate => open_template_picker(TemplateTarget::CurrentNote),
+			Command::TodaysNote => open_daily_note(Local::now().date_naive()).await,
+			Command::PreviousDailyNote => open_adjacent_daily_note(-1).await,
+			Command::NextDailyNote => open_adjacent_daily_note(1).await,
+			Command::ToggleCalendar => toggle_side_panel(SidePanel::Calendar),
+			Command::ToggleTask => toggle_task_at_cursor(),
+			Command::ToggleTasksPanel => toggle_side_panel(SidePanel::Tasks),
+			Command::ToggleTagBrowser => toggle_side_panel(SidePanel::Tags),
+			Command::ToggleOutline => toggle_side_panel(SidePanel::Outline),
+			Command::ToggleProperties => toggle_properties(),
+			Command::ZoomIn => zoom_editor(true),
+			Command::ZoomOut => zoom_editor(false),
+			Command::ResetZoom => reset_zoom(),
+			Command::ZoomInInterface => zoom_ui(true),
+			Command::ZoomOutInterface => zoom_ui(false),
+			Command::ResetInterfaceZoom => reset_ui_zoom(),
+			Command::Undo => undo_edit(),
+			Command::Redo => redo_edit(),
+			Command::UndoToLastSave => undo_to_last_save(),
+			Command::ToggleHistoryPanel => toggle_side_panel(SidePanel::History),
+			Command::TogglePersistentUndoHistory => toggle_persistent_undo_history(),
+			Command::ToggleVimMode => toggle_vim_mode(),
+			Command::ReloadKeybindings => reload_keymap(),
+			Command::Line(operation) => run_line_operation_at_cursor(operation),
+			Command::Table(command) => run_table_command_at_cursor(command),
+		}
+	}
+}
@@ -0,0 +1,142 @@
+//! # Daily Notes.
+//! Journal notes named after their date (`YYYY-MM-DD.md`), stored in a folder of the trove.
+
+use chrono::{Datelike, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Format of the file name of a daily note.
+const DAILY_NOTE_FORMAT: &str = "%Y-%m-%d";
+
+/// Where the daily notes are stored and how they are created.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyNotesConfig {
+	pub folder: String,           // Folder of the daily notes, relative to the trove
+	pub template: Option<String>, // Name of the template used for new daily notes, if any
+}
+
+impl Default for DailyNotesConfig {
+	fn default() -> Self {
+		DailyNotesConfig {
+			folder: "Daily".to_string(),
+			template: None,
+		}
+	}
+}
+
+impl DailyNotesConfig {
+	/// Returns the folder of the daily notes, always inside the trove: the `.` and `..` parts of the folder, and
+	/// the roots and drives that would make it absolute, are left out.
+	pub fn folder_path(&self, trove_dir: &Path) -> PathBuf {
+		let mut path = trove_dir.to_path_buf();
+		for part in self.folder.split(['/', '\\']).map(str::trim) {
+			if !(part.is_empty() || part == "." || part == ".." || part.contains(':')) {
+				path.push(part);
+			}
+		}
+		path
+	}
+
+	/// Returns the path of the daily note of the given date.
+	pub fn note_path(&self, trove_dir: &Path, date: NaiveDate) -> PathBuf {
+		self.folder_path(trove_dir).join(format!("{}.md", date.format(DAILY_NOTE_FORMAT)))
+	}
+
+	/// Returns the date of the note if it is a daily note.
+	pub fn note_date(&self, trove_dir: &Path, path: &Path) -> Option<NaiveDate> {
+		if path.parent()? != self.folder_path(trove_dir) {
+			return None;
+		}
+		parse_daily_note_date(path)
+	}
+}
+
+/// Parses the date from the file name of a daily note.
+pub fn parse_daily_note_date(path: &Path) -> Option<NaiveDate> {
+	let stem = path.file_stem()?.to_str()?;
+	NaiveDate::parse_from_str(stem, DAILY_NOTE_FORMAT).ok()
+}
+
+/// Returns the day before or after the given date, `offset` being a number of days.
+pub fn offset_day(date: NaiveDate, offset: i64) -> NaiveDate {
+	let days = Days::new(offset.unsigned_abs());
+	let moved = if offset < 0 {
+		date.checked_sub_days(days)
+	} else {
+		date.checked_add_days(days)
+	};
+	moved.unwrap_or(date)
+}
+
+/// Returns the first day of the month before or after the month of the given date.
+pub fn offset_month(date: NaiveDate, offset: i32) -> NaiveDate {
+	let months = date.year() * 12 + date.month0() as i32 + offset;
+	NaiveDate::from_ymd_opt(months.div_euclid(12), months.rem_euclid(12) as u32 + 1, 1).unwrap_or(date)
+}
+
+/// Returns the weeks of the month of the given date, from Monday to Sunday. Days outside of the month
+/// are `None`.
+pub fn month_weeks(date: NaiveDate) -> Vec<[Option<NaiveDate>; 7]> {
+	let first = date.with_day(1).unwrap_or(date);
+	let mut weeks = Vec::new();
+	let mut week = [None; 7];
+	let mut day = first;
+
+	while day.month() == first.month() {
+		let weekday = day.weekday().num_days_from_monday() as usize;
+		week[weekday] = Some(day);
+		if weekday == 6 {
+			weeks.push(week);
+			week = [None; 7];
+		}
+		match day.succ_opt() {
+			Some(next) => day = next,
+			None => break,
+		}
+	}
+	if week.iter().any(Option::is_some) {
+		weeks.push(week);
+	}
+	weeks
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn folder_path(folder: &str) -> PathBuf {
+		let config = DailyNotesConfig {
+			folder: folder.to_string(),
+			template: None,
+		};
+		config.folder_path(Path::new("/trove"))
+	}
+
+	#[test]
+	fn folder_path_is_relative_to_the_trove() {
+		assert_eq!(folder_path("Daily"), Path::new("/trove/Daily"));
+		assert_eq!(folder_path(" Journal/2025/ "), Path::new("/trove/Journal/2025"));
+		assert_eq!(folder_path("Journal\\2025"), Path::new("/trove/Journal/2025"));
+		assert_eq!(folder_path(""), Path::new("/trove"));
+	}
+
+	#[test]
+	fn folder_path_stays_inside_the_trove() {
+		assert_eq!(folder_path("../outside"), Path::new("/trove/outside"));
+		assert_eq!(folder_path("Daily/../../.."), Path::new("/trove/Daily"));
+		assert_eq!(folder_path("/etc"), Path::new("/trove/etc"));
+		assert_eq!(folder_path("C:\\Users"), Path::new("/trove/Users"));
+		assert_eq!(folder_path("./Daily/."), Path::new("/trove/Daily"));
+	}
+
+	#[test]
+	fn note_date_only_in_the_folder() {
+		let config = DailyNotesConfig::default();
+		let trove = Path::new("/trove");
+		let date = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+		assert_eq!(config.note_date(trove, &config.note_path(trove, date)), Some(date));
+		assert_eq!(config.note_date(trove, Path::new("/trove/2025-03-14.md")), None);
+		assert_eq!(config.note_date(trove, Path::new("/trove/Daily/notes.md")), None);
+	}
+}
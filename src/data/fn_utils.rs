@@ -1,8 +1,19 @@
 use freya::prelude::*;
 
 use crate::data::{
+	commands::Command,
 	io_utils::save_file,
-	stores::{CURRENT_TAB, FILES_ARENA, TABS, close_tab, cycle_tab, delete_tab, new_tab, toggle_command_palette},
+	keymap::{Chord, KeyPress},
+	line_editing::{LineOperation, apply_line_operation},
+	list_editing::{continue_list, indent_list_items, renumber_lists, wrap_selection},
+	multi_cursor::{Caret, CaretAction, CaretMotion, apply_caret_action, next_occurrence},
+	stores::{
+		CLIPBOARD, CURRENT_TAB, EXTRA_CURSORS, KEYMAP, RECORDING_BINDING, SHOW_THEMES_DROPUP, VIM_MODE, VIM_STATE, close_tab, current_file,
+		redo_edit, undo_edit,
+	},
+	tables::{add_row, cell_index, move_to_cell, paste_as_table, realign_table},
+	text_edit::TextEdit,
+	vim::{VimCommand, VimKey, VimMode},
 };
 
 // #[derive(PartialEq)]
@@ -11,71 +22,326 @@ use crate::data::{
 // 	Editor(UseEditable),
 // }
 
+/// Runs the command bound to the key in the keymap, following sequences of chords.
 pub(crate) async fn handle_global_keyboard_input(e: KeyboardEvent) {
-	let key = &e.data.key;
-	let modifiers = e.data.modifiers;
-
-	if !modifiers.contains(Modifiers::CONTROL) {
+	// Keys typed while recording a binding belong to the keyboard shortcuts overlay.
+	if RECORDING_BINDING.peek().is_some() {
 		return;
 	}
-	match key {
-		Key::Character(c) if c == "s" => {
-			e.stop_propagation();
-			log::debug!("CTRL + S was Pressed.");
-			let current_tab_content = FILES_ARENA()
-				.get(TABS().get(CURRENT_TAB().unwrap()).unwrap().file_key)
-				.unwrap()
-				.clone();
-			save_file(current_tab_content).await;
-		}
-		Key::Character(c) if (c == "D" && modifiers.contains(Modifiers::SHIFT)) => {
+	let Some(chord) = chord_from_event(&e) else {
+		return;
+	};
+	let key_press = KEYMAP.write().press(chord.clone());
+	match key_press {
+		KeyPress::Command(id) => {
 			e.stop_propagation();
-			log::debug!("CTRL + SHIFT + D was Pressed.");
-			if let Some(tab_index) = CURRENT_TAB() {
-				delete_tab(tab_index).await
+			log::debug!("{chord} was pressed: {id}");
+			match Command::from_id(&id) {
+				Some(command) => command.run().await,
+				None => log::error!("No command named {id} for the key binding {chord}"),
 			}
 		}
-		Key::Character(c) if c == "w" => {
+		KeyPress::Pending => {
 			e.stop_propagation();
-			log::debug!("CTRL + W was Pressed.");
-			if let Some(tab_index) = CURRENT_TAB() {
-				close_tab(tab_index).await
-			}
+			log::debug!("{chord} was pressed, waiting for the next chord.");
 		}
-		Key::Character(c) if c == "t" => {
-			e.stop_propagation();
-			log::debug!("CTRL + T was Pressed.");
-			new_tab().await
+		KeyPress::Unbound => (),
+	}
+}
+
+/// Forgets the last key handled by the keymap once it is released.
+pub(crate) fn handle_global_key_release(_: KeyboardEvent) {
+	if KEYMAP.peek().has_handled_key() {
+		KEYMAP.write().release();
+	}
+}
+
+/// Returns false for the keys belonging to the keymap, to a binding being recorded or to the open themes
+/// menu, which must not reach the editor.
+pub(crate) fn handle_editor_key_input(e: &KeyboardEvent) -> bool {
+	let themes_menu_key = *SHOW_THEMES_DROPUP.peek() && matches!(e.data.key, Key::ArrowUp | Key::ArrowDown | Key::Enter | Key::Escape);
+	!themes_menu_key && RECORDING_BINDING.peek().is_none() && chord_from_event(e).is_none_or(|chord| !KEYMAP.peek().is_reserved(&chord))
+}
+
+/// Returns the chord of a key event, `None` for the modifier keys.
+pub(crate) fn chord_from_event(e: &KeyboardEvent) -> Option<Chord> {
+	let mods = e.data.modifiers;
+	Chord::new(
+		&e.data.key.to_string(),
+		mods.contains(Modifiers::CONTROL),
+		mods.contains(Modifiers::ALT),
+		mods.contains(Modifiers::SHIFT),
+		mods.contains(Modifiers::META),
+	)
+}
+
+/// Handles the markdown aware editing keys: Enter and Tab in tables and lists, pasting delimited data,
+/// moving lines with Alt+Up and Alt+Down and typing over a selection. Returns true if the key was handled and must not reach the editor.
+pub(crate) fn handle_markdown_editing(editable: &mut UseEditable, e: &KeyboardEvent) -> bool {
+	let mods = e.data.modifiers;
+	let (cursor, selection) = {
+		let editor = editable.editor().read();
+		(editor.cursor_pos(), editor.get_selection())
+	};
+	let selection = selection.filter(|(start, end)| start != end);
+	// The text is only built for the keys that may edit it, not for plain typing.
+	let text = || editable.editor().read().to_string();
+
+	let edit = match &e.data.key {
+		Key::Enter if mods.is_empty() && selection.is_none() => {
+			let text = text();
+			add_row(&text, cursor).or_else(|| continue_list(&text, cursor))
 		}
-		Key::Character(c) if c == "p" => {
-			e.stop_propagation();
-			log::debug!("CTRL + P was Pressed.");
-			toggle_command_palette();
+		Key::Tab if mods.is_empty() || mods == Modifiers::SHIFT => {
+			let text = text();
+			let backwards = mods.contains(Modifiers::SHIFT);
+			move_to_cell(&text, cursor, backwards).or_else(|| indent_list_items(&text, cursor, selection, backwards))
 		}
-		Key::Tab => {
-			e.stop_propagation();
-			log::debug!("CTRL + Tab was Pressed.");
-			cycle_tab().await;
+		Key::Character(c) if c == "v" && mods == Modifiers::CONTROL => {
+			let range = selection.map_or(cursor..cursor, |(start, end)| start.min(end)..start.max(end));
+			CLIPBOARD().get().ok().and_then(|pasted| paste_as_table(&text(), range, &pasted))
+		}
+		Key::ArrowUp if mods == Modifiers::ALT => apply_line_operation(&text(), cursor, selection, LineOperation::MoveUp),
+		Key::ArrowDown if mods == Modifiers::ALT => apply_line_operation(&text(), cursor, selection, LineOperation::MoveDown),
+		Key::Character(typed) if !mods.contains(Modifiers::CONTROL) && !mods.contains(Modifiers::ALT) => {
+			selection.and_then(|selection| wrap_selection(&text(), selection, typed))
 		}
-		_ => (),
+		_ => None,
+	};
+
+	match edit {
+		Some(edit) => {
+			apply_text_edit(editable, edit);
+			true
+		}
+		None => false,
 	}
 }
 
-pub(crate) fn handle_editor_key_input(e: &KeyboardEvent) -> bool {
-	let mods = &e.data.modifiers;
-	let key = &e.data.key;
-
-	let is_ctrl_char = |ch: &str| mods.contains(Modifiers::CONTROL) && key == &Key::Character(ch.into());
-
-	let skip = is_ctrl_char("s")    // Save
-		|| is_ctrl_char("t")        // New tab
-		|| is_ctrl_char("p")        // Open command palette
-		|| is_ctrl_char("w")        // Close tab
-		|| (mods.contains(Modifiers::CONTROL)
-		&& mods.contains(Modifiers::SHIFT)
-		&& key == &Key::Character("D".into()))  // Delete
-		|| (mods.contains(Modifiers::CONTROL)
-		&& matches!(e.data.code, Code::Tab)); // Tab cycle
-
-	!skip
+/// Handles Ctrl+D, which selects the next occurrence of the selection with a new cursor, and the keys
+/// applied at every cursor while there are extra cursors. Returns true if the key was handled.
+pub(crate) fn handle_multi_cursor_key(editable: &mut UseEditable, e: &KeyboardEvent) -> bool {
+	let mods = e.data.modifiers;
+	let main = main_caret(&editable.editor().read());
+	let mut carets = EXTRA_CURSORS();
+	carets.push(main);
+
+	if mods == Modifiers::CONTROL && e.data.key == Key::Character("d".into()) {
+		let text = editable.editor().read().to_string();
+		if let Some(carets) = next_occurrence(&text, &carets) {
+			set_carets(editable, carets);
+		}
+		return true;
+	}
+	if carets.len() == 1 {
+		return false;
+	}
+
+	let extend = mods.contains(Modifiers::SHIFT);
+	let action = match &e.data.key {
+		Key::Escape => {
+			EXTRA_CURSORS.write().clear();
+			return true;
+		}
+		Key::Character(c) if c == "v" && mods == Modifiers::CONTROL => match CLIPBOARD().get() {
+			Ok(pasted) => CaretAction::Insert(pasted),
+			Err(_) => return true,
+		},
+		Key::Character(typed) if !mods.contains(Modifiers::CONTROL) && !mods.contains(Modifiers::ALT) => CaretAction::Insert(typed.clone()),
+		Key::Enter if mods.is_empty() => CaretAction::Insert("\n".to_string()),
+		Key::Tab if mods.is_empty() => CaretAction::Insert("\t".to_string()),
+		Key::Backspace => CaretAction::Backspace,
+		Key::Delete => CaretAction::Delete,
+		Key::ArrowLeft => CaretAction::Move(CaretMotion::Left, extend),
+		Key::ArrowRight => CaretAction::Move(CaretMotion::Right, extend),
+		Key::ArrowUp => CaretAction::Move(CaretMotion::Up, extend),
+		Key::ArrowDown => CaretAction::Move(CaretMotion::Down, extend),
+		Key::Home => CaretAction::Move(CaretMotion::LineStart, extend),
+		Key::End => CaretAction::Move(CaretMotion::LineEnd, extend),
+		// Any other key only applies to the main cursor.
+		_ => {
+			EXTRA_CURSORS.write().clear();
+			return false;
+		}
+	};
+
+	let text = editable.editor().read().to_string();
+	let (edit, carets) = apply_caret_action(&text, &carets, &action);
+	match edit {
+		Some(edit) => {
+			apply_text_edit(editable, edit);
+			*EXTRA_CURSORS.write() = carets[..carets.len() - 1].to_vec();
+		}
+		None => set_carets(editable, carets),
+	}
+	true
+}
+
+/// Passes the key through the Vim layer when Vim mode is on. Returns true if the key was handled, keys
+/// with Ctrl or Alt and the keys typed in insert mode go on to the other handlers.
+pub(crate) fn handle_vim_key(editable: &mut UseEditable, e: &KeyboardEvent) -> bool {
+	if !VIM_MODE() {
+		return false;
+	}
+	let mods = e.data.modifiers;
+	let key = match &e.data.key {
+		Key::Character(c) if c == "r" && mods == Modifiers::CONTROL => VimKey::Redo,
+		Key::Character(c) if c == "[" && mods == Modifiers::CONTROL => VimKey::Escape,
+		Key::Character(c) if !mods.contains(Modifiers::CONTROL) && !mods.contains(Modifiers::ALT) => match c.chars().next() {
+			Some(ch) => VimKey::Char(ch),
+			None => return false,
+		},
+		Key::Escape => VimKey::Escape,
+		Key::Enter if mods.is_empty() => VimKey::Enter,
+		Key::Backspace if mods.is_empty() => VimKey::Backspace,
+		Key::Tab if mods.is_empty() => VimKey::Tab,
+		_ => return false,
+	};
+	// The keys typed in insert mode go on to the editor without reading the text.
+	let typed_in_insert = VIM_STATE.peek().mode() == VimMode::Insert && key != VimKey::Escape;
+	let (text, cursor) = {
+		let editor = editable.editor().read();
+		let text = if typed_in_insert { String::new() } else { editor.to_string() };
+		(text, editor.cursor_pos())
+	};
+
+	// The register is the clipboard, read before the keys that can paste.
+	if VIM_STATE.peek().mode() != VimMode::Insert
+		&& matches!(key, VimKey::Char('p' | 'P' | '.'))
+		&& let Ok(pasted) = CLIPBOARD().get()
+	{
+		VIM_STATE.write().set_register(pasted);
+	}
+	let output = VIM_STATE.write().handle_key(key, &text, cursor);
+	if !output.handled {
+		return false;
+	}
+	if let Some(yanked) = output.yanked
+		&& let Err(err) = CLIPBOARD().set(yanked)
+	{
+		log::error!("Failed to copy the yanked text to the clipboard: {err:?}");
+	}
+	if let Some(edit) = output.edit {
+		apply_text_edit(editable, edit);
+	}
+
+	match output.command {
+		Some(VimCommand::Undo) => undo_edit(),
+		Some(VimCommand::Redo) => redo_edit(),
+		Some(command @ (VimCommand::Save | VimCommand::Close | VimCommand::SaveAndClose)) => {
+			spawn(async move {
+				if command != VimCommand::Close
+					&& let Some(file) = current_file()
+				{
+					save_file(file).await;
+				}
+				if command != VimCommand::Save
+					&& let Some(tab_index) = CURRENT_TAB()
+				{
+					close_tab(tab_index).await;
+				}
+			});
+		}
+		None => (),
+	}
+	true
+}
+
+/// Returns true for the keys starting an edit of their own in the undo history rather than continuing the
+/// typing: new lines, tabs, spaces and the keys with Ctrl or Alt.
+pub(crate) fn starts_own_edit(e: &KeyboardEvent) -> bool {
+	let mods = e.data.modifiers;
+	mods.contains(Modifiers::CONTROL)
+		|| mods.contains(Modifiers::ALT)
+		|| match &e.data.key {
+			Key::Enter | Key::Tab => true,
+			Key::Character(typed) => typed.chars().all(char::is_whitespace),
+			_ => false,
+		}
+}
+
+/// Returns the main cursor of the editor with its selection.
+pub(crate) fn main_caret(editor: &RopeEditor) -> Caret {
+	let cursor = editor.cursor_pos();
+	match editor.get_selection() {
+		Some((start, end)) if start != end => Caret {
+			anchor: if end == cursor { start } else { end },
+			head: cursor,
+		},
+		_ => Caret::at(cursor),
+	}
+}
+
+/// Makes the last caret the main cursor of the editor and the others extra cursors.
+pub(crate) fn set_carets(editable: &mut UseEditable, mut carets: Vec<Caret>) {
+	let Some(main) = carets.pop() else {
+		return;
+	};
+	{
+		let mut editor = editable.editor_mut().write();
+		editor.clear_selection();
+		editor.set_cursor_pos(main.head);
+		if !main.is_empty() {
+			editor.set_selection((main.anchor, main.head));
+		}
+	}
+	*EXTRA_CURSORS.write() = carets;
+}
+
+/// Renumbers the ordered lists after keys removing text, which can remove list items.
+pub(crate) fn renumber_lists_after_key(editable: &mut UseEditable, e: &KeyboardEvent) {
+	if !matches!(e.data.key, Key::Backspace | Key::Delete) {
+		return;
+	}
+	let (text, cursor) = {
+		let editor = editable.editor().read();
+		(editor.to_string(), editor.cursor_pos())
+	};
+	if let Some(edit) = renumber_lists(&text, cursor) {
+		apply_text_edit(editable, edit);
+	}
+}
+
+/// Re-aligns the table the cursor was in if the key moved the cursor out of its cell: a navigation key, or a
+/// key moving it to another line or cell. Typing in a cell leaves the table as it is.
+pub(crate) fn realign_table_after_key(editable: &mut UseEditable, e: &KeyboardEvent, previous_cursor: usize, previous_row: usize) {
+	let (text, cursor) = {
+		let editor = editable.editor().read();
+		let Some(line) = editor.line(previous_row) else {
+			return;
+		};
+		if !line.text.trim_start().starts_with('|') || editor.cursor_pos() == previous_cursor {
+			return;
+		}
+		let is_navigation = matches!(
+			e.data.key,
+			Key::ArrowUp | Key::ArrowDown | Key::ArrowLeft | Key::ArrowRight | Key::Home | Key::End | Key::PageUp | Key::PageDown
+		);
+		if !is_navigation && editor.cursor_row() == previous_row {
+			let previous_column = previous_cursor - editor.line_to_char(previous_row);
+			if cell_index(&line.text, editor.cursor_col()) == cell_index(&line.text, previous_column) {
+				return;
+			}
+		}
+		(editor.to_string(), editor.cursor_pos())
+	};
+	if let Some(edit) = realign_table(&text, previous_cursor, cursor) {
+		apply_text_edit(editable, edit);
+	}
+}
+
+/// Applies the given edit to the editor buffer, replacing any selection with the one in the edit.
+pub(crate) fn apply_text_edit(editable: &mut UseEditable, edit: TextEdit) {
+	let mut editor = editable.editor_mut().write();
+	if !edit.range.is_empty() {
+		editor.remove(edit.range.clone());
+	}
+	if !edit.text.is_empty() {
+		editor.insert(&edit.text, edit.range.start);
+	}
+	editor.clear_selection();
+	editor.set_cursor_pos(edit.cursor);
+	if let Some(selection) = edit.selection {
+		editor.set_selection(selection);
+	}
 }
@@ -0,0 +1,181 @@
+//! # Folding.
+//! Ranges of lines that can be collapsed in the editor: heading sections, list items with nested
+//! items and fenced code blocks.
+//!
+//! A fold is identified by the line it starts on, which stays visible in the editor while the rest of
+//! its range is hidden.
+
+use crate::data::outline::{Outline, is_fence};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+/// Returns every foldable range of the document, keyed by the line it starts on.
+pub fn foldable_ranges(lines: &[&str], outline: &Outline) -> BTreeMap<usize, Range<usize>> {
+	let mut ranges = BTreeMap::new();
+
+	// Heading sections, without the blank lines before the next heading.
+	for index in 0..outline.headings().len() {
+		let section = outline.section_range(index, lines.len());
+		let end = last_content_line(lines, section.clone()).map_or(section.start, |line| line + 1);
+		if end > section.start + 1 {
+			ranges.insert(section.start, section.start..end);
+		}
+	}
+
+	let mut code_block_start: Option<usize> = None;
+	for (index, line) in lines.iter().enumerate() {
+		if is_fence(line) {
+			match code_block_start.take() {
+				Some(start) => {
+					ranges.insert(start, start..index + 1);
+				}
+				None => code_block_start = Some(index),
+			}
+			continue;
+		}
+		if code_block_start.is_some() {
+			continue;
+		}
+		if let Some(range) = list_item_range(lines, index) {
+			ranges.insert(index, range);
+		}
+	}
+	// An unclosed code block runs until the end of the document.
+	if let Some(start) = code_block_start
+		&& start + 1 < lines.len()
+	{
+		ranges.insert(start, start..lines.len());
+	}
+
+	ranges
+}
+
+/// Returns the range of a list item and its nested lines, if the item has any.
+fn list_item_range(lines: &[&str], index: usize) -> Option<Range<usize>> {
+	let indent = list_item_indent(lines[index])?;
+	let nested = lines[index + 1..]
+		.iter()
+		.take_while(|line| line.trim().is_empty() || indentation(line) > indent)
+		.count();
+	let end = last_content_line(lines, index + 1..index + 1 + nested)? + 1;
+	Some(index..end)
+}
+
+/// Returns the indentation of a line if it is a bullet, numbered or task list item.
+fn list_item_indent(line: &str) -> Option<usize> {
+	let trimmed = line.trim_start();
+	let is_bullet = trimmed.starts_with(['-', '*', '+']) && trimmed[1..].starts_with(' ');
+	let is_numbered = trimmed.split_once(['.', ')']).is_some_and(|(number, rest)| {
+		!number.is_empty() && number.len() <= 9 && number.chars().all(|ch| ch.is_ascii_digit()) && rest.starts_with(' ')
+	});
+	(is_bullet || is_numbered).then(|| indentation(line))
+}
+
+/// Width of the leading whitespace of a line, tabs count as 4 spaces.
+fn indentation(line: &str) -> usize {
+	line.chars()
+		.take_while(|ch| ch.is_whitespace())
+		.map(|ch| if ch == '\t' { 4 } else { 1 })
+		.sum()
+}
+
+/// Returns the last line of the range that is not blank.
+fn last_content_line(lines: &[&str], range: Range<usize>) -> Option<usize> {
+	range.rev().find(|&line| !lines[line].trim().is_empty())
+}
+
+/// Returns the ranges folded at the given lines, lines where nothing can be folded are ignored.
+pub fn folded_ranges(foldable: &BTreeMap<usize, Range<usize>>, folded: &BTreeSet<usize>) -> Vec<Range<usize>> {
+	folded.iter().filter_map(|line| foldable.get(line).cloned()).collect()
+}
+
+/// Moves the folds of a document to the lines they start on after an edit. The lines before and after the
+/// edited ones are found by comparing the texts, the folds after the edit move with the lines added or
+/// removed, and a fold on an edited line is kept only on the first one, which is edited in place.
+pub fn shift_folds(folds: &BTreeSet<usize>, old: &str, new: &str) -> BTreeSet<usize> {
+	let old: Vec<&str> = old.split('\n').collect();
+	let new: Vec<&str> = new.split('\n').collect();
+	let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+	let max_suffix = old.len().min(new.len()) - prefix;
+	let suffix = old
+		.iter()
+		.rev()
+		.zip(new.iter().rev())
+		.take(max_suffix)
+		.take_while(|(a, b)| a == b)
+		.count();
+	let edited_end = old.len() - suffix;
+
+	folds
+		.iter()
+		.filter_map(|&line| match line {
+			line if line < prefix => Some(line),
+			line if line >= edited_end => Some(line + new.len() - old.len()),
+			line if line == prefix && new.len() - suffix > prefix => Some(line),
+			_ => None,
+		})
+		.collect()
+}
+
+/// Returns the lines of the document that stay visible once the given ranges are folded.
+pub fn visible_lines(total_lines: usize, folded: &[Range<usize>]) -> Vec<usize> {
+	let mut hidden = vec![false; total_lines];
+	for range in folded {
+		let end = range.end.min(total_lines);
+		if range.start + 1 < end {
+			hidden[range.start + 1..end].fill(true);
+		}
+	}
+	(0..total_lines).filter(|line| !hidden[*line]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn folds(lines: &[usize]) -> BTreeSet<usize> {
+		lines.iter().copied().collect()
+	}
+
+	#[test]
+	fn folds_move_with_the_lines_added_above() {
+		let old = "# A\ntext\n# B\ntext";
+		assert_eq!(shift_folds(&folds(&[0, 2]), old, "new\n# A\ntext\n# B\ntext"), folds(&[1, 3]));
+		assert_eq!(shift_folds(&folds(&[0, 2]), old, "# A\ntext\nmore\n# B\ntext"), folds(&[0, 3]));
+	}
+
+	#[test]
+	fn folds_move_with_the_lines_removed_above() {
+		let old = "# A\ntext\nmore\n# B\ntext";
+		assert_eq!(shift_folds(&folds(&[0, 3]), old, "# A\ntext\n# B\ntext"), folds(&[0, 2]));
+	}
+
+	#[test]
+	fn folds_stay_on_lines_edited_in_place() {
+		let old = "# A\ntext";
+		assert_eq!(shift_folds(&folds(&[0]), old, "# Ab\ntext"), folds(&[0]));
+		assert_eq!(shift_folds(&folds(&[0]), old, "\n# A\ntext"), folds(&[1]));
+	}
+
+	#[test]
+	fn folds_on_removed_lines_are_dropped() {
+		let old = "text\n# A\nbody\n# B\nbody";
+		assert_eq!(shift_folds(&folds(&[1, 3]), old, "text\n# B\nbody"), folds(&[1]));
+	}
+
+	#[test]
+	fn folded_ranges_hide_their_lines() {
+		let lines = ["# A", "text", "", "# B", "- item", "\t- nested", "```", "code", "```"];
+		let outline = {
+			let mut outline = Outline::default();
+			outline.update(&lines);
+			outline
+		};
+		let foldable = foldable_ranges(&lines, &outline);
+		assert_eq!(foldable.get(&0), Some(&(0..2)));
+		assert_eq!(foldable.get(&4), Some(&(4..6)));
+		assert_eq!(foldable.get(&6), Some(&(6..9)));
+		let folded = folded_ranges(&foldable, &folds(&[0, 6]));
+		assert_eq!(visible_lines(lines.len(), &folded), vec![0, 2, 3, 4, 5, 6]);
+	}
+}
@@ -0,0 +1,136 @@
+//! # Fonts.
+//! Reads the family name of TrueType and OpenType font files, so the fonts of the user are registered
+//! under the name they are known by.
+
+/// Returns the family name written in the `name` table of a TTF/OTF font, or of the first font of a
+/// collection.
+pub fn font_family_name(data: &[u8]) -> Option<String> {
+	let font_offset = if data.get(0..4)? == b"ttcf" {
+		read_u32(data, 12)? as usize
+	} else {
+		0
+	};
+	let table_count = read_u16(data, font_offset + 4)? as usize;
+	let name_table = (0..table_count)
+		.map(|index| font_offset + 12 + index * 16)
+		.find(|record| data.get(*record..record + 4) == Some(b"name"))
+		.and_then(|record| read_u32(data, record + 8))? as usize;
+
+	let count = read_u16(data, name_table + 2)? as usize;
+	let strings = name_table + read_u16(data, name_table + 4)? as usize;
+	let mut best: Option<(u8, String)> = None;
+	for index in 0..count {
+		let record = name_table + 6 + index * 12;
+		let platform = read_u16(data, record)?;
+		let language = read_u16(data, record + 4)?;
+		let name_id = read_u16(data, record + 6)?;
+		let length = read_u16(data, record + 8)? as usize;
+		let offset = strings + read_u16(data, record + 10)? as usize;
+		let Some(bytes) = data.get(offset..offset + length) else {
+			continue;
+		};
+		// The typographic family groups the styles that the legacy family splits, like "Inter" and
+		// "Inter Medium".
+		let id_rank = match name_id {
+			16 => 0,
+			1 => 2,
+			_ => continue,
+		};
+		let (rank, name) = match platform {
+			0 | 3 => (id_rank + u8::from(platform == 3 && language != 0x409), decode_utf16_be(bytes)?),
+			1 => (id_rank + 1, bytes.iter().map(|byte| char::from(*byte)).collect()),
+			_ => continue,
+		};
+		if !name.trim().is_empty() && best.as_ref().is_none_or(|(best_rank, _)| rank < *best_rank) {
+			best = Some((rank, name.trim().to_string()));
+		}
+	}
+	best.map(|(_, name)| name)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+	Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+	Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn decode_utf16_be(bytes: &[u8]) -> Option<String> {
+	let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+	String::from_utf16(&units).ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A font with a single `name` table holding the given (platform, language, name id, bytes) records.
+	fn font(names: &[(u16, u16, u16, Vec<u8>)]) -> Vec<u8> {
+		let name_table = 12 + 16;
+		let mut data = vec![0, 1, 0, 0];
+		data.extend(1u16.to_be_bytes());
+		data.extend([0; 6]);
+		data.extend(b"name");
+		data.extend([0; 4]);
+		data.extend((name_table as u32).to_be_bytes());
+		data.extend([0; 4]);
+
+		data.extend(0u16.to_be_bytes());
+		data.extend((names.len() as u16).to_be_bytes());
+		data.extend((6 + names.len() as u16 * 12).to_be_bytes());
+		let mut strings: Vec<u8> = Vec::new();
+		for (platform, language, name_id, bytes) in names {
+			for value in [*platform, 0, *language, *name_id, bytes.len() as u16, strings.len() as u16] {
+				data.extend(value.to_be_bytes());
+			}
+			strings.extend(bytes);
+		}
+		data.extend(strings);
+		data
+	}
+
+	fn utf16(text: &str) -> Vec<u8> {
+		text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+	}
+
+	#[test]
+	fn prefers_the_typographic_family() {
+		let data = font(&[(3, 0x409, 1, utf16("Inter Medium")), (3, 0x409, 16, utf16("Inter"))]);
+		assert_eq!(font_family_name(&data).as_deref(), Some("Inter"));
+	}
+
+	#[test]
+	fn prefers_english_windows_names() {
+		let data = font(&[
+			(3, 0x40c, 1, utf16("Police")),
+			(1, 0, 1, b"Mac Font".to_vec()),
+			(3, 0x409, 1, utf16("Font")),
+		]);
+		assert_eq!(font_family_name(&data).as_deref(), Some("Font"));
+		let data = font(&[(1, 0, 1, b"Mac Font".to_vec())]);
+		assert_eq!(font_family_name(&data).as_deref(), Some("Mac Font"));
+	}
+
+	#[test]
+	fn reads_the_first_font_of_a_collection() {
+		let mut data = b"ttcf".to_vec();
+		data.extend([0, 1, 0, 0, 0, 0, 0, 1]);
+		data.extend(16u32.to_be_bytes());
+		let mut font = font(&[(3, 0x409, 1, utf16("Collected"))]);
+		// Offsets in the font are from the start of the collection.
+		let name_table = u32::from_be_bytes(font[20..24].try_into().unwrap()) + 16;
+		font[20..24].copy_from_slice(&name_table.to_be_bytes());
+		data.extend(font);
+		assert_eq!(font_family_name(&data).as_deref(), Some("Collected"));
+	}
+
+	#[test]
+	fn rejects_invalid_fonts() {
+		assert_eq!(font_family_name(b""), None);
+		assert_eq!(font_family_name(&font(&[(3, 0x409, 2, utf16("Bold"))])), None);
+		let mut truncated = font(&[(3, 0x409, 1, utf16("Font"))]);
+		truncated.truncate(40);
+		assert_eq!(font_family_name(&truncated), None);
+	}
+}
@@ -0,0 +1,486 @@
+//! # Front Matter.
+//! Parsing and editing of the YAML front matter block at the start of a note.
+//!
+//! Only the flat subset of YAML used for note properties is understood, that is scalars and lists of
+//! scalars. Everything else in the block (comments, blank lines, nested maps) is kept verbatim, and
+//! editing a property only rewrites the lines of that property, so the rest of the block keeps its
+//! formatting.
+
+use crate::data::text_edit::TextEdit;
+use chrono::NaiveDate;
+use std::fmt::Display;
+use std::ops::Range;
+
+/// Typed value of a front matter property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+	Text(String),
+	List(Vec<String>),
+	Date(NaiveDate),
+	Bool(bool),
+	Number(f64),
+}
+
+impl PropertyValue {
+	/// Infers the type of a scalar YAML value, quoted values are always text.
+	pub fn parse(raw: &str) -> PropertyValue {
+		let raw = raw.trim();
+		if let Some(text) = unquote(raw) {
+			return PropertyValue::Text(text);
+		}
+		if raw.starts_with('[') && raw.ends_with(']') {
+			let items = raw[1..raw.len() - 1]
+				.split(',')
+				.map(|item| unquote(item.trim()).unwrap_or_else(|| item.trim().to_string()))
+				.filter(|item| !item.is_empty())
+				.collect();
+			return PropertyValue::List(items);
+		}
+		match raw {
+			"true" | "True" | "TRUE" => return PropertyValue::Bool(true),
+			"false" | "False" | "FALSE" => return PropertyValue::Bool(false),
+			_ => (),
+		}
+		if raw.len() == 10
+			&& let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+		{
+			return PropertyValue::Date(date);
+		}
+		if is_yaml_number(raw)
+			&& let Ok(number) = raw.parse::<f64>()
+		{
+			return PropertyValue::Number(number);
+		}
+		PropertyValue::Text(raw.to_string())
+	}
+
+	/// Parses text typed by the user into a value of the same type as this one, lists are comma separated.
+	/// Returns `None` if the text is not valid for the type.
+	pub fn parse_as(&self, text: &str) -> Option<PropertyValue> {
+		let text = text.trim();
+		match self {
+			PropertyValue::Text(_) => Some(PropertyValue::Text(text.to_string())),
+			PropertyValue::List(_) => Some(PropertyValue::List(
+				text.split(',')
+					.map(|item| item.trim().to_string())
+					.filter(|item| !item.is_empty())
+					.collect(),
+			)),
+			PropertyValue::Date(_) => NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().map(PropertyValue::Date),
+			PropertyValue::Bool(_) => match text {
+				"true" => Some(PropertyValue::Bool(true)),
+				"false" => Some(PropertyValue::Bool(false)),
+				_ => None,
+			},
+			PropertyValue::Number(_) => text.parse::<f64>().ok().filter(|_| is_yaml_number(text)).map(PropertyValue::Number),
+		}
+	}
+
+	/// Name of the type of the value shown to the user.
+	pub fn kind_name(&self) -> &'static str {
+		match self {
+			PropertyValue::Text(_) => "Text",
+			PropertyValue::List(_) => "List",
+			PropertyValue::Date(_) => "Date",
+			PropertyValue::Bool(_) => "Checkbox",
+			PropertyValue::Number(_) => "Number",
+		}
+	}
+
+	/// Renders a scalar value as YAML, lists are rendered in flow style.
+	fn to_yaml(&self) -> String {
+		match self {
+			PropertyValue::Text(text) => quote_if_needed(text),
+			PropertyValue::List(items) => {
+				let items: Vec<String> = items.iter().map(|item| quote_if_needed(item)).collect();
+				format!("[{}]", items.join(", "))
+			}
+			_ => self.to_string(),
+		}
+	}
+}
+
+/// Text shown in the properties editor, lists are comma separated.
+impl Display for PropertyValue {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			PropertyValue::Text(text) => write!(f, "{text}"),
+			PropertyValue::List(items) => write!(f, "{}", items.join(", ")),
+			PropertyValue::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+			PropertyValue::Bool(value) => write!(f, "{value}"),
+			PropertyValue::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => write!(f, "{}", *number as i64),
+			PropertyValue::Number(number) => write!(f, "{number}"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+	Property {
+		key: String,
+		value: PropertyValue,
+		lines: Vec<String>, // Lines of the property as written in the file
+	},
+	Raw(String),
+}
+
+/// The front matter block of a note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontMatter {
+	entries: Vec<Entry>,
+	closing: String,
+	range: Range<usize>, // Chars of the whole block in the document, delimiters included
+	byte_len: usize,
+}
+
+impl FrontMatter {
+	/// An empty front matter, inserted at the start of the document once a property is added.
+	pub fn empty() -> FrontMatter {
+		FrontMatter {
+			entries: Vec::new(),
+			closing: "---".to_string(),
+			range: 0..0,
+			byte_len: 0,
+		}
+	}
+
+	/// Returns every property of the block in the order they are written.
+	pub fn properties(&self) -> impl Iterator<Item = (&str, &PropertyValue)> {
+		self.entries.iter().filter_map(|entry| match entry {
+			Entry::Property { key, value, .. } => Some((key.as_str(), value)),
+			Entry::Raw(_) => None,
+		})
+	}
+
+	pub fn get(&self, key: &str) -> Option<&PropertyValue> {
+		self.properties().find(|(name, _)| *name == key).map(|(_, value)| value)
+	}
+
+	/// Sets the value of the property, adding it at the end of the block if it does not exist.
+	pub fn set(&mut self, key: &str, value: PropertyValue) {
+		let existing = self.entries.iter_mut().find_map(|entry| match entry {
+			Entry::Property {
+				key: name,
+				value: old_value,
+				lines,
+			} if name == key => Some((old_value, lines)),
+			_ => None,
+		});
+
+		match existing {
+			Some((old_value, lines)) => {
+				// Block lists stay block lists, with the indentation of their items.
+				let indent = lines.get(1).map(|line| &line[..line.len() - line.trim_start().len()]);
+				*lines = render_property(&lines[0], &value, indent);
+				*old_value = value;
+			}
+			None => {
+				let lines = render_property(&format!("{}:", quote_if_needed(key)), &value, None);
+				self.entries.push(Entry::Property {
+					key: key.to_string(),
+					value,
+					lines,
+				});
+			}
+		}
+	}
+
+	pub fn remove(&mut self, key: &str) {
+		self.entries
+			.retain(|entry| !matches!(entry, Entry::Property { key: name, .. } if name == key));
+	}
+
+	/// Renders the whole block, an empty front matter renders to nothing.
+	pub fn render(&self) -> String {
+		if self.entries.is_empty() {
+			return String::new();
+		}
+		let mut block = String::from("---\n");
+		for entry in &self.entries {
+			match entry {
+				Entry::Property { lines, .. } => lines.iter().for_each(|line| {
+					block.push_str(line);
+					block.push('\n');
+				}),
+				Entry::Raw(line) => {
+					block.push_str(line);
+					block.push('\n');
+				}
+			}
+		}
+		block.push_str(&self.closing);
+		block.push('\n');
+		block
+	}
+
+	/// Returns the note content following the front matter.
+	pub fn body<'a>(&self, content: &'a str) -> &'a str {
+		&content[self.byte_len.min(content.len())..]
+	}
+
+	/// Builds the edit replacing the block in the document with its current state, keeping the cursor
+	/// at the same place in the rest of the document.
+	pub fn text_edit(&self, cursor: usize) -> TextEdit {
+		let block = self.render();
+		let block_len = block.chars().count();
+		let cursor = if cursor >= self.range.end {
+			cursor - self.range.len() + block_len
+		} else {
+			cursor.min(self.range.start + block_len)
+		};
+		TextEdit::replace(self.range.clone(), block).with_cursor(cursor)
+	}
+}
+
+/// Parses the front matter at the start of the note, returns `None` if the note has none.
+pub fn parse_front_matter(content: &str) -> Option<FrontMatter> {
+	let mut lines = content.split_inclusive('\n');
+	let first = lines.next()?;
+	if first.trim_end() != "---" {
+		return None;
+	}
+
+	let mut char_len = first.chars().count();
+	let mut byte_len = first.len();
+	let mut entries: Vec<Entry> = Vec::new();
+	let mut closing = None;
+
+	for raw_line in lines {
+		char_len += raw_line.chars().count();
+		byte_len += raw_line.len();
+		let line = raw_line.trim_end_matches(['\n', '\r']);
+
+		if line == "---" || line == "..." {
+			closing = Some(line.to_string());
+			break;
+		}
+
+		let is_continuation = line.starts_with([' ', '\t']) || line.starts_with("- ") || line == "-";
+		if is_continuation
+			&& !line.trim().is_empty()
+			&& let Some(Entry::Property { lines, .. }) = entries.last_mut()
+		{
+			lines.push(line.to_string());
+			continue;
+		}
+
+		match split_key(line) {
+			Some((key, value)) if !is_continuation => entries.push(Entry::Property {
+				key,
+				value: PropertyValue::parse(value),
+				lines: vec![line.to_string()],
+			}),
+			_ => entries.push(Entry::Raw(line.to_string())),
+		}
+	}
+
+	let closing = closing?;
+
+	// Properties spanning many lines are either block lists, or values that are not understood
+	// (like nested maps) which are kept as they are.
+	let entries = entries
+		.into_iter()
+		.flat_map(|entry| match entry {
+			Entry::Property { key, lines, .. } if lines.len() > 1 => match parse_block_list(&lines[1..]) {
+				Some(items) => vec![Entry::Property {
+					key,
+					value: PropertyValue::List(items),
+					lines,
+				}],
+				None => lines.into_iter().map(Entry::Raw).collect(),
+			},
+			entry => vec![entry],
+		})
+		.collect();
+
+	Some(FrontMatter {
+		entries,
+		closing,
+		range: 0..char_len,
+		byte_len,
+	})
+}
+
+/// Splits a `key: value` line, the key has to start at the beginning of the line.
+fn split_key(line: &str) -> Option<(String, &str)> {
+	if line.starts_with('#') {
+		return None;
+	}
+	let (key, value) = line.split_once(':')?;
+	if !value.is_empty() && !value.starts_with(' ') {
+		return None;
+	}
+	let key = key.trim();
+	let key = unquote(key).unwrap_or_else(|| key.to_string());
+	(!key.is_empty()).then_some((key, value))
+}
+
+/// Parses the items of a block style list, returns `None` if any line is not a list item.
+fn parse_block_list(lines: &[String]) -> Option<Vec<String>> {
+	lines
+		.iter()
+		.map(|line| {
+			let item = line.trim_start().strip_prefix('-')?.trim();
+			Some(unquote(item).unwrap_or_else(|| item.to_string()))
+		})
+		.collect()
+}
+
+/// Renders the lines of a property keeping the original `key:` part of its first line. Lists are rendered
+/// in block style if the indentation of their items is given, and in flow style otherwise.
+fn render_property(first_line: &str, value: &PropertyValue, list_indent: Option<&str>) -> Vec<String> {
+	let prefix = match first_line.find(':') {
+		Some(index) => &first_line[..=index],
+		None => first_line,
+	};
+	match (value, list_indent) {
+		(PropertyValue::List(items), Some(indent)) if !items.is_empty() => std::iter::once(prefix.to_string())
+			.chain(items.iter().map(|item| format!("{indent}- {}", quote_if_needed(item))))
+			.collect(),
+		_ => vec![format!("{prefix} {}", value.to_yaml())],
+	}
+}
+
+/// Returns the text of a quoted YAML scalar, or `None` if the value is not quoted.
+fn unquote(raw: &str) -> Option<String> {
+	if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') {
+		let inner = &raw[1..raw.len() - 1];
+		let mut text = String::with_capacity(inner.len());
+		let mut chars = inner.chars();
+		while let Some(ch) = chars.next() {
+			if ch != '\\' {
+				text.push(ch);
+				continue;
+			}
+			match chars.next() {
+				Some('n') => text.push('\n'),
+				Some('t') => text.push('\t'),
+				Some(escaped) => text.push(escaped),
+				None => text.push('\\'),
+			}
+		}
+		return Some(text);
+	}
+	if raw.len() >= 2 && raw.starts_with('\'') && raw.ends_with('\'') {
+		return Some(raw[1..raw.len() - 1].replace("''", "'"));
+	}
+	None
+}
+
+/// Quotes the text if it would otherwise not be read back as the same text.
+fn quote_if_needed(text: &str) -> String {
+	let needs_quotes = text.is_empty()
+		|| text.trim() != text
+		|| text.contains(": ")
+		|| text.contains(" #")
+		|| text.contains(['\n', '"', ','])
+		|| text.starts_with(['[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '%', '@', '`', '-', '?', ':'])
+		|| PropertyValue::parse(text) != PropertyValue::Text(text.to_string());
+
+	if needs_quotes {
+		format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+	} else {
+		text.to_string()
+	}
+}
+
+/// Returns true for the plain decimal numbers that YAML reads as numbers.
+fn is_yaml_number(raw: &str) -> bool {
+	let digits = raw.strip_prefix(['-', '+']).unwrap_or(raw);
+	digits.starts_with(|ch: char| ch.is_ascii_digit())
+		&& digits
+			.chars()
+			.all(|ch| ch.is_ascii_digit() || matches!(ch, '.' | 'e' | 'E' | '-' | '+'))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const NOTE: &str =
+		"---\ntitle: Notes\n# A comment\ndate: 2025-03-14\ndraft: false\ntags:\n  - one\n  - \"two\"\nnested:\n  key: value\n---\nBody\n";
+
+	#[test]
+	fn infers_the_types_of_values() {
+		assert_eq!(PropertyValue::parse("plain text"), PropertyValue::Text("plain text".to_string()));
+		assert_eq!(PropertyValue::parse("\"true\""), PropertyValue::Text("true".to_string()));
+		assert_eq!(PropertyValue::parse("'it''s'"), PropertyValue::Text("it's".to_string()));
+		assert_eq!(PropertyValue::parse("True"), PropertyValue::Bool(true));
+		assert_eq!(PropertyValue::parse("-1.5e3"), PropertyValue::Number(-1500.0));
+		assert_eq!(PropertyValue::parse("1.2.3"), PropertyValue::Text("1.2.3".to_string()));
+		assert_eq!(
+			PropertyValue::parse("2025-03-14"),
+			PropertyValue::Date(NaiveDate::from_ymd_opt(2025, 3, 14).unwrap())
+		);
+		assert_eq!(
+			PropertyValue::parse("[a, \"b\", ]"),
+			PropertyValue::List(vec!["a".to_string(), "b".to_string()])
+		);
+	}
+
+	#[test]
+	fn parses_user_input_as_the_type_of_the_value() {
+		let number = PropertyValue::Number(1.0);
+		assert_eq!(number.parse_as(" 42 "), Some(PropertyValue::Number(42.0)));
+		assert_eq!(number.parse_as("inf"), None);
+		assert_eq!(PropertyValue::Bool(true).parse_as("yes"), None);
+		assert_eq!(PropertyValue::Date(NaiveDate::MIN).parse_as("2025-02-30"), None);
+		assert_eq!(
+			PropertyValue::List(vec![]).parse_as("a, , b"),
+			Some(PropertyValue::List(vec!["a".to_string(), "b".to_string()]))
+		);
+		assert_eq!(PropertyValue::Number(42.0).to_string(), "42");
+		assert_eq!(PropertyValue::Number(0.5).to_string(), "0.5");
+	}
+
+	#[test]
+	fn parses_the_block() {
+		let front_matter = parse_front_matter(NOTE).unwrap();
+		let properties: Vec<&str> = front_matter.properties().map(|(key, _)| key).collect();
+		assert_eq!(properties, vec!["title", "date", "draft", "tags"]);
+		assert_eq!(front_matter.get("draft"), Some(&PropertyValue::Bool(false)));
+		assert_eq!(
+			front_matter.get("tags"),
+			Some(&PropertyValue::List(vec!["one".to_string(), "two".to_string()]))
+		);
+		assert_eq!(front_matter.body(NOTE), "Body\n");
+		// Unchanged, the block renders as it was written.
+		assert_eq!(front_matter.render(), NOTE.strip_suffix("Body\n").unwrap());
+
+		assert_eq!(parse_front_matter("Body\n---\n"), None);
+		assert_eq!(parse_front_matter("---\ntitle: unclosed\n"), None);
+	}
+
+	#[test]
+	fn edits_only_the_changed_property() {
+		let mut front_matter = parse_front_matter(NOTE).unwrap();
+		front_matter.set("tags", PropertyValue::List(vec!["three".to_string()]));
+		front_matter.set("title", PropertyValue::Text("a: b".to_string()));
+		front_matter.set("new key", PropertyValue::Text("true".to_string()));
+		front_matter.remove("draft");
+		assert_eq!(
+			front_matter.render(),
+			"---\ntitle: \"a: b\"\n# A comment\ndate: 2025-03-14\ntags:\n  - three\nnested:\n  key: value\nnew key: \"true\"\n---\n"
+		);
+
+		let rendered = front_matter.render();
+		let reparsed = parse_front_matter(&rendered).unwrap();
+		assert_eq!(reparsed.get("title"), Some(&PropertyValue::Text("a: b".to_string())));
+		assert_eq!(reparsed.get("new key"), Some(&PropertyValue::Text("true".to_string())));
+	}
+
+	#[test]
+	fn edit_keeps_the_cursor_in_the_body() {
+		let mut front_matter = parse_front_matter("---\na: 1\n---\nBody").unwrap();
+		front_matter.set("a", PropertyValue::Number(100.0));
+		let edit = front_matter.text_edit(14);
+		assert_eq!(edit.range, 0..13);
+		assert_eq!(edit.text, "---\na: 100\n---\n");
+		assert_eq!(edit.cursor, 16);
+
+		let mut front_matter = FrontMatter::empty();
+		assert_eq!(front_matter.render(), "");
+		front_matter.set("a", PropertyValue::Bool(true));
+		assert_eq!(front_matter.text_edit(0).text, "---\na: true\n---\n");
+	}
+}
@@ -1,10 +1,22 @@
 use crate::data::{
+	commands::Command,
+	fonts::font_family_name,
+	keymap::Keymap,
+	settings::Settings,
+	spell_check::Dictionary,
 	stores::{
-		ACTIVE_DOCUMENT_TITLE, CLIPBOARD, CURRENT_TAB, FILES_ARENA, PLATFORM, RECENT_FILES, TABS, THEME_STORE, new_tab, push_tab,
-		switch_tab,
+		ACTIVE_DOCUMENT_TITLE, CLIPBOARD, CURRENT_TAB, DAILY_NOTES_CONFIG, FILES_ARENA, FOLDED_LINES, PERSIST_UNDO_HISTORY, PLATFORM,
+		RECENT_FILES, SETTINGS, TABS, TAG_INDEX, TASK_INDEX, THEME_STORE, UNDO_TREES, VIM_MODE, new_tab, push_tab, push_toast, switch_tab,
 	},
-	types::{APP_DATA_DIR, DEFAULT_TROVE_DIR, MarkdownFile, USER_DATA_FILE, UserData},
+	tags::TagIndex,
+	tasks::TaskIndex,
+	types::{
+		APP_DATA_DIR, DEFAULT_TROVE_DIR, DICTIONARY_FILE, FONTS_DIR, KEYBINDINGS_FILE, MarkdownFile, SETTINGS_FILE, SYSTEM_DICTIONARIES,
+		TEMPLATES_DIR, ToastAction, ToastLevel, UNDO_HISTORY_DIR, USER_DATA_FILE, UserData,
+	},
+	undo_tree::{SavedHistory, UndoTree, text_hash},
 };
+use chrono::Local;
 use freya::prelude::*;
 use log::LevelFilter;
 use log4rs::{
@@ -18,28 +30,52 @@ use log4rs::{
 	config::{Appender, Logger, Root},
 	encode::pattern::PatternEncoder,
 };
-use std::{fs, io::Write, path::PathBuf};
+use std::{
+	fs,
+	io::Write,
+	path::{Path, PathBuf},
+	process,
+};
 use tokio::{
 	fs::{File, rename},
 	io::AsyncWriteExt,
 	runtime::Runtime,
 };
 
-/// Initializes log4rs with custom configuration for stdout and file logging.
-pub fn logger_init() {
-	let log_file_path = {
-		let Some(state) = dirs::state_dir() else {
-			log::error!("No App State directory could be found/accessed!");
-			panic!("Failed to find App State directory.")
-		};
-		let log_dir = state.join(APP_DATA_DIR);
+/// Returns the path of the log file, in the state directory.
+pub fn get_log_path() -> PathBuf {
+	let Some(state) = dirs::state_dir() else {
+		log::error!("No App State directory could be found/accessed!");
+		panic!("Failed to find App State directory.")
+	};
+	let log_dir = state.join(APP_DATA_DIR);
 
-		if let Err(e) = fs::create_dir_all(&log_dir) {
-			log::error!("Failed to create log directory!: {e}");
-		};
+	if let Err(e) = fs::create_dir_all(&log_dir) {
+		log::error!("Failed to create log directory!: {e}");
+	};
 
-		log_dir.join("rhyolite.log")
+	log_dir.join("rhyolite.log")
+}
+
+/// Opens the log file with the default app of the system.
+pub fn open_log_file() {
+	let path = get_log_path();
+	let opener = if cfg!(target_os = "windows") {
+		"explorer"
+	} else if cfg!(target_os = "macos") {
+		"open"
+	} else {
+		"xdg-open"
 	};
+	if let Err(e) = process::Command::new(opener).arg(&path).spawn() {
+		log::error!("Could not open the log file {path:?} with {opener}: {e}");
+		push_toast(ToastLevel::Error, format!("Could not open the log file: {e}"), Vec::new());
+	}
+}
+
+/// Initializes log4rs with custom configuration for stdout and file logging.
+pub fn logger_init() {
+	let log_file_path = get_log_path();
 
 	// TODO: Add session based log files or rolling log files with archiving of old files, to prevent a single log file from growing too large.
 	let size_trigger = SizeTrigger::new(10 * 1024 * 1024); // 10 MB
@@ -71,8 +107,13 @@ pub fn logger_init() {
 	log4rs::init_config(config).unwrap();
 }
 
-/// Returns the path to the default trove directory.
+/// Returns the path to the default trove directory, the one of the settings if set.
 pub fn get_default_trove_dir() -> PathBuf {
+	if let Some(trove_path) = SETTINGS.peek().files.default_trove.clone()
+		&& trove_path.is_dir()
+	{
+		return trove_path;
+	}
 	// TODO: Handle path resolution/creation without panicking
 	let Some(documents_path) = dirs::document_dir() else {
 		log::error!("No document directory could be found/accessed!");
@@ -95,12 +136,205 @@ pub fn get_config_dir() -> PathBuf {
 	path
 }
 
+/// Returns the path to the templates folder of the default trove.
+pub fn get_templates_dir() -> PathBuf {
+	let templates_path = get_default_trove_dir().join(TEMPLATES_DIR);
+	if let Err(e) = fs::create_dir_all(&templates_path) {
+		log::error!("Could not create the templates folder {templates_path:?}: {e}");
+	}
+	templates_path
+}
+
 pub fn get_userdata_path() -> PathBuf {
 	let userdata_dir = get_config_dir();
 	fs::create_dir_all(&userdata_dir).expect("Could not create Rhyolite config directory");
 	userdata_dir.join(USER_DATA_FILE)
 }
 
+/// Returns the path of the key bindings file, in the config directory.
+pub fn get_keybindings_path() -> PathBuf {
+	get_config_dir().join(KEYBINDINGS_FILE)
+}
+
+/// Loads the key bindings, creating a file listing the default ones if there is none. Invalid and
+/// conflicting bindings are logged as warnings.
+pub fn load_keymap() -> Keymap {
+	let path = get_keybindings_path();
+	if !path.exists()
+		&& let Err(e) = fs::write(&path, Keymap::default_file())
+	{
+		log::error!("Could not create the key bindings file {path:?}: {e}");
+	}
+	let content = fs::read_to_string(&path).unwrap_or_else(|e| {
+		log::error!("Could not read the key bindings file {path:?}: {e}");
+		String::new()
+	});
+	let (keymap, warnings) = Keymap::load(&content, Command::is_id);
+	for warning in warnings {
+		log::warn!("{warning}");
+	}
+	keymap
+}
+
+/// Writes the bindings of the keymap that differ from the defaults to the key bindings file.
+pub fn save_keymap(keymap: &Keymap) {
+	let path = get_keybindings_path();
+	if let Err(e) = fs::write(&path, keymap.to_file()) {
+		log::error!("Could not save the key bindings file {path:?}: {e}");
+	}
+}
+
+/// Returns the path of the settings file, in the config directory.
+pub fn get_settings_path() -> PathBuf {
+	get_config_dir().join(SETTINGS_FILE)
+}
+
+/// Loads the settings, creating a file with the default ones if there is none. Invalid values are logged
+/// as warnings.
+pub fn load_settings() -> Settings {
+	let path = get_settings_path();
+	if !path.exists() {
+		save_settings(&Settings::default());
+	}
+	let content = fs::read_to_string(&path).unwrap_or_else(|e| {
+		log::error!("Could not read the settings file {path:?}: {e}");
+		String::new()
+	});
+	let (settings, warnings) = Settings::load(&content);
+	for warning in warnings {
+		log::warn!("{warning}");
+	}
+	settings
+}
+
+pub fn save_settings(settings: &Settings) {
+	let path = get_settings_path();
+	if let Err(e) = fs::write(&path, settings.to_file()) {
+		log::error!("Could not save the settings file {path:?}: {e}");
+	}
+}
+
+/// Reads the TTF/OTF fonts of the fonts folder of the config directory, along with their family names.
+pub fn load_user_fonts() -> Vec<(String, Vec<u8>)> {
+	let fonts_dir = get_config_dir().join(FONTS_DIR);
+	if let Err(e) = fs::create_dir_all(&fonts_dir) {
+		log::error!("Could not create the fonts folder {fonts_dir:?}: {e}");
+	}
+	let Ok(entries) = fs::read_dir(&fonts_dir) else {
+		return Vec::new();
+	};
+	let mut fonts = Vec::new();
+	for path in entries.flatten().map(|entry| entry.path()) {
+		let is_font = path
+			.extension()
+			.and_then(|extension| extension.to_str())
+			.is_some_and(|extension| matches!(extension.to_lowercase().as_str(), "ttf" | "otf" | "ttc"));
+		if !is_font {
+			continue;
+		}
+		match fs::read(&path) {
+			Ok(data) => {
+				let Some(family) = font_family_name(&data) else {
+					log::warn!("Could not read the family name of the font {path:?}, skipped it.");
+					continue;
+				};
+				log::info!("Loaded the font {family} from {path:?}");
+				fonts.push((family, data));
+			}
+			Err(e) => log::error!("Could not read the font {path:?}: {e}"),
+		}
+	}
+	fonts
+}
+
+/// Returns the path of the word list used by the spell check: the dictionary of the config directory, or
+/// else the word list of the system if it has one.
+pub fn get_dictionary_path() -> Option<PathBuf> {
+	std::iter::once(get_config_dir().join(DICTIONARY_FILE))
+		.chain(SYSTEM_DICTIONARIES.iter().map(PathBuf::from))
+		.find(|path| path.is_file())
+}
+
+/// Loads the word list used by the spell check, empty if there is none.
+pub fn load_dictionary() -> Dictionary {
+	let Some(path) = get_dictionary_path() else {
+		log::warn!(
+			"No dictionary was found, spell check is off. Add a word list at {:?} to use it.",
+			get_config_dir().join(DICTIONARY_FILE)
+		);
+		return Dictionary::default();
+	};
+	match fs::read_to_string(&path) {
+		Ok(content) => Dictionary::from_word_list(&content),
+		Err(e) => {
+			log::warn!("Could not read the dictionary {path:?}, spell check is off: {e}");
+			Dictionary::default()
+		}
+	}
+}
+
+/// Returns the path of the file storing the undo history of a note, in the app state directory. The
+/// file is named after a hash of the path of the note.
+pub fn get_undo_history_path(note_path: &Path) -> Option<PathBuf> {
+	let dir = dirs::state_dir()?.join(APP_DATA_DIR).join(UNDO_HISTORY_DIR);
+	Some(dir.join(format!("{}.toml", text_hash(&note_path.to_string_lossy()))))
+}
+
+/// Loads the undo history of a note, if it was saved for the given content of the note.
+pub fn load_undo_history(note_path: &Path, content: &str) -> Option<UndoTree> {
+	let history = fs::read_to_string(get_undo_history_path(note_path)?).ok()?;
+	match toml::from_str::<SavedHistory>(&history) {
+		Ok(saved) => {
+			let tree = UndoTree::from_saved(saved, content);
+			if tree.is_none() {
+				log::warn!("The undo history of {note_path:?} does not match the note, it was discarded.");
+			}
+			tree
+		}
+		Err(e) => {
+			log::error!("Failed to load the undo history of {note_path:?}: {e}");
+			None
+		}
+	}
+}
+
+/// Saves the undo history of a note, if the history is kept across restarts.
+pub fn save_undo_history(note_path: &Path) {
+	if !PERSIST_UNDO_HISTORY() {
+		return;
+	}
+	let Some(history_path) = get_undo_history_path(note_path) else {
+		return;
+	};
+	let Some(saved) = UNDO_TREES.read().get(note_path).map(UndoTree::to_saved) else {
+		return;
+	};
+	if let Some(dir) = history_path.parent()
+		&& let Err(e) = fs::create_dir_all(dir)
+	{
+		log::error!("Could not create the undo history directory {dir:?}: {e}");
+		return;
+	}
+	match toml::to_string(&saved) {
+		Ok(history) => {
+			if let Err(e) = fs::write(&history_path, history) {
+				log::error!("Unable to write the undo history of {note_path:?}: {e}");
+			}
+		}
+		Err(e) => log::error!("Failed to serialise the undo history of {note_path:?}: {e}"),
+	}
+}
+
+/// Removes the saved undo history of a note.
+pub fn delete_undo_history(note_path: &Path) {
+	if let Some(history_path) = get_undo_history_path(note_path)
+		&& history_path.exists()
+		&& let Err(e) = fs::remove_file(&history_path)
+	{
+		log::error!("Failed to remove the undo history of {note_path:?}: {e}");
+	}
+}
+
 /// Generate a path that is not conflicting by incrementing a counter at the file end
 pub fn generate_available_path(path: PathBuf) -> PathBuf {
 	if !path.exists() {
@@ -132,7 +366,7 @@ pub fn generate_available_path(path: PathBuf) -> PathBuf {
 }
 
 /// Opens the file from the given path.
-pub fn _open_file_from_path(path: PathBuf) -> Option<MarkdownFile> {
+pub fn open_file_from_path(path: PathBuf) -> Option<MarkdownFile> {
 	let markdown_file = fs::read_to_string(path.clone());
 
 	// TODO: Handle this gracefully
@@ -162,6 +396,11 @@ pub fn _open_file_from_path(path: PathBuf) -> Option<MarkdownFile> {
 
 /// Generates a new Markdown file from the given path (does not save it)
 pub fn new_file_from_path(path: PathBuf) -> Option<MarkdownFile> {
+	new_file_with_content(path, String::new())
+}
+
+/// Generates a new Markdown file with the given content from the given path (does not save it)
+pub fn new_file_with_content(path: PathBuf, content: String) -> Option<MarkdownFile> {
 	let cloned_path = path.clone();
 
 	let Some(file_name) = cloned_path.file_stem() else {
@@ -175,12 +414,69 @@ pub fn new_file_from_path(path: PathBuf) -> Option<MarkdownFile> {
 		editable: UseEditable::new_in_hook(
 			CLIPBOARD(),
 			PLATFORM(),
-			EditableConfig::new(String::new()).with_allow_tabs(true),
+			EditableConfig::new(content).with_allow_tabs(true),
 			EditableMode::SingleLineMultipleEditors,
 		),
 	})
 }
 
+/// Returns the paths of all the markdown files in the given directory and its subdirectories, skipping hidden ones.
+pub fn list_markdown_files(dir: &Path) -> Vec<PathBuf> {
+	let mut files = Vec::new();
+	let Ok(entries) = fs::read_dir(dir) else {
+		log::error!("Error reading directory: {dir:?}");
+		return files;
+	};
+
+	for entry in entries.flatten() {
+		let path = entry.path();
+		if path
+			.file_name()
+			.and_then(|name| name.to_str())
+			.is_some_and(|name| name.starts_with('.'))
+		{
+			continue;
+		}
+		if path.is_dir() {
+			files.extend(list_markdown_files(&path));
+		} else if path.extension().is_some_and(|extension| extension == "md") {
+			files.push(path);
+		}
+	}
+	files.sort();
+	files
+}
+
+/// Rebuilds the tag and task indexes from the markdown files in the default trove, using the buffers of
+/// open files instead of their contents on the disk.
+pub fn refresh_indexes() {
+	let mut tag_index = TagIndex::default();
+	let mut task_index = TaskIndex::default();
+	let templates_dir = get_templates_dir();
+
+	// Templates are not notes, their tags and tasks are not indexed.
+	for path in list_markdown_files(&get_default_trove_dir())
+		.into_iter()
+		.filter(|path| !path.starts_with(&templates_dir))
+	{
+		match fs::read_to_string(&path) {
+			Ok(content) => {
+				tag_index.update_file(&path, &content);
+				task_index.update_file(&path, &content);
+			}
+			Err(e) => log::error!("Error reading file {path:?}: {e}"),
+		}
+	}
+	for (_, markdownfile) in FILES_ARENA().iter() {
+		let content = markdownfile.editable.editor().to_string();
+		tag_index.update_file(&markdownfile.path, &content);
+		task_index.update_file(&markdownfile.path, &content);
+	}
+
+	*TAG_INDEX.write() = tag_index;
+	*TASK_INDEX.write() = task_index;
+}
+
 pub async fn save_userdata() {
 	let last_open_tab = CURRENT_TAB().unwrap_or_default();
 
@@ -188,17 +484,31 @@ pub async fn save_userdata() {
 		active_tabs: TABS(),
 		last_open_tab,
 		recent_files: RECENT_FILES(),
-		current_theme: THEME_STORE().current_theme.clone(),
+		// Not the theme being previewed, if the app closes during a preview.
+		current_theme: THEME_STORE().committed_theme().clone(),
+		folded_lines: FOLDED_LINES()
+			.into_iter()
+			.filter(|(path, folds)| !folds.is_empty() && path.is_file())
+			.collect(),
+		daily_notes: DAILY_NOTES_CONFIG(),
+		persist_undo_history: PERSIST_UNDO_HISTORY(),
+		vim_mode: VIM_MODE(),
 	};
 
-	if let Ok(toml_serialised_state) = toml::to_string::<UserData>(&current_editor_state)
-		&& let Ok(mut userdata_file) = fs::File::create(get_userdata_path())
-	{
-		// TODO: Handle Error for this operation and the parent operations.
-		let io_result = userdata_file.write(toml_serialised_state.as_bytes());
-		if io_result.is_err() {
-			log::error!("Unable to write userdata file: {}", io_result.unwrap_err());
-		}
+	let result = toml::to_string::<UserData>(&current_editor_state)
+		.map_err(|e| e.to_string())
+		.and_then(|toml_serialised_state| {
+			fs::File::create(get_userdata_path())
+				.and_then(|mut userdata_file| userdata_file.write_all(toml_serialised_state.as_bytes()))
+				.map_err(|e| e.to_string())
+		});
+	if let Err(e) = result {
+		log::error!("Unable to write userdata file: {e}");
+		push_toast(
+			ToastLevel::Error,
+			format!("Could not save the open tabs and the theme: {e}"),
+			vec![ToastAction::RetrySaveUserdata, ToastAction::OpenLog],
+		);
 	}
 }
 
@@ -275,12 +585,19 @@ pub fn load_default_trove() {
 pub fn load_from_userdata() {
 	let userdata_string = fs::read_to_string(get_userdata_path()).expect("Could not read user data file");
 
-	let Ok(userdata) = toml::from_str::<UserData>(userdata_string.as_str()) else {
-		log::error!("Failed to load the userdata, corrupted userdata file.");
-		// TODO: Handle Error
-		let _ = fs::remove_file(get_userdata_path());
-		log::warn!("Loading all files from the default trove.");
-		return load_default_trove();
+	let userdata = match toml::from_str::<UserData>(userdata_string.as_str()) {
+		Ok(userdata) => userdata,
+		Err(e) => {
+			log::error!("Failed to load the userdata, corrupted userdata file: {e}");
+			let _ = fs::remove_file(get_userdata_path());
+			log::warn!("Loading all files from the default trove.");
+			push_toast(
+				ToastLevel::Warning,
+				"The saved tabs could not be read, the notes of the default trove were opened instead.",
+				vec![ToastAction::OpenLog],
+			);
+			return load_default_trove();
+		}
 	};
 
 	let mut markdownfiles: Vec<MarkdownFile> = Vec::new();
@@ -336,6 +653,11 @@ pub fn load_from_userdata() {
 
 	let tokio = Runtime::new().unwrap();
 
+	*FOLDED_LINES.write() = userdata.folded_lines;
+	*DAILY_NOTES_CONFIG.write() = userdata.daily_notes;
+	*PERSIST_UNDO_HISTORY.write() = userdata.persist_undo_history;
+	*VIM_MODE.write() = userdata.vim_mode;
+
 	if markdownfiles.is_empty() {
 		tokio.block_on(new_tab());
 	} else {
@@ -345,28 +667,91 @@ pub fn load_from_userdata() {
 			tokio.block_on(push_tab(title, file_key));
 		}
 		*CURRENT_TAB.write() = Some(userdata.last_open_tab);
-		THEME_STORE.write().current_theme = userdata.current_theme;
+		THEME_STORE.write().restore_theme(userdata.current_theme);
 		*RECENT_FILES.write() = userdata.recent_files;
 		tokio.block_on(switch_tab(CURRENT_TAB().unwrap_or_default()));
 	}
 }
 
 pub async fn save_file(markdownfile: MarkdownFile) {
-	if let Ok(mut file) = File::create(markdownfile.path.clone()).await {
-		if let Ok(_result) = file.write_all(markdownfile.editable.editor().to_string().as_bytes()).await {
+	let content = markdownfile.editable.editor().to_string();
+	let result = match File::create(markdownfile.path.clone()).await {
+		Ok(mut file) => file.write_all(content.as_bytes()).await,
+		Err(e) => Err(e),
+	};
+	match result {
+		Ok(()) => {
+			TAG_INDEX.write().update_file(&markdownfile.path, &content);
+			TASK_INDEX.write().update_file(&markdownfile.path, &content);
+			if let Some(tree) = UNDO_TREES.write().get_mut(&markdownfile.path) {
+				tree.mark_saved(&content, Local::now().timestamp_millis());
+			}
+			save_undo_history(&markdownfile.path);
 			log::debug!("Successfully saved {} at {:#?}", markdownfile.title, markdownfile.path)
-		} else {
-			log::error!("Failed to save {} at {:#?}!", markdownfile.title, markdownfile.path)
+		}
+		Err(e) => {
+			log::error!("Failed to save {} at {:#?}: {e}", markdownfile.title, markdownfile.path);
+			push_toast(
+				ToastLevel::Error,
+				format!("Could not save {}: {e}", markdownfile.title),
+				vec![ToastAction::RetrySave(markdownfile.path), ToastAction::OpenLog],
+			);
 		}
 	}
 }
 
-pub async fn delete_file(markdownfile: MarkdownFile) {
-	if let Ok(_result) = tokio::fs::remove_file(markdownfile.path.clone()).await {
-		log::debug!("Successfully removed {}.", markdownfile.title)
-	} else {
-		log::error!("Failed to save the file!")
+/// Saves the file if it differs from its content on disk.
+pub async fn autosave_file(markdownfile: MarkdownFile) {
+	let content = markdownfile.editable.editor().to_string();
+	if tokio::fs::read_to_string(&markdownfile.path)
+		.await
+		.is_ok_and(|saved| saved == content)
+	{
+		return;
 	}
+	save_file(markdownfile).await;
+}
+
+/// Deletes the file of a note, returns whether it was deleted.
+pub async fn delete_file(markdownfile: MarkdownFile) -> bool {
+	match tokio::fs::remove_file(markdownfile.path.clone()).await {
+		Ok(()) => {
+			TAG_INDEX.write().remove_file(&markdownfile.path);
+			TASK_INDEX.write().remove_file(&markdownfile.path);
+			UNDO_TREES.write().remove(&markdownfile.path);
+			delete_undo_history(&markdownfile.path);
+			log::debug!("Successfully removed {}.", markdownfile.title);
+			true
+		}
+		Err(e) => {
+			log::error!("Failed to delete {} at {:#?}: {e}", markdownfile.title, markdownfile.path);
+			push_toast(
+				ToastLevel::Error,
+				format!("Could not delete {}: {e}", markdownfile.title),
+				vec![ToastAction::OpenLog],
+			);
+			false
+		}
+	}
+}
+
+/// Returns true if a file other than the one at `old_path` is at `new_path`. On case-insensitive file
+/// systems a path differing from the current one only by case finds the same file, which is only another
+/// file if the directory holds an entry with exactly the new name.
+fn is_other_file(old_path: &Path, new_path: &Path) -> bool {
+	if !new_path.exists() {
+		return false;
+	}
+	let (Some(old_name), Some(new_name)) = (old_path.file_name(), new_path.file_name()) else {
+		return true;
+	};
+	if !old_name.eq_ignore_ascii_case(new_name) {
+		return true;
+	}
+	new_path
+		.parent()
+		.and_then(|dir| fs::read_dir(dir).ok())
+		.is_none_or(|entries| entries.flatten().any(|entry| entry.file_name() == new_name))
 }
 
 pub async fn update_document_title(new_title: String) {
@@ -383,13 +768,43 @@ pub async fn update_document_title(new_title: String) {
 		let old_path = markdown_file.path.clone();
 		let new_path = old_path.with_file_name(format!("{}.md", new_title));
 
+		// Renaming onto another note would replace it.
+		if new_path != old_path && is_other_file(&old_path, &new_path) {
+			log::warn!("Did not rename {old_path:?}, {new_path:?} already exists.");
+			push_toast(
+				ToastLevel::Warning,
+				format!("A note named {new_title} already exists, the note was not renamed."),
+				Vec::new(),
+			);
+			return;
+		}
+
 		if let Err(e) = rename(&old_path, &new_path).await {
 			log::error!("Failed to rename file: {}", e);
+			push_toast(
+				ToastLevel::Error,
+				format!("Could not rename the note to {new_title}: {e}"),
+				vec![ToastAction::OpenLog],
+			);
 			return;
 		}
 
 		markdown_file.title = new_title.clone();
 		markdown_file.path = new_path.clone();
+		TAG_INDEX.write().rename_file(&old_path, &new_path);
+		TASK_INDEX.write().rename_file(&old_path, &new_path);
+		let mut folded_lines = FOLDED_LINES.write();
+		if let Some(folds) = folded_lines.remove(&old_path) {
+			folded_lines.insert(new_path.clone(), folds);
+		}
+		drop(folded_lines);
+		let mut undo_trees = UNDO_TREES.write();
+		if let Some(tree) = undo_trees.remove(&old_path) {
+			undo_trees.insert(new_path.clone(), tree);
+		}
+		drop(undo_trees);
+		delete_undo_history(&old_path);
+		save_undo_history(&new_path);
 
 		drop(tabs);
 
@@ -415,6 +830,8 @@ pub fn initialise_app() {
 		log::debug!("Loading last app state.");
 		load_from_userdata()
 	};
+
+	refresh_indexes();
 }
 
 // TODO: Mark saved files and only save the unsaved files.
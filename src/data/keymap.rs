@@ -0,0 +1,450 @@
+//! # Keymap.
+//! Key bindings of the app commands, loaded from `keybindings.toml` in the config directory.
+//!
+//! A chord is a key with its modifiers, written like `Ctrl+Shift+D`, and a binding is one chord or a
+//! sequence of chords separated by spaces, like `Ctrl+K Ctrl+S`. The file maps command names to their
+//! bindings and only lists the commands whose bindings differ from the defaults, an empty list unbinds a
+//! command. Keys bound here never reach the editor.
+
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+/// Bindings used for the commands missing from the bindings file.
+const DEFAULT_BINDINGS: [(&str, &str); 18] = [
+	("save_note", "Ctrl+S"),
+	("delete_note", "Ctrl+Shift+D"),
+	("close_tab", "Ctrl+W"),
+	("new_note", "Ctrl+T"),
+	("show_command_palette", "Ctrl+P"),
+	("next_tab", "Ctrl+Tab"),
+	("show_keyboard_shortcuts", "Ctrl+/"),
+	("zoom_in", "Ctrl+="),
+	("zoom_in", "Ctrl++"),
+	("zoom_out", "Ctrl+-"),
+	("reset_zoom", "Ctrl+0"),
+	("zoom_in_interface", "Ctrl+Alt+="),
+	("zoom_out_interface", "Ctrl+Alt+-"),
+	("reset_interface_zoom", "Ctrl+Alt+0"),
+	("toggle_outline", "Ctrl+K Ctrl+O"),
+	("toggle_history_panel", "Ctrl+K Ctrl+H"),
+	("toggle_vim_mode", "Ctrl+K Ctrl+V"),
+	("reload_keybindings", "Ctrl+K Ctrl+R"),
+];
+
+const FILE_HEADER: &str = "\
+# Rhyolite key bindings.
+# Maps command names to lists of bindings. A binding is a chord like \"Ctrl+Shift+D\", or chords separated
+# by spaces like \"Ctrl+K Ctrl+S\". Only the commands listed here change, an empty list unbinds a command.
+";
+
+/// A key with its modifiers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Chord {
+	pub ctrl: bool,
+	pub alt: bool,
+	pub shift: bool,
+	pub meta: bool,
+	pub key: String, // Normalised name of the key, lowercase for chars
+}
+
+impl Chord {
+	/// Builds a chord from the name of a key, as given by the key events. Returns `None` for the modifier
+	/// keys and unknown keys.
+	pub fn new(key: &str, ctrl: bool, alt: bool, shift: bool, meta: bool) -> Option<Chord> {
+		let key = normalize_key(key)?;
+		// Shift is part of a symbol typed with it, `Ctrl+?` and not `Ctrl+Shift+?`.
+		let is_symbol = key.chars().count() == 1 && !key.chars().all(char::is_alphabetic);
+		Some(Chord {
+			ctrl,
+			alt,
+			shift: shift && !is_symbol,
+			meta,
+			key,
+		})
+	}
+
+	/// Parses a chord like `Ctrl+Shift+D`, names are case insensitive.
+	pub fn parse(text: &str) -> Result<Chord, String> {
+		// `Ctrl++` binds the plus key.
+		let (modifiers, key) = match text.strip_suffix("++") {
+			Some(modifiers) => (modifiers, "+"),
+			None => text.rsplit_once('+').unwrap_or(("", text)),
+		};
+		let (mut ctrl, mut alt, mut shift, mut meta) = (false, false, false, false);
+		for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+			match modifier.trim().to_lowercase().as_str() {
+				"ctrl" | "control" => ctrl = true,
+				"alt" | "option" => alt = true,
+				"shift" => shift = true,
+				"super" | "meta" | "cmd" | "win" => meta = true,
+				_ => return Err(format!("unknown modifier \"{modifier}\"")),
+			}
+		}
+		Chord::new(key.trim(), ctrl, alt, shift, meta).ok_or_else(|| format!("unknown key \"{key}\""))
+	}
+}
+
+impl fmt::Display for Chord {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (held, name) in [
+			(self.ctrl, "Ctrl+"),
+			(self.alt, "Alt+"),
+			(self.shift, "Shift+"),
+			(self.meta, "Super+"),
+		] {
+			if held {
+				f.write_str(name)?;
+			}
+		}
+		if self.key.chars().count() == 1 {
+			f.write_str(&self.key.to_uppercase())
+		} else {
+			f.write_str(&self.key)
+		}
+	}
+}
+
+/// Returns the name of a key in chords, `None` for modifier and unknown keys.
+fn normalize_key(key: &str) -> Option<String> {
+	if key.chars().count() == 1 {
+		return Some(if key == " " { "Space".to_string() } else { key.to_lowercase() });
+	}
+	let name = match key.to_lowercase().as_str() {
+		"esc" | "escape" => "Escape",
+		"enter" | "return" => "Enter",
+		"tab" => "Tab",
+		"space" => "Space",
+		"backspace" => "Backspace",
+		"delete" | "del" => "Delete",
+		"insert" => "Insert",
+		"home" => "Home",
+		"end" => "End",
+		"pageup" => "PageUp",
+		"pagedown" => "PageDown",
+		"up" | "arrowup" => "Up",
+		"down" | "arrowdown" => "Down",
+		"left" | "arrowleft" => "Left",
+		"right" | "arrowright" => "Right",
+		"plus" => "+",
+		"minus" => "-",
+		function if function.starts_with('f') && function[1..].parse::<u8>().is_ok_and(|number| (1..=24).contains(&number)) => {
+			return Some(function.to_uppercase());
+		}
+		_ => return None,
+	};
+	Some(name.to_string())
+}
+
+/// Parses a binding, chords separated by spaces.
+pub fn parse_sequence(text: &str) -> Result<Vec<Chord>, String> {
+	let chords = text.split_whitespace().map(Chord::parse).collect::<Result<Vec<Chord>, String>>()?;
+	if chords.is_empty() {
+		return Err("empty binding".to_string());
+	}
+	Ok(chords)
+}
+
+pub fn format_sequence(sequence: &[Chord]) -> String {
+	sequence.iter().map(Chord::to_string).collect::<Vec<String>>().join(" ")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+	pub command: String,
+	pub sequence: Vec<Chord>,
+}
+
+/// What a key press does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyPress {
+	Command(String), // The key completes the binding of the command
+	Pending,         // The key starts or continues a sequence
+	Unbound,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KeymapFile {
+	#[serde(default)]
+	bindings: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Keymap {
+	bindings: Vec<Binding>,                   // Bindings of the file first, they win over the defaults
+	overrides: BTreeMap<String, Vec<String>>, // Bindings of the file, by command
+	pending: Vec<Chord>,                      // Chords typed of an unfinished sequence
+	handled: Option<Chord>,                   // Last chord used by the keymap, until the key is released
+}
+
+impl Keymap {
+	/// Builds the keymap from the content of the bindings file. Returns it with warnings about the invalid
+	/// and the conflicting bindings.
+	pub fn load(content: &str, is_command: fn(&str) -> bool) -> (Keymap, Vec<String>) {
+		let mut warnings = Vec::new();
+		let overrides = match toml::from_str::<KeymapFile>(content) {
+			Ok(file) => file.bindings,
+			Err(e) => {
+				warnings.push(format!("Could not read the key bindings, using the defaults: {e}"));
+				BTreeMap::new()
+			}
+		};
+		let keymap = Keymap::build(overrides, is_command, &mut warnings);
+		(keymap, warnings)
+	}
+
+	fn build(overrides: BTreeMap<String, Vec<String>>, is_command: fn(&str) -> bool, warnings: &mut Vec<String>) -> Keymap {
+		let mut bindings = Vec::new();
+		for (command, sequences) in &overrides {
+			if !is_command(command) {
+				warnings.push(format!("Unknown command \"{command}\" in the key bindings"));
+				continue;
+			}
+			for text in sequences {
+				match parse_sequence(text) {
+					Ok(sequence) => bindings.push(Binding {
+						command: command.clone(),
+						sequence,
+					}),
+					Err(e) => warnings.push(format!("Invalid binding \"{text}\" for {command}: {e}")),
+				}
+			}
+		}
+		for (command, text) in DEFAULT_BINDINGS {
+			if !overrides.contains_key(command) {
+				bindings.push(Binding {
+					command: command.to_string(),
+					sequence: parse_sequence(text).expect("Default bindings are valid"),
+				});
+			}
+		}
+		warnings.extend(conflicts(&bindings));
+		Keymap {
+			bindings,
+			overrides,
+			..Keymap::default()
+		}
+	}
+
+	/// The content of a bindings file listing the default bindings as comments, one line per command.
+	pub fn default_file() -> String {
+		let mut commands: Vec<(&str, Vec<String>)> = Vec::new();
+		for (command, text) in DEFAULT_BINDINGS {
+			let text = format!("\"{text}\"");
+			match commands.iter_mut().find(|(name, _)| *name == command) {
+				Some((_, texts)) => texts.push(text),
+				None => commands.push((command, vec![text])),
+			}
+		}
+		let mut content = format!("{FILE_HEADER}\n[bindings]\n");
+		for (command, texts) in commands {
+			content.push_str(&format!("# {command} = [{}]\n", texts.join(", ")));
+		}
+		content
+	}
+
+	/// The content of the bindings file for the current bindings.
+	pub fn to_file(&self) -> String {
+		let file = KeymapFile {
+			bindings: self.overrides.clone(),
+		};
+		format!("{FILE_HEADER}\n{}", toml::to_string(&file).unwrap_or_default())
+	}
+
+	pub fn bindings(&self) -> &[Binding] {
+		&self.bindings
+	}
+
+	/// The bindings of a command, as written in the bindings file.
+	pub fn sequences_of(&self, command: &str) -> Vec<String> {
+		self.bindings
+			.iter()
+			.filter(|binding| binding.command == command)
+			.map(|binding| format_sequence(&binding.sequence))
+			.collect()
+	}
+
+	/// Replaces the bindings of a command. Returns the warnings about the new keymap.
+	pub fn rebind(&mut self, command: &str, sequences: Vec<String>, is_command: fn(&str) -> bool) -> Vec<String> {
+		let mut overrides = std::mem::take(&mut self.overrides);
+		overrides.insert(command.to_string(), sequences);
+		let mut warnings = Vec::new();
+		*self = Keymap::build(overrides, is_command, &mut warnings);
+		warnings
+	}
+
+	/// Handles a key press, following the sequence of the previous keys.
+	pub fn press(&mut self, chord: Chord) -> KeyPress {
+		let broken_sequence = !self.pending.is_empty();
+		let mut sequence = std::mem::take(&mut self.pending);
+		sequence.push(chord.clone());
+
+		if let Some(binding) = self.bindings.iter().find(|binding| binding.sequence == sequence) {
+			self.handled = Some(chord);
+			return KeyPress::Command(binding.command.clone());
+		}
+		if self.bindings.iter().any(|binding| binding.sequence.starts_with(&sequence)) {
+			self.pending = sequence;
+			self.handled = Some(chord);
+			return KeyPress::Pending;
+		}
+		// A key breaking a sequence is dropped.
+		self.handled = broken_sequence.then_some(chord);
+		KeyPress::Unbound
+	}
+
+	/// Forgets the last handled chord, once its key is released.
+	pub fn release(&mut self) {
+		self.handled = None;
+	}
+
+	pub fn has_handled_key(&self) -> bool {
+		self.handled.is_some()
+	}
+
+	/// Whether the chord belongs to the keymap and must not reach the editor.
+	pub fn is_reserved(&self, chord: &Chord) -> bool {
+		!self.pending.is_empty()
+			|| self.handled.as_ref() == Some(chord)
+			|| self.bindings.iter().any(|binding| binding.sequence.first() == Some(chord))
+	}
+
+	/// The chords typed of an unfinished sequence.
+	pub fn pending(&self) -> Option<String> {
+		(!self.pending.is_empty()).then(|| format_sequence(&self.pending))
+	}
+}
+
+/// Warns about bindings that are the same or that start another one, which can then never be typed.
+fn conflicts(bindings: &[Binding]) -> Vec<String> {
+	let mut warnings = Vec::new();
+	for (index, first) in bindings.iter().enumerate() {
+		for second in &bindings[index + 1..] {
+			if first.command == second.command {
+				continue;
+			}
+			let (a, b) = (format_sequence(&first.sequence), format_sequence(&second.sequence));
+			if first.sequence == second.sequence {
+				warnings.push(format!(
+					"\"{a}\" is bound to both {} and {}, only {} runs",
+					first.command, second.command, first.command
+				));
+			} else if second.sequence.starts_with(&first.sequence) {
+				warnings.push(format!("\"{a}\" of {} hides \"{b}\" of {}", first.command, second.command));
+			} else if first.sequence.starts_with(&second.sequence) {
+				warnings.push(format!("\"{b}\" of {} hides \"{a}\" of {}", second.command, first.command));
+			}
+		}
+	}
+	warnings
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn any_command(_: &str) -> bool {
+		true
+	}
+
+	fn chord(text: &str) -> Chord {
+		Chord::parse(text).expect("a valid chord")
+	}
+
+	#[test]
+	fn parses_chords() {
+		assert_eq!(chord("ctrl+shift+d").to_string(), "Ctrl+Shift+D");
+		assert_eq!(chord("Control+Alt+PageUp").to_string(), "Ctrl+Alt+PageUp");
+		assert_eq!(chord("Ctrl++").key, "+");
+		assert_eq!(chord("Ctrl+Plus"), chord("Ctrl++"));
+		assert_eq!(chord("F12").to_string(), "F12");
+		// Shift is part of the symbols typed with it.
+		assert_eq!(chord("Ctrl+Shift+?").to_string(), "Ctrl+?");
+	}
+
+	#[test]
+	fn rejects_invalid_chords() {
+		assert!(Chord::parse("Hyper+A").is_err());
+		assert!(Chord::parse("Ctrl+Shift").is_err());
+		assert!(Chord::parse("F25").is_err());
+		assert!(parse_sequence("  ").is_err());
+	}
+
+	#[test]
+	fn parses_sequences() {
+		let sequence = parse_sequence("Ctrl+K  Ctrl+S").expect("a valid sequence");
+		assert_eq!(sequence, vec![chord("Ctrl+K"), chord("Ctrl+S")]);
+		assert_eq!(format_sequence(&sequence), "Ctrl+K Ctrl+S");
+	}
+
+	#[test]
+	fn the_file_replaces_the_defaults_of_its_commands() {
+		let (keymap, warnings) = Keymap::load("[bindings]\nsave_note = [\"Ctrl+Alt+S\"]\nclose_tab = []\n", any_command);
+		assert!(warnings.is_empty(), "{warnings:?}");
+		assert_eq!(keymap.sequences_of("save_note"), vec!["Ctrl+Alt+S"]);
+		assert!(keymap.sequences_of("close_tab").is_empty());
+		assert_eq!(keymap.sequences_of("new_note"), vec!["Ctrl+T"]);
+	}
+
+	#[test]
+	fn warns_about_invalid_bindings_and_unknown_commands() {
+		let (keymap, warnings) = Keymap::load("[bindings]\nsave_note = [\"Ctrl+Nope\"]\nunknown = [\"Ctrl+U\"]\n", |command| {
+			command != "unknown"
+		});
+		assert_eq!(warnings.len(), 2, "{warnings:?}");
+		assert!(keymap.sequences_of("unknown").is_empty());
+		let (_, warnings) = Keymap::load("not toml", any_command);
+		assert_eq!(warnings.len(), 1);
+	}
+
+	#[test]
+	fn warns_about_conflicts() {
+		let (_, warnings) = Keymap::load("[bindings]\nnew_note = [\"Ctrl+S\"]\n", any_command);
+		assert_eq!(
+			warnings,
+			vec!["\"Ctrl+S\" is bound to both new_note and save_note, only new_note runs"]
+		);
+		let (_, warnings) = Keymap::load("[bindings]\nnew_note = [\"Ctrl+K\"]\n", any_command);
+		assert_eq!(warnings.len(), 4, "{warnings:?}");
+		assert!(warnings[0].starts_with("\"Ctrl+K\" of new_note hides"));
+	}
+
+	#[test]
+	fn follows_sequences() {
+		let (mut keymap, _) = Keymap::load("", any_command);
+		assert_eq!(keymap.press(chord("Ctrl+K")), KeyPress::Pending);
+		assert_eq!(keymap.pending().as_deref(), Some("Ctrl+K"));
+		assert_eq!(keymap.press(chord("Ctrl+O")), KeyPress::Command("toggle_outline".to_string()));
+		assert_eq!(keymap.pending(), None);
+		// A key breaking a sequence is dropped.
+		assert_eq!(keymap.press(chord("Ctrl+K")), KeyPress::Pending);
+		assert_eq!(keymap.press(chord("A")), KeyPress::Unbound);
+		assert!(keymap.has_handled_key());
+		keymap.release();
+		assert_eq!(keymap.press(chord("A")), KeyPress::Unbound);
+		assert!(!keymap.has_handled_key());
+	}
+
+	#[test]
+	fn the_default_file_lists_each_command_once() {
+		let content = Keymap::default_file();
+		let lines: Vec<&str> = content
+			.lines()
+			.filter(|line| line.starts_with("# ") && line.contains(" = ["))
+			.collect();
+		let commands: std::collections::BTreeSet<&str> = lines.iter().filter_map(|line| line[2..].split(' ').next()).collect();
+		assert_eq!(commands.len(), lines.len());
+		assert!(lines.contains(&"# save_note = [\"Ctrl+S\"]"));
+
+		// Uncommented, it gives the default bindings.
+		let uncommented: Vec<&str> = content
+			.lines()
+			.map(|line| if lines.contains(&line) { &line[2..] } else { line })
+			.collect();
+		let (keymap, warnings) = Keymap::load(&uncommented.join("\n"), any_command);
+		let (defaults, _) = Keymap::load("", any_command);
+		assert!(warnings.is_empty(), "{warnings:?}");
+		for (command, _) in DEFAULT_BINDINGS {
+			assert_eq!(keymap.sequences_of(command), defaults.sequences_of(command));
+		}
+	}
+}
@@ -0,0 +1,429 @@
+//! # Line Editing.
+//! Operations on the lines of the selection (or of the cursor): moving, duplicating, deleting, joining,
+//! sorting and commenting lines, changing the case of the selection and indenting lines.
+//!
+//! Every operation builds a single `TextEdit`, so that it is applied to the document in one step.
+
+use crate::data::{
+	list_editing::{INDENT, outdent_line},
+	text_edit::{TextEdit, line_starts},
+};
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineOperation {
+	MoveUp,
+	MoveDown,
+	Duplicate,
+	Delete,
+	Join,
+	Sort,
+	Unique,
+	UpperCase,
+	LowerCase,
+	TitleCase,
+	ToggleComment,
+	Indent,
+	Outdent,
+}
+
+const COMMENT_START: &str = "<!--";
+const COMMENT_END: &str = "-->";
+
+/// A position in the document as a line and a column (in chars).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+	row: usize,
+	column: usize,
+}
+
+impl Position {
+	fn shifted(self, rows: isize) -> Position {
+		Position {
+			row: (self.row as isize + rows).max(0) as usize,
+			..self
+		}
+	}
+}
+
+/// The cursor and the other end of the selection.
+#[derive(Debug, Clone, Copy)]
+struct Selection {
+	head: Position,
+	anchor: Position,
+}
+
+impl Selection {
+	/// Lines of the selection, a selection ending at the start of a line does not include it.
+	fn rows(&self) -> Range<usize> {
+		let (start, end) = (self.head.min(self.anchor), self.head.max(self.anchor));
+		let last = if end.row > start.row && end.column == 0 {
+			end.row - 1
+		} else {
+			end.row
+		};
+		start.row..last + 1
+	}
+
+	fn shift(&mut self, rows: isize) {
+		self.head = self.head.shifted(rows);
+		self.anchor = self.anchor.shifted(rows);
+	}
+
+	/// Selects the given lines entirely.
+	fn select_rows(&mut self, lines: &[String], rows: Range<usize>) {
+		self.anchor = Position {
+			row: rows.start,
+			column: 0,
+		};
+		self.head = Position {
+			row: rows.end - 1,
+			column: lines[rows.end - 1].chars().count(),
+		};
+	}
+
+	/// Moves the cursor and the selection with the text when chars are added or removed at a column of a line.
+	fn adjust(&mut self, row: usize, column: usize, delta: isize) {
+		for position in [&mut self.head, &mut self.anchor] {
+			if position.row == row && position.column >= column {
+				position.column = (position.column as isize + delta).max(column as isize) as usize;
+			}
+		}
+	}
+
+	/// Builds the edit from the original text to the given lines, with this selection.
+	fn into_edit(self, original: &str, lines: &[String]) -> Option<TextEdit> {
+		let text = lines.join("\n");
+		if text == original {
+			return None;
+		}
+		let starts = line_starts(&text);
+		let index = |position: Position| {
+			let row = position.row.min(lines.len() - 1);
+			starts[row] + position.column.min(lines[row].chars().count())
+		};
+		let (head, anchor) = (index(self.head), index(self.anchor));
+		let edit = TextEdit::between(original, &text).with_cursor(head);
+		Some(if head == anchor { edit } else { edit.with_selection(anchor, head) })
+	}
+}
+
+// Positions are ordered by line, then by column.
+impl PartialOrd for Position {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Position {
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		(self.row, self.column).cmp(&(other.row, other.column))
+	}
+}
+
+/// Applies the operation to the lines of the selection (or of the cursor), returns `None` if it changes
+/// nothing.
+pub fn apply_line_operation(text: &str, cursor: usize, selection: Option<(usize, usize)>, operation: LineOperation) -> Option<TextEdit> {
+	let starts = line_starts(text);
+	let position = |index: usize| {
+		let row = starts.partition_point(|start| *start <= index).saturating_sub(1);
+		Position {
+			row,
+			column: index - starts[row],
+		}
+	};
+	// The cursor is the end of the selection it is on.
+	let anchor = match selection {
+		Some((start, end)) if start == cursor => end,
+		Some((start, end)) if end == cursor => start,
+		Some((start, _)) => start,
+		None => cursor,
+	};
+	let mut lines: Vec<String> = text.split('\n').map(String::from).collect();
+	let mut ends = Selection {
+		head: position(cursor),
+		anchor: position(anchor),
+	};
+	let rows = ends.rows();
+
+	match operation {
+		LineOperation::MoveUp => {
+			if rows.start == 0 {
+				return None;
+			}
+			let line = lines.remove(rows.start - 1);
+			lines.insert(rows.end - 1, line);
+			ends.shift(-1);
+		}
+		LineOperation::MoveDown => {
+			if rows.end >= lines.len() {
+				return None;
+			}
+			let line = lines.remove(rows.end);
+			lines.insert(rows.start, line);
+			ends.shift(1);
+		}
+		LineOperation::Duplicate => {
+			let copy = lines[rows.clone()].to_vec();
+			lines.splice(rows.end..rows.end, copy);
+			ends.shift(rows.len() as isize);
+		}
+		LineOperation::Delete => {
+			lines.drain(rows.clone());
+			if lines.is_empty() {
+				lines.push(String::new());
+			}
+			let row = rows.start.min(lines.len() - 1);
+			ends.head = Position {
+				row,
+				column: ends.head.column,
+			};
+			ends.anchor = ends.head;
+		}
+		LineOperation::Join => {
+			// A single line is joined with the next one.
+			let last = if rows.len() > 1 { rows.end - 1 } else { rows.start + 1 };
+			if last >= lines.len() {
+				return None;
+			}
+			let mut joined = lines[rows.start].trim_end().to_string();
+			let column = joined.chars().count();
+			for line in lines.drain(rows.start + 1..last + 1) {
+				let line = line.trim();
+				if !line.is_empty() {
+					joined.push(' ');
+					joined.push_str(line);
+				}
+			}
+			lines[rows.start] = joined;
+			ends.head = Position { row: rows.start, column };
+			ends.anchor = ends.head;
+		}
+		LineOperation::Sort | LineOperation::Unique => {
+			if rows.len() < 2 {
+				return None;
+			}
+			let mut block: Vec<String> = lines[rows.clone()].to_vec();
+			if operation == LineOperation::Sort {
+				block.sort();
+			} else {
+				let mut seen = std::collections::HashSet::new();
+				block.retain(|line| seen.insert(line.clone()));
+			}
+			let end = rows.start + block.len();
+			lines.splice(rows.clone(), block);
+			ends.select_rows(&lines, rows.start..end);
+		}
+		LineOperation::UpperCase | LineOperation::LowerCase | LineOperation::TitleCase => {
+			return change_case(text, cursor, selection, operation);
+		}
+		LineOperation::ToggleComment => {
+			let (first, last) = (rows.start, rows.end - 1);
+			let indent = lines[first].len() - lines[first].trim_start().len();
+			// On a single line, both markers must fit without overlapping, as they do not in `<!-->`.
+			let commented = lines[first].trim_start().starts_with(COMMENT_START)
+				&& lines[last].trim_end().ends_with(COMMENT_END)
+				&& (first != last || lines[first].trim().len() >= COMMENT_START.len() + COMMENT_END.len());
+
+			if commented {
+				let start_len = COMMENT_START.len() + usize::from(lines[first][indent + COMMENT_START.len()..].starts_with(' '));
+				lines[first].replace_range(indent..indent + start_len, "");
+				let column = lines[first][..indent].chars().count();
+
+				let trimmed_len = lines[last].trim_end().len();
+				let end_start = trimmed_len - COMMENT_END.len();
+				let end_start = if lines[last][..end_start].ends_with(' ') {
+					end_start - 1
+				} else {
+					end_start
+				};
+				let end_column = lines[last][..end_start].chars().count();
+				lines[last].replace_range(end_start..trimmed_len, "");
+
+				ends.adjust(first, column, -(start_len as isize));
+				ends.adjust(last, end_column, -((trimmed_len - end_start) as isize));
+			} else {
+				lines[first].insert_str(indent, &format!("{COMMENT_START} "));
+				let column = lines[first][..indent].chars().count();
+				lines[last].push_str(&format!(" {COMMENT_END}"));
+				ends.adjust(first, column, COMMENT_START.len() as isize + 1);
+			}
+		}
+		LineOperation::Indent | LineOperation::Outdent => {
+			for row in rows {
+				let line = &lines[row];
+				let new_line = if operation == LineOperation::Outdent {
+					outdent_line(line)
+				} else if line.is_empty() {
+					continue;
+				} else {
+					format!("{INDENT}{line}")
+				};
+				let delta = new_line.chars().count() as isize - line.chars().count() as isize;
+				lines[row] = new_line;
+				ends.adjust(row, 0, delta);
+			}
+		}
+	}
+
+	ends.into_edit(text, &lines)
+}
+
+/// Changes the case of the selection, or of the word at the cursor, keeping it selected.
+fn change_case(text: &str, cursor: usize, selection: Option<(usize, usize)>, operation: LineOperation) -> Option<TextEdit> {
+	let chars: Vec<char> = text.chars().collect();
+	let range = match selection {
+		Some((start, end)) if start != end => start.min(end)..start.max(end),
+		_ => {
+			let is_word = |index: &usize| chars[*index].is_alphanumeric();
+			let start = (0..cursor).rev().take_while(is_word).last().unwrap_or(cursor);
+			let end = (cursor..chars.len()).take_while(is_word).last().map_or(cursor, |end| end + 1);
+			start..end
+		}
+	};
+	if range.is_empty() {
+		return None;
+	}
+
+	let selected: String = chars[range.clone()].iter().collect();
+	let changed = match operation {
+		LineOperation::UpperCase => selected.to_uppercase(),
+		LineOperation::LowerCase => selected.to_lowercase(),
+		_ => title_case(&selected),
+	};
+	if changed == selected {
+		return None;
+	}
+	let end = range.start + changed.chars().count();
+	Some(TextEdit::replace(range.clone(), changed).with_selection(range.start, end))
+}
+
+/// Capitalizes the first letter of every word and lowercases the others.
+fn title_case(text: &str) -> String {
+	let mut result = String::with_capacity(text.len());
+	let mut word_start = true;
+	for ch in text.chars() {
+		if word_start {
+			result.extend(ch.to_uppercase());
+		} else {
+			result.extend(ch.to_lowercase());
+		}
+		word_start = !ch.is_alphanumeric() && ch != '\'';
+	}
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn apply(text: &str, edit: TextEdit) -> String {
+		let mut chars: Vec<char> = text.chars().collect();
+		chars.splice(edit.range, edit.text.chars());
+		chars.into_iter().collect()
+	}
+
+	fn run(text: &str, cursor: usize, selection: Option<(usize, usize)>, operation: LineOperation) -> (String, TextEdit) {
+		let edit = apply_line_operation(text, cursor, selection, operation).expect("an edit");
+		(apply(text, edit.clone()), edit)
+	}
+
+	#[test]
+	fn moves_lines_with_the_cursor() {
+		let (text, edit) = run("one\ntwo\nthree", 5, None, LineOperation::MoveUp);
+		assert_eq!(text, "two\none\nthree");
+		assert_eq!(edit.cursor, 1);
+
+		let (text, edit) = run("one\ntwo\nthree", 1, Some((1, 5)), LineOperation::MoveDown);
+		assert_eq!(text, "three\none\ntwo");
+		assert_eq!((edit.cursor, edit.selection), (7, Some((11, 7))));
+
+		assert!(apply_line_operation("one\ntwo", 1, None, LineOperation::MoveUp).is_none());
+		assert!(apply_line_operation("one\ntwo", 5, None, LineOperation::MoveDown).is_none());
+	}
+
+	#[test]
+	fn duplicates_and_deletes_lines() {
+		let (text, edit) = run("one\ntwo", 1, None, LineOperation::Duplicate);
+		assert_eq!(text, "one\none\ntwo");
+		assert_eq!(edit.cursor, 5);
+
+		let (text, edit) = run("one\ntwo\nthree", 6, None, LineOperation::Delete);
+		assert_eq!(text, "one\nthree");
+		assert_eq!(edit.cursor, 6);
+
+		// A selection ending at the start of a line does not delete it.
+		let (text, _) = run("one\ntwo\nthree", 0, Some((0, 4)), LineOperation::Delete);
+		assert_eq!(text, "two\nthree");
+	}
+
+	#[test]
+	fn joins_lines() {
+		let (text, edit) = run("one  \n  two\nthree", 0, None, LineOperation::Join);
+		assert_eq!(text, "one two\nthree");
+		assert_eq!(edit.cursor, 3);
+
+		let (text, _) = run("one\n\ntwo\nthree", 0, Some((0, 8)), LineOperation::Join);
+		assert_eq!(text, "one two\nthree");
+
+		assert!(apply_line_operation("one", 0, None, LineOperation::Join).is_none());
+	}
+
+	#[test]
+	fn sorts_and_removes_duplicate_lines() {
+		let (text, edit) = run("c\na\nb\na", 0, Some((0, 7)), LineOperation::Sort);
+		assert_eq!(text, "a\na\nb\nc");
+		assert_eq!(edit.selection, Some((0, 7)));
+
+		let (text, edit) = run("c\na\nc\na", 0, Some((0, 7)), LineOperation::Unique);
+		assert_eq!(text, "c\na");
+		assert_eq!(edit.selection, Some((0, 3)));
+
+		assert!(apply_line_operation("b\na", 0, None, LineOperation::Sort).is_none());
+	}
+
+	#[test]
+	fn changes_the_case_of_the_selection_or_word() {
+		let (text, edit) = run("hello world", 7, None, LineOperation::UpperCase);
+		assert_eq!(text, "hello WORLD");
+		assert_eq!(edit.selection, Some((6, 11)));
+
+		let (text, _) = run("HELLO World", 0, Some((0, 11)), LineOperation::LowerCase);
+		assert_eq!(text, "hello world");
+
+		let (text, _) = run("don't STOP me", 0, Some((0, 13)), LineOperation::TitleCase);
+		assert_eq!(text, "Don't Stop Me");
+
+		assert!(apply_line_operation("HELLO", 2, None, LineOperation::UpperCase).is_none());
+	}
+
+	fn toggle_comment(text: &str) -> String {
+		let edit = apply_line_operation(text, 0, None, LineOperation::ToggleComment).expect("an edit");
+		apply(text, edit)
+	}
+
+	#[test]
+	fn comments_and_uncomments_a_line() {
+		assert_eq!(toggle_comment("  hello"), "  <!-- hello -->");
+		assert_eq!(toggle_comment("  <!-- hello -->"), "  hello");
+	}
+
+	#[test]
+	fn uncomments_an_empty_comment() {
+		assert_eq!(toggle_comment("<!-- -->"), "");
+		assert_eq!(toggle_comment("<!---->"), "");
+	}
+
+	#[test]
+	fn overlapping_markers_are_not_a_comment() {
+		assert_eq!(toggle_comment("<!-->"), "<!-- <!--> -->");
+		assert_eq!(toggle_comment("<!--->"), "<!-- <!---> -->");
+	}
+
+	#[test]
+	fn uncomments_a_block_of_lines() {
+		let text = "<!-- first\nsecond -->";
+		let edit = apply_line_operation(text, 0, Some((0, text.len())), LineOperation::ToggleComment).expect("an edit");
+		assert_eq!(apply(text, edit), "first\nsecond");
+	}
+}
@@ -0,0 +1,402 @@
+//! # List Editing.
+//! Markdown aware editing of lists and blockquotes.
+//!
+//! - Enter continues bullet, numbered and task list items and blockquotes, and ends the list when the
+//!   current item is empty.
+//! - Tab and Shift+Tab indent and outdent list items.
+//! - The ordered list these edits are in is renumbered after them.
+//! - Brackets, backticks and emphasis markers typed over a selection wrap it.
+
+use crate::data::{
+	outline::{in_code_block, is_fence},
+	text_edit::{TextEdit, line_starts},
+};
+use std::ops::Range;
+
+/// Indentation added to a list item by Tab.
+pub const INDENT: &str = "\t";
+
+/// Width of a tab when comparing the indentation of lines.
+const TAB_WIDTH: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Marker {
+	Bullet(char),
+	Ordered(u32, char),
+	Quote(String), // All the `>` markers of the line with their spaces
+}
+
+/// The prefix of a list item or blockquote line, before the content of the line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LinePrefix {
+	indent: String,
+	marker: Marker,
+	task: bool,
+	len: usize, // Byte length of the whole prefix
+}
+
+impl LinePrefix {
+	fn is_list_item(&self) -> bool {
+		!matches!(self.marker, Marker::Quote(_))
+	}
+
+	/// The prefix of the item following this one.
+	fn next(&self) -> String {
+		let marker = match &self.marker {
+			Marker::Bullet(bullet) => format!("{bullet} "),
+			Marker::Ordered(number, delimiter) => format!("{}{delimiter} ", number + 1),
+			Marker::Quote(quote) => quote.clone(),
+		};
+		let task = if self.task { "[ ] " } else { "" };
+		format!("{}{marker}{task}", self.indent)
+	}
+}
+
+fn parse_prefix(line: &str) -> Option<LinePrefix> {
+	let rest = line.trim_start_matches([' ', '\t']);
+	let indent = line[..line.len() - rest.len()].to_string();
+
+	if rest.starts_with('>') {
+		let quote_len = rest.len() - rest.trim_start_matches(['>', ' ']).len();
+		let quote = rest[..quote_len].to_string();
+		return Some(LinePrefix {
+			len: indent.len() + quote_len,
+			indent,
+			marker: Marker::Quote(quote),
+			task: false,
+		});
+	}
+
+	let (marker, marker_len) = if rest.starts_with(['-', '*', '+']) {
+		(Marker::Bullet(rest.chars().next()?), 1)
+	} else {
+		let digits = rest.chars().take_while(|ch| ch.is_ascii_digit()).count();
+		let delimiter = rest[digits..].chars().next().filter(|ch| matches!(ch, '.' | ')'))?;
+		if digits == 0 || digits > 9 {
+			return None;
+		}
+		(Marker::Ordered(rest[..digits].parse().ok()?, delimiter), digits + 1)
+	};
+	let after_marker = &rest[marker_len..];
+	if !after_marker.starts_with(' ') {
+		return None;
+	}
+	let content = after_marker.trim_start_matches(' ');
+	let mut len = indent.len() + marker_len + (after_marker.len() - content.len());
+
+	let task =
+		["[ ]", "[x]", "[X]"].iter().any(|checkbox| content.starts_with(checkbox)) && (content.len() == 3 || content[3..].starts_with(' '));
+	if task {
+		len += (content.len() - content[3..].trim_start_matches(' ').len()).min(content.len());
+	}
+
+	Some(LinePrefix { indent, marker, task, len })
+}
+
+/// Lines of the items content are indented, any other line but a blank one ends the lists.
+fn ends_lists(line: &str) -> bool {
+	is_fence(line)
+		|| !line.trim().is_empty() && !line.starts_with([' ', '\t']) && parse_prefix(line).is_none_or(|prefix| !prefix.is_list_item())
+}
+
+fn indent_width(indent: &str) -> usize {
+	indent.chars().map(|ch| if ch == '\t' { TAB_WIDTH } else { 1 }).sum()
+}
+
+/// A document split in lines, with the cursor as a line and a column (in chars).
+struct Lines {
+	lines: Vec<String>,
+	row: usize,
+	column: usize,
+}
+
+impl Lines {
+	fn new(text: &str, cursor: usize) -> Lines {
+		let starts = line_starts(text);
+		let row = starts.partition_point(|start| *start <= cursor).saturating_sub(1);
+		Lines {
+			lines: text.split('\n').map(String::from).collect(),
+			row,
+			column: cursor - starts[row],
+		}
+	}
+
+	/// Replaces a line, moving the cursor with the text if it is on this line.
+	fn set_line(&mut self, row: usize, line: String) {
+		if row == self.row {
+			let old_len = self.lines[row].chars().count() as isize;
+			let new_len = line.chars().count() as isize;
+			self.column = (self.column as isize + new_len - old_len).max(0) as usize;
+		}
+		self.lines[row] = line;
+	}
+
+	/// Renumbers the items of the ordered lists on the given lines, each list keeps the number of its first
+	/// item. The other lists of the document are left as they are written.
+	fn renumber(&mut self, rows: Range<usize>) {
+		if rows.clone().all(|row| ends_lists(&self.lines[row])) {
+			return;
+		}
+		let first = (0..rows.start)
+			.rev()
+			.find(|row| ends_lists(&self.lines[*row]))
+			.map_or(0, |row| row + 1);
+		let last = (rows.end..self.lines.len())
+			.find(|row| ends_lists(&self.lines[*row]))
+			.unwrap_or(self.lines.len());
+		let mut levels: Vec<(usize, Option<u32>)> = Vec::new(); // Indentation and next number of each list level
+		let mut in_code_block = false;
+		let mut in_paragraph = false;
+
+		for row in 0..last {
+			let line = &self.lines[row];
+			if is_fence(line) {
+				in_code_block = !in_code_block;
+			}
+			if in_code_block || line.trim().is_empty() {
+				in_paragraph = false;
+				continue;
+			}
+			// Only a list starting at 1 interrupts a paragraph, as in `2024. was a good year`.
+			let Some(prefix) = parse_prefix(line)
+				.filter(LinePrefix::is_list_item)
+				.filter(|prefix| !in_paragraph || !matches!(prefix.marker, Marker::Ordered(number, _) if number != 1))
+			else {
+				if ends_lists(line) {
+					levels.clear();
+					in_paragraph = !is_fence(line);
+				}
+				continue;
+			};
+			in_paragraph = false;
+
+			let width = indent_width(&prefix.indent);
+			while levels.last().is_some_and(|(indent, _)| *indent > width) {
+				levels.pop();
+			}
+			let Marker::Ordered(number, delimiter) = prefix.marker else {
+				match levels.last_mut() {
+					Some(level) if level.0 == width => level.1 = None,
+					_ => levels.push((width, None)),
+				}
+				continue;
+			};
+
+			let expected = match levels.last_mut() {
+				Some((indent, next)) if *indent == width => {
+					let expected = next.unwrap_or(number);
+					*next = Some(expected + 1);
+					expected
+				}
+				_ => {
+					levels.push((width, Some(number + 1)));
+					number
+				}
+			};
+			if expected != number && row >= first {
+				let marker_start = prefix.indent.len();
+				let marker_end = marker_start + line[marker_start..].find(delimiter).unwrap_or_default() + 1;
+				let renumbered = format!("{}{expected}{delimiter}{}", prefix.indent, &line[marker_end..]);
+				self.set_line(row, renumbered);
+			}
+		}
+	}
+
+	/// Builds the edit from the original text to the current lines.
+	fn into_edit(self, original: &str) -> Option<TextEdit> {
+		let text = self.lines.join("\n");
+		if text == original {
+			return None;
+		}
+		let cursor = line_starts(&text)[self.row] + self.column;
+		Some(TextEdit::between(original, &text).with_cursor(cursor))
+	}
+}
+
+/// Handles Enter on a list item or blockquote line, returns `None` to insert a plain newline, as in fenced
+/// code.
+pub fn continue_list(text: &str, cursor: usize) -> Option<TextEdit> {
+	if in_code_block(text, cursor) {
+		return None;
+	}
+	let mut lines = Lines::new(text, cursor);
+	let row = lines.row;
+	let line = lines.lines[row].clone();
+	let prefix = parse_prefix(&line)?;
+	let prefix_chars = line[..prefix.len].chars().count();
+	if lines.column < prefix_chars {
+		return None;
+	}
+
+	if line[prefix.len..].trim().is_empty() {
+		// An empty nested item is outdented, any other empty item ends the list.
+		if prefix.is_list_item() && !prefix.indent.is_empty() {
+			lines.set_line(row, outdent_line(&line));
+		} else {
+			lines.set_line(row, String::new());
+		}
+	} else {
+		let split = line.char_indices().nth(lines.column).map_or(line.len(), |(index, _)| index);
+		let next_line = prefix.next() + line[split..].trim_start();
+		lines.lines[row] = line[..split].to_string();
+		lines.lines.insert(row + 1, next_line);
+		lines.row = row + 1;
+		lines.column = prefix.next().chars().count();
+	}
+
+	lines.renumber(lines.row..lines.row + 1);
+	lines.into_edit(text)
+}
+
+/// Indents or outdents the list items on the lines of the selection (or of the cursor), returns `None`
+/// if none of these lines is a list item.
+pub fn indent_list_items(text: &str, cursor: usize, selection: Option<(usize, usize)>, outdent: bool) -> Option<TextEdit> {
+	let mut lines = Lines::new(text, cursor);
+	let rows: Range<usize> = match selection {
+		Some((start, end)) => {
+			let starts = line_starts(text);
+			let row_of = |index: usize| starts.partition_point(|start| *start <= index).saturating_sub(1);
+			row_of(start.min(end))..row_of(start.max(end)) + 1
+		}
+		None => lines.row..lines.row + 1,
+	};
+
+	let list_rows: Vec<usize> = rows
+		.filter(|row| parse_prefix(&lines.lines[*row]).is_some_and(|prefix| prefix.is_list_item()))
+		.collect();
+	if list_rows.is_empty() {
+		return None;
+	}
+	let edited = list_rows[0]..list_rows[list_rows.len() - 1] + 1;
+	for row in list_rows {
+		let line = &lines.lines[row];
+		let new_line = if outdent {
+			outdent_line(line)
+		} else {
+			// An item moved into a new nested list starts it at 1, renumbering fixes it if the list exists.
+			format!("{INDENT}{}", restart_numbering(line))
+		};
+		lines.set_line(row, new_line);
+	}
+
+	lines.renumber(edited);
+	// Nothing to outdent still counts as handled, so that Shift+Tab does not insert a tab.
+	Some(lines.into_edit(text).unwrap_or_else(|| TextEdit::replace(cursor..cursor, "")))
+}
+
+/// Sets the number of an ordered list item to 1.
+fn restart_numbering(line: &str) -> String {
+	let rest = line.trim_start_matches([' ', '\t']);
+	let digits = rest.chars().take_while(|ch| ch.is_ascii_digit()).count();
+	if digits == 0 {
+		return line.to_string();
+	}
+	let indent = &line[..line.len() - rest.len()];
+	format!("{indent}1{}", &rest[digits..])
+}
+
+/// Removes one level of indentation, a tab or up to four spaces.
+pub fn outdent_line(line: &str) -> String {
+	if let Some(rest) = line.strip_prefix('\t') {
+		return rest.to_string();
+	}
+	let spaces = line.chars().take(TAB_WIDTH).take_while(|ch| *ch == ' ').count();
+	line[spaces..].to_string()
+}
+
+/// Renumbers the ordered list the cursor is in, returns `None` if it is already numbered.
+pub fn renumber_lists(text: &str, cursor: usize) -> Option<TextEdit> {
+	let mut lines = Lines::new(text, cursor);
+	lines.renumber(lines.row..lines.row + 1);
+	lines.into_edit(text)
+}
+
+/// Wraps the selection with the pair of the typed character if it is a bracket, a backtick, a quote or
+/// an emphasis marker, keeping the wrapped text selected.
+pub fn wrap_selection(text: &str, selection: (usize, usize), typed: &str) -> Option<TextEdit> {
+	let (open, close) = match typed {
+		"(" => ("(", ")"),
+		"[" => ("[", "]"),
+		"{" => ("{", "}"),
+		"`" => ("`", "`"),
+		"\"" => ("\"", "\""),
+		"*" => ("*", "*"),
+		"_" => ("_", "_"),
+		"~" => ("~", "~"),
+		"=" => ("=", "="),
+		_ => return None,
+	};
+	let (start, end) = (selection.0.min(selection.1), selection.0.max(selection.1));
+	if start == end {
+		return None;
+	}
+	let selected: String = text.chars().skip(start).take(end - start).collect();
+	Some(
+		TextEdit::replace(start..end, format!("{open}{selected}{close}"))
+			.with_cursor(end + 1)
+			.with_selection(start + 1, end + 1),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn apply(text: &str, edit: Option<TextEdit>) -> String {
+		let Some(edit) = edit else {
+			return text.to_string();
+		};
+		let mut chars: Vec<char> = text.chars().collect();
+		chars.splice(edit.range, edit.text.chars());
+		chars.into_iter().collect()
+	}
+
+	#[test]
+	fn continues_list_items() {
+		assert_eq!(apply("- one", continue_list("- one", 5)), "- one\n- ");
+		assert_eq!(apply("1. one", continue_list("1. one", 6)), "1. one\n2. ");
+		assert_eq!(apply("- [x] done", continue_list("- [x] done", 10)), "- [x] done\n- [ ] ");
+		assert_eq!(apply("> quote", continue_list("> quote", 7)), "> quote\n> ");
+	}
+
+	#[test]
+	fn ends_the_list_on_an_empty_item() {
+		assert_eq!(apply("- one\n- ", continue_list("- one\n- ", 8)), "- one\n");
+		assert_eq!(apply("- one\n\t- ", continue_list("- one\n\t- ", 9)), "- one\n- ");
+	}
+
+	#[test]
+	fn does_not_continue_in_fenced_code() {
+		let text = "```\n- one\n```";
+		assert_eq!(continue_list(text, 9), None);
+		assert!(continue_list("```\n```\n- one", 13).is_some());
+	}
+
+	#[test]
+	fn renumbers_the_list_of_the_cursor() {
+		let text = "1. a\n3. b\n\npara\n\n1. c\n1. d";
+		assert_eq!(apply(text, renumber_lists(text, 0)), "1. a\n2. b\n\npara\n\n1. c\n1. d");
+		assert_eq!(apply(text, renumber_lists(text, text.len())), "1. a\n3. b\n\npara\n\n1. c\n2. d");
+		assert_eq!(renumber_lists(text, 12), None);
+	}
+
+	#[test]
+	fn renumbers_nested_lists_by_level() {
+		let text = "1. a\n\t5. x\n\t7. y\n5. b";
+		assert_eq!(apply(text, renumber_lists(text, 0)), "1. a\n\t5. x\n\t6. y\n2. b");
+	}
+
+	#[test]
+	fn does_not_renumber_in_fenced_code_or_paragraphs() {
+		let text = "1. a\n```\n1. code\n```\n1. b";
+		assert_eq!(renumber_lists(text, 0), None);
+		let text = "In\n2024. was\n1. a\n1. b";
+		assert_eq!(apply(text, renumber_lists(text, text.len())), "In\n2024. was\n1. a\n2. b");
+	}
+
+	#[test]
+	fn indents_items_into_a_nested_list() {
+		let text = "1. a\n2. b\n3. c";
+		assert_eq!(apply(text, indent_list_items(text, 6, None, false)), "1. a\n\t1. b\n2. c");
+	}
+}
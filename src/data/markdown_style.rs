@@ -0,0 +1,301 @@
+//! # Markdown style.
+//! Gives each char of an editor line the style of the markdown element or of the code scope it belongs
+//! to, the tokens of the theme giving the color of each style. Code blocks get a rough highlighting of
+//! keywords, strings, comments, numbers, functions and types that works for most languages.
+
+use crate::data::{
+	outline::{is_fence, parse_atx_heading},
+	spell_check::split_misspelled,
+};
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanStyle {
+	Text,
+	Heading(u8),
+	Emphasis,
+	Strong,
+	Link,
+	InlineCode,
+	Quote,
+	ListMarker,
+	CodeFence,
+	Code(SyntaxScope),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxScope {
+	Plain,
+	Keyword,
+	String,
+	Comment,
+	Number,
+	Function,
+	Type,
+	Punctuation,
+}
+
+/// Keywords of the common languages.
+const KEYWORDS: [&str; 62] = [
+	"as",
+	"async",
+	"await",
+	"break",
+	"case",
+	"catch",
+	"class",
+	"const",
+	"continue",
+	"def",
+	"default",
+	"defer",
+	"do",
+	"elif",
+	"else",
+	"enum",
+	"export",
+	"extends",
+	"false",
+	"fn",
+	"for",
+	"from",
+	"func",
+	"function",
+	"go",
+	"if",
+	"impl",
+	"import",
+	"in",
+	"interface",
+	"is",
+	"lambda",
+	"let",
+	"loop",
+	"match",
+	"mod",
+	"mut",
+	"new",
+	"nil",
+	"None",
+	"not",
+	"null",
+	"or",
+	"package",
+	"pass",
+	"pub",
+	"raise",
+	"return",
+	"self",
+	"static",
+	"struct",
+	"switch",
+	"this",
+	"throw",
+	"trait",
+	"true",
+	"try",
+	"type",
+	"use",
+	"var",
+	"where",
+	"while",
+];
+
+/// A run of chars of a line sharing a style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyledSpan {
+	pub text: String,
+	pub style: SpanStyle,
+	pub misspelled: bool,
+}
+
+/// The style of each char of a line, `in_code_block` telling whether the line belongs to a fenced code
+/// block, fences included.
+pub fn line_styles(line: &str, in_code_block: bool) -> Vec<SpanStyle> {
+	let chars: Vec<char> = line.chars().collect();
+	if in_code_block && is_fence(line) {
+		return vec![SpanStyle::CodeFence; chars.len()];
+	}
+	if in_code_block {
+		return code_styles(&chars);
+	}
+	if let Some(heading) = parse_atx_heading(line, 0) {
+		return vec![SpanStyle::Heading(heading.level); chars.len()];
+	}
+
+	let mut styles = vec![SpanStyle::Text; chars.len()];
+	let mut start = skip_spaces(&chars, 0);
+	if chars.get(start) == Some(&'>') {
+		styles.fill(SpanStyle::Quote);
+		while chars.get(start) == Some(&'>') {
+			start = skip_spaces(&chars, start + 1);
+		}
+	}
+	let marker_end = list_marker_end(&chars, start);
+	styles[start..marker_end].fill(SpanStyle::ListMarker);
+	inline_styles(&chars, marker_end, &mut styles);
+	styles
+}
+
+/// Splits a line into the spans sharing a style and a spelling, from the styles of its chars and the char
+/// ranges of its misspelled words.
+pub fn styled_spans(line: &str, styles: &[SpanStyle], misspelled: &[Range<usize>]) -> Vec<StyledSpan> {
+	let mut spans: Vec<StyledSpan> = Vec::new();
+	let mut index = 0;
+	for (text, misspelled) in split_misspelled(line, misspelled) {
+		for ch in text.chars() {
+			let style = styles.get(index).copied().unwrap_or(SpanStyle::Text);
+			match spans.last_mut() {
+				Some(span) if span.style == style && span.misspelled == misspelled => span.text.push(ch),
+				_ => spans.push(StyledSpan {
+					text: ch.to_string(),
+					style,
+					misspelled,
+				}),
+			}
+			index += 1;
+		}
+	}
+	spans
+}
+
+fn skip_spaces(chars: &[char], mut index: usize) -> usize {
+	while chars.get(index).is_some_and(|ch| *ch == ' ' || *ch == '\t') {
+		index += 1;
+	}
+	index
+}
+
+/// Returns where the list marker starting at the index ends, with its task box and the space after it,
+/// or the index itself when there is no marker.
+fn list_marker_end(chars: &[char], start: usize) -> usize {
+	let mut end = start;
+	if matches!(chars.get(start), Some('-' | '*' | '+')) {
+		end += 1;
+	} else {
+		while chars.get(end).is_some_and(|ch| ch.is_ascii_digit()) {
+			end += 1;
+		}
+		if end == start || end - start > 9 || !matches!(chars.get(end), Some('.' | ')')) {
+			return start;
+		}
+		end += 1;
+	}
+	if !matches!(chars.get(end), Some(' ') | None) {
+		return start;
+	}
+	end = skip_spaces(chars, end);
+	if chars.get(end) == Some(&'[')
+		&& matches!(chars.get(end + 1), Some(' ' | 'x' | 'X'))
+		&& chars.get(end + 2) == Some(&']')
+		&& matches!(chars.get(end + 3), Some(' ') | None)
+	{
+		end = skip_spaces(chars, end + 3);
+	}
+	end
+}
+
+/// Finds the chars of the closing delimiter, after the given index.
+fn find_closing(chars: &[char], from: usize, delimiter: &[char]) -> Option<usize> {
+	(from..=chars.len().saturating_sub(delimiter.len())).find(|index| chars[*index..].starts_with(delimiter))
+}
+
+/// Styles the inline code, links, strong and emphasized text from the index on.
+fn inline_styles(chars: &[char], from: usize, styles: &mut [SpanStyle]) {
+	let mut index = from;
+	while index < chars.len() {
+		let rest = &chars[index..];
+		let styled = if rest[0] == '`' {
+			let ticks = rest.iter().take_while(|ch| **ch == '`').count();
+			find_closing(chars, index + ticks, &rest[..ticks]).map(|end| (end + ticks, SpanStyle::InlineCode))
+		} else if rest.starts_with(&['[', '[']) {
+			find_closing(chars, index + 2, &[']', ']']).map(|end| (end + 2, SpanStyle::Link))
+		} else if rest[0] == '[' {
+			find_closing(chars, index + 1, &[']', '('])
+				.and_then(|end| find_closing(chars, end + 2, &[')']))
+				.map(|end| (end + 1, SpanStyle::Link))
+		} else if rest.starts_with(&['*', '*']) || rest.starts_with(&['_', '_']) {
+			find_closing(chars, index + 2, &rest[..2])
+				.filter(|end| *end > index + 2)
+				.map(|end| (end + 2, SpanStyle::Strong))
+		} else if (rest[0] == '*' || (rest[0] == '_' && !index.checked_sub(1).is_some_and(|before| chars[before].is_alphanumeric())))
+			&& rest.get(1).is_some_and(|next| !next.is_whitespace())
+		{
+			find_closing(chars, index + 1, &rest[..1]).map(|end| (end + 1, SpanStyle::Emphasis))
+		} else if rest.starts_with(&['h', 't', 't', 'p', ':', '/', '/']) || rest.starts_with(&['h', 't', 't', 'p', 's', ':', '/', '/']) {
+			let length = rest.iter().take_while(|ch| !ch.is_whitespace()).count();
+			Some((index + length, SpanStyle::Link))
+		} else {
+			None
+		};
+		match styled {
+			Some((end, style)) => {
+				styles[index..end].fill(style);
+				index = end;
+			}
+			None => index += 1,
+		}
+	}
+}
+
+/// Styles a line of code after the scope of its chars.
+fn code_styles(chars: &[char]) -> Vec<SpanStyle> {
+	let mut styles = vec![SpanStyle::Code(SyntaxScope::Plain); chars.len()];
+	let mut index = 0;
+	while index < chars.len() {
+		let ch = chars[index];
+		let rest = &chars[index..];
+		let (end, scope) = if rest.starts_with(&['/', '/']) || (ch == '#' && matches!(rest.get(1), Some(' ' | '!') | None)) {
+			(chars.len(), SyntaxScope::Comment)
+		} else if rest.starts_with(&['/', '*']) {
+			let end = find_closing(chars, index + 2, &['*', '/']).map_or(chars.len(), |end| end + 2);
+			(end, SyntaxScope::Comment)
+		} else if ch == '"' || ch == '\'' || ch == '`' {
+			match closing_quote(chars, index) {
+				Some(end) => (end + 1, SyntaxScope::String),
+				None => (index + 1, SyntaxScope::Punctuation),
+			}
+		} else if ch.is_ascii_digit() {
+			let length = rest
+				.iter()
+				.take_while(|ch| ch.is_alphanumeric() || **ch == '.' || **ch == '_')
+				.count();
+			(index + length, SyntaxScope::Number)
+		} else if ch.is_alphabetic() || ch == '_' {
+			let length = rest.iter().take_while(|ch| ch.is_alphanumeric() || **ch == '_').count();
+			let word: String = rest[..length].iter().collect();
+			let next = chars[index + length..].iter().find(|ch| !ch.is_whitespace());
+			let scope = if KEYWORDS.contains(&word.as_str()) {
+				SyntaxScope::Keyword
+			} else if matches!(next, Some('(' | '!')) {
+				SyntaxScope::Function
+			} else if ch.is_uppercase() {
+				SyntaxScope::Type
+			} else {
+				SyntaxScope::Plain
+			};
+			(index + length, scope)
+		} else if ch.is_whitespace() {
+			(index + 1, SyntaxScope::Plain)
+		} else {
+			(index + 1, SyntaxScope::Punctuation)
+		};
+		styles[index..end].fill(SpanStyle::Code(scope));
+		index = end;
+	}
+	styles
+}
+
+/// Index of the quote closing the string opened at the index, skipping the escaped ones.
+fn closing_quote(chars: &[char], open: usize) -> Option<usize> {
+	let quote = chars[open];
+	let mut index = open + 1;
+	while index < chars.len() {
+		match chars[index] {
+			'\\' => index += 2,
+			ch if ch == quote => return Some(index),
+			_ => index += 1,
+		}
+	}
+	None
+}
@@ -1,5 +1,27 @@
+pub mod commands;
+pub mod daily_notes;
 pub mod fn_utils;
+pub mod folding;
+pub mod fonts;
+pub mod frontmatter;
 pub mod io_utils;
+pub mod keymap;
+pub mod line_editing;
+pub mod list_editing;
+pub mod markdown_style;
+pub mod multi_cursor;
+pub mod outline;
+pub mod settings;
+pub mod spell_check;
 pub mod stores;
+pub mod tables;
+pub mod tags;
+pub mod tasks;
+pub mod templates;
+pub mod text_edit;
+pub mod theme_contrast;
+pub mod theme_import;
 pub mod themes;
 pub mod types;
+pub mod undo_tree;
+pub mod vim;
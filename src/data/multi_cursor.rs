@@ -0,0 +1,367 @@
+//! # Multiple Cursors.
+//! Extra cursors in the editor, added with Ctrl+D (next occurrence of the selection), Alt+click and
+//! Alt+drag (column selection).
+//!
+//! The main cursor stays the one of the `UseEditable` buffer, the extra ones are kept in
+//! `stores::EXTRA_CURSORS`. While there are extra cursors, typing, deleting, pasting and moving apply at
+//! every cursor, as a single `TextEdit`. The last caret of a list is always the main cursor.
+
+use crate::data::text_edit::{TextEdit, line_starts};
+use std::ops::Range;
+
+/// A cursor with its selection, as char indices into the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Caret {
+	pub anchor: usize, // Fixed end of the selection
+	pub head: usize,   // Position of the cursor
+}
+
+impl Caret {
+	pub fn at(index: usize) -> Caret {
+		Caret {
+			anchor: index,
+			head: index,
+		}
+	}
+
+	pub fn range(&self) -> Range<usize> {
+		self.anchor.min(self.head)..self.anchor.max(self.head)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.anchor == self.head
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaretMotion {
+	Left,
+	Right,
+	Up,
+	Down,
+	LineStart,
+	LineEnd,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaretAction {
+	Insert(String),
+	Backspace,
+	Delete,
+	Move(CaretMotion, bool), // Whether the selection is extended
+}
+
+/// Applies the action at every caret. Returns the edit of the document, if the action changes it, and the
+/// carets after the action.
+pub fn apply_caret_action(text: &str, carets: &[Caret], action: &CaretAction) -> (Option<TextEdit>, Vec<Caret>) {
+	let chars: Vec<char> = text.chars().collect();
+	let replacements: Vec<(Range<usize>, String)> = match action {
+		CaretAction::Move(motion, extend) => return (None, merge(move_carets(text, carets, *motion, *extend))),
+		CaretAction::Insert(inserted) => {
+			// Pasting as many lines as there are carets puts one line at each caret.
+			let lines: Vec<&str> = inserted.split('\n').collect();
+			let distribute = carets.len() > 1 && lines.len() == carets.len();
+			let mut order: Vec<usize> = (0..carets.len()).collect();
+			order.sort_by_key(|index| carets[*index].range().start);
+			let mut texts = vec![String::new(); carets.len()];
+			for (rank, index) in order.into_iter().enumerate() {
+				texts[index] = if distribute { lines[rank].to_string() } else { inserted.clone() };
+			}
+			carets.iter().map(|caret| caret.range()).zip(texts).collect()
+		}
+		CaretAction::Backspace | CaretAction::Delete => carets
+			.iter()
+			.map(|caret| {
+				let range = match caret.range() {
+					range if !range.is_empty() => range,
+					range if *action == CaretAction::Backspace => range.start.saturating_sub(1)..range.start,
+					range => range.start..(range.start + 1).min(chars.len()),
+				};
+				(range, String::new())
+			})
+			.collect(),
+	};
+
+	// The replacements are applied from the start of the document, positions after them shift.
+	let mut order: Vec<usize> = (0..carets.len()).collect();
+	order.sort_by_key(|index| replacements[*index].0.start);
+	let mut new_text = String::with_capacity(text.len());
+	let mut new_carets = vec![Caret::at(0); carets.len()];
+	let (mut copied, mut length) = (0, 0);
+	for index in order {
+		let (range, replacement) = &replacements[index];
+		let range = range.start.max(copied)..range.end.max(copied);
+		new_text.extend(&chars[copied..range.start]);
+		new_text.push_str(replacement);
+		length += range.start - copied + replacement.chars().count();
+		copied = range.end;
+		new_carets[index] = Caret::at(length);
+	}
+	new_text.extend(&chars[copied..]);
+
+	let carets = merge(new_carets);
+	let edit = if new_text == text {
+		None
+	} else {
+		main_caret_edit(TextEdit::between(text, &new_text), carets.last())
+	};
+	(edit, carets)
+}
+
+/// Sets the cursor and the selection of the edit to the main caret.
+fn main_caret_edit(edit: TextEdit, main: Option<&Caret>) -> Option<TextEdit> {
+	let main = main?;
+	let edit = edit.with_cursor(main.head);
+	Some(if main.is_empty() {
+		edit
+	} else {
+		edit.with_selection(main.anchor, main.head)
+	})
+}
+
+fn move_carets(text: &str, carets: &[Caret], motion: CaretMotion, extend: bool) -> Vec<Caret> {
+	let starts = line_starts(text);
+	let length = text.chars().count();
+	let row_of = |index: usize| starts.partition_point(|start| *start <= index).saturating_sub(1);
+	let line_end = |row: usize| starts.get(row + 1).map_or(length, |next| next - 1);
+
+	carets
+		.iter()
+		.map(|caret| {
+			let head = match motion {
+				// Moving without extending collapses a selection on its side.
+				CaretMotion::Left if !extend && !caret.is_empty() => caret.range().start,
+				CaretMotion::Right if !extend && !caret.is_empty() => caret.range().end,
+				CaretMotion::Left => caret.head.saturating_sub(1),
+				CaretMotion::Right => (caret.head + 1).min(length),
+				CaretMotion::LineStart => starts[row_of(caret.head)],
+				CaretMotion::LineEnd => line_end(row_of(caret.head)),
+				CaretMotion::Up | CaretMotion::Down => {
+					let row = row_of(caret.head);
+					let column = caret.head - starts[row];
+					let target = match motion {
+						CaretMotion::Up if row == 0 => return if extend { Caret { head: 0, ..*caret } } else { Caret::at(0) },
+						CaretMotion::Up => row - 1,
+						_ if row + 1 == starts.len() => {
+							return if extend {
+								Caret { head: length, ..*caret }
+							} else {
+								Caret::at(length)
+							};
+						}
+						_ => row + 1,
+					};
+					(starts[target] + column).min(line_end(target))
+				}
+			};
+			if extend { Caret { head, ..*caret } } else { Caret::at(head) }
+		})
+		.collect()
+}
+
+/// Removes the carets overlapping a later one, keeping the order of the others.
+fn merge(carets: Vec<Caret>) -> Vec<Caret> {
+	let mut merged: Vec<Caret> = Vec::with_capacity(carets.len());
+	for caret in carets.into_iter().rev() {
+		let overlaps = merged.iter().any(|other| {
+			let (a, b) = (caret.range(), other.range());
+			a == b || (a.start < b.end && b.start < a.end) || (a.is_empty() && b.contains(&a.start))
+		});
+		if !overlaps {
+			merged.push(caret);
+		}
+	}
+	merged.reverse();
+	merged
+}
+
+/// Selects the next occurrence of the main selection with a new caret, or the word at the main cursor if
+/// nothing is selected. Returns `None` if there is no other occurrence.
+pub fn next_occurrence(text: &str, carets: &[Caret]) -> Option<Vec<Caret>> {
+	let chars: Vec<char> = text.chars().collect();
+	let main = *carets.last()?;
+	let mut carets = carets.to_vec();
+
+	if main.is_empty() {
+		let is_word = |index: &usize| chars[*index].is_alphanumeric() || chars[*index] == '_';
+		let start = (0..main.head).rev().take_while(is_word).last().unwrap_or(main.head);
+		let end = (main.head..chars.len()).take_while(is_word).last().map_or(main.head, |end| end + 1);
+		if start == end {
+			return None;
+		}
+		*carets.last_mut()? = Caret { anchor: start, head: end };
+		return Some(carets);
+	}
+
+	let needle = &chars[main.range()];
+	let matches_at = |index: &usize| chars[*index..].starts_with(needle);
+	let taken = |index: &usize| carets.iter().any(|caret| caret.range().start == *index);
+	// Searching after the main caret, then from the start of the document.
+	let start = (main.range().end..chars.len())
+		.chain(0..main.range().start)
+		.find(|index| matches_at(index) && !taken(index))?;
+	carets.push(Caret {
+		anchor: start,
+		head: start + needle.len(),
+	});
+	Some(carets)
+}
+
+/// Builds the carets of a column selection between two positions, one per line, skipping the lines too
+/// short to reach the selected columns. The caret on the line of the head comes last.
+pub fn column_carets(text: &str, anchor: usize, head: usize) -> Vec<Caret> {
+	let starts = line_starts(text);
+	let length = text.chars().count();
+	let row_of = |index: usize| starts.partition_point(|start| *start <= index).saturating_sub(1);
+	let line_length = |row: usize| starts.get(row + 1).map_or(length, |next| next - 1) - starts[row];
+
+	let (anchor_row, head_row) = (row_of(anchor), row_of(head));
+	let (anchor_column, head_column) = (anchor - starts[anchor_row], head - starts[head_row]);
+	let rows: Vec<usize> = if anchor_row <= head_row {
+		(anchor_row..=head_row).collect()
+	} else {
+		(head_row..=anchor_row).rev().collect()
+	};
+
+	rows.into_iter()
+		.filter(|row| *row == head_row || line_length(*row) >= anchor_column.min(head_column))
+		.map(|row| Caret {
+			anchor: starts[row] + anchor_column.min(line_length(row)),
+			head: starts[row] + head_column.min(line_length(row)),
+		})
+		.collect()
+}
+
+/// Returns the columns to highlight on a line for the given carets, the part of their selection on the line.
+pub fn line_highlights(carets: &[Caret], line_start: usize, line_length: usize) -> Vec<(usize, usize)> {
+	let line_end = line_start + line_length;
+	carets
+		.iter()
+		.filter_map(|caret| {
+			let range = caret.range();
+			if range.start <= line_end && range.end > line_start {
+				let start = range.start.max(line_start) - line_start;
+				let end = range.end.min(line_end) - line_start;
+				(start < end).then_some((start, end))
+			} else {
+				None
+			}
+		})
+		.collect()
+}
+
+/// Returns the columns of the cursors of the given carets that are on a line, the end of the line included.
+pub fn line_caret_columns(carets: &[Caret], line_start: usize, line_length: usize) -> Vec<usize> {
+	carets
+		.iter()
+		.filter(|caret| (line_start..=line_start + line_length).contains(&caret.head))
+		.map(|caret| caret.head - line_start)
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn apply(text: &str, edit: TextEdit) -> String {
+		let mut chars: Vec<char> = text.chars().collect();
+		chars.splice(edit.range, edit.text.chars());
+		chars.into_iter().collect()
+	}
+
+	fn act(text: &str, carets: &[Caret], action: CaretAction) -> (String, Vec<Caret>) {
+		let (edit, carets) = apply_caret_action(text, carets, &action);
+		(edit.map_or(text.to_string(), |edit| apply(text, edit)), carets)
+	}
+
+	#[test]
+	fn types_at_every_caret() {
+		let (text, carets) = act("ab\ncd", &[Caret::at(1), Caret::at(4)], CaretAction::Insert("X".to_string()));
+		assert_eq!(text, "aXb\ncXd");
+		assert_eq!(carets, vec![Caret::at(2), Caret::at(6)]);
+
+		// The selections are replaced.
+		let (text, _) = act(
+			"ab ab",
+			&[Caret { anchor: 0, head: 2 }, Caret { anchor: 3, head: 5 }],
+			CaretAction::Insert("c".to_string()),
+		);
+		assert_eq!(text, "c c");
+	}
+
+	#[test]
+	fn pastes_one_line_at_each_caret() {
+		let (text, _) = act("ab\ncd", &[Caret::at(3), Caret::at(0)], CaretAction::Insert("1\n2".to_string()));
+		assert_eq!(text, "1ab\n2cd");
+		let (text, _) = act("ab", &[Caret::at(0), Caret::at(2)], CaretAction::Insert("1\n2\n3".to_string()));
+		assert_eq!(text, "1\n2\n3ab1\n2\n3");
+	}
+
+	#[test]
+	fn deletes_at_every_caret_and_merges_the_carets_that_meet() {
+		let (text, carets) = act("ab\ncd", &[Caret::at(0), Caret::at(3)], CaretAction::Backspace);
+		assert_eq!(text, "abcd");
+		assert_eq!(carets, vec![Caret::at(0), Caret::at(2)]);
+
+		let (text, carets) = act("abc", &[Caret::at(1), Caret::at(2)], CaretAction::Backspace);
+		assert_eq!(text, "c");
+		assert_eq!(carets, vec![Caret::at(0)]);
+
+		let (text, _) = act("abc", &[Caret::at(0), Caret::at(3)], CaretAction::Delete);
+		assert_eq!(text, "bc");
+	}
+
+	#[test]
+	fn moves_every_caret() {
+		let carets = [Caret::at(1), Caret { anchor: 3, head: 4 }];
+		let (_, moved) = apply_caret_action("ab\ncde", &carets, &CaretAction::Move(CaretMotion::Right, false));
+		assert_eq!(moved, vec![Caret::at(2), Caret::at(4)]);
+		// Selections meeting after the move are merged in the later one.
+		let (_, moved) = apply_caret_action("ab\ncde", &carets, &CaretAction::Move(CaretMotion::Down, true));
+		assert_eq!(moved, vec![Caret { anchor: 3, head: 6 }]);
+		let (_, moved) = apply_caret_action("ab\ncde", &carets, &CaretAction::Move(CaretMotion::LineEnd, false));
+		assert_eq!(moved, vec![Caret::at(2), Caret::at(6)]);
+	}
+
+	#[test]
+	fn selects_the_next_occurrences() {
+		let text = "foo bar foo";
+		let carets = next_occurrence(text, &[Caret::at(1)]).expect("the word at the cursor");
+		assert_eq!(carets, vec![Caret { anchor: 0, head: 3 }]);
+		let carets = next_occurrence(text, &carets).expect("the next occurrence");
+		assert_eq!(carets, vec![Caret { anchor: 0, head: 3 }, Caret { anchor: 8, head: 11 }]);
+		assert_eq!(next_occurrence(text, &carets), None);
+
+		// The search goes on from the start of the document.
+		let carets = next_occurrence(text, &[Caret { anchor: 8, head: 11 }]).expect("the first occurrence");
+		assert_eq!(carets[1], Caret { anchor: 0, head: 3 });
+		assert_eq!(next_occurrence("a  b", &[Caret::at(2)]), None);
+	}
+
+	#[test]
+	fn builds_column_selections() {
+		let text = "abcd\n\nabcd";
+		assert_eq!(
+			column_carets(text, 1, 9),
+			vec![Caret { anchor: 1, head: 3 }, Caret { anchor: 7, head: 9 }]
+		);
+		// Upwards, the caret on the line of the head still comes last.
+		assert_eq!(
+			column_carets(text, 9, 1),
+			vec![Caret { anchor: 9, head: 7 }, Caret { anchor: 3, head: 1 }]
+		);
+		// Lines too short to reach the columns are skipped.
+		assert_eq!(column_carets("abcd\nx\nabcd", 2, 9), vec![Caret::at(2), Caret::at(9)]);
+		assert_eq!(
+			column_carets("abcd\nx\nabcd", 2, 10),
+			vec![Caret { anchor: 2, head: 3 }, Caret { anchor: 9, head: 10 }]
+		);
+	}
+
+	#[test]
+	fn finds_the_selections_and_cursors_of_a_line() {
+		let carets = [Caret::at(2), Caret { anchor: 0, head: 5 }, Caret::at(7)];
+		assert_eq!(line_highlights(&carets, 3, 4), vec![(0, 2)]);
+		assert_eq!(line_caret_columns(&carets, 3, 4), vec![2, 4]);
+	}
+}
@@ -0,0 +1,321 @@
+//! # Document Outline.
+//! ATX (`# Title`) and Setext (`Title` underlined with `===` or `---`) headings of a note.
+//!
+//! The outline is updated incrementally: the state of every line is cached along with a hash of its
+//! text, so after an edit only the lines from the first changed one onwards are parsed again.
+
+use crate::data::text_edit::{TextEdit, line_starts};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+	pub level: u8,
+	pub title: String,
+	pub line: usize, // Line of the heading text, the underline of Setext headings is the next line
+}
+
+/// Parse state of a line, cached to restart parsing from any line.
+#[derive(Debug, Clone, PartialEq)]
+struct LineState {
+	hash: u64,
+	in_code_block: bool, // Whether the next line is inside a fenced code block
+	consumed: bool,      // The line belongs to the front matter or is the underline of a Setext heading
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outline {
+	headings: Vec<Heading>,
+	lines: Vec<LineState>,
+}
+
+impl Outline {
+	pub fn headings(&self) -> &[Heading] {
+		&self.headings
+	}
+
+	/// Updates the outline with the new lines of the document, only parsing the changed part.
+	pub fn update(&mut self, lines: &[&str]) {
+		let hashes: Vec<u64> = lines.iter().map(|line| hash_line(line)).collect();
+		let unchanged = hashes
+			.iter()
+			.zip(&self.lines)
+			.take_while(|(hash, state)| **hash == state.hash)
+			.count();
+		if unchanged == hashes.len() && hashes.len() == self.lines.len() {
+			return;
+		}
+
+		// A Setext heading depends on the line after it, so the line before the change is parsed again, and
+		// lines consumed by a previous line are parsed again from that line.
+		let mut restart = unchanged.saturating_sub(1);
+		while restart > 0 && self.lines[restart].consumed {
+			restart -= 1;
+		}
+		self.headings.retain(|heading| heading.line < restart);
+		self.lines.truncate(restart);
+
+		let mut in_code_block = restart > 0 && self.lines[restart - 1].in_code_block;
+		let mut index = restart;
+
+		if index == 0 && lines.first().is_some_and(|line| line.trim_end() == "---") {
+			let front_matter_end = lines.iter().skip(1).position(|line| matches!(line.trim_end(), "---" | "..."));
+			if let Some(end) = front_matter_end {
+				for hash in &hashes[..end + 2] {
+					self.push_line(*hash, false, true);
+				}
+				index = end + 2;
+			}
+		}
+
+		while index < lines.len() {
+			let line = lines[index];
+
+			if is_fence(line) {
+				in_code_block = !in_code_block;
+			} else if !in_code_block {
+				if let Some(heading) = parse_atx_heading(line, index) {
+					self.headings.push(heading);
+				} else if let Some(level) = lines.get(index + 1).and_then(|next| setext_level(next))
+					&& is_paragraph_line(line)
+				{
+					self.headings.push(Heading {
+						level,
+						title: line.trim().to_string(),
+						line: index,
+					});
+					self.push_line(hashes[index], false, false);
+					self.push_line(hashes[index + 1], false, true);
+					index += 2;
+					continue;
+				}
+			}
+
+			self.push_line(hashes[index], in_code_block, false);
+			index += 1;
+		}
+	}
+
+	/// Whether the line belongs to a fenced code block, fences included.
+	pub fn is_code_line(&self, line: usize) -> bool {
+		let code_follows = |line: usize| self.lines.get(line).is_some_and(|state| state.in_code_block);
+		code_follows(line) || line.checked_sub(1).is_some_and(code_follows)
+	}
+
+	fn push_line(&mut self, hash: u64, in_code_block: bool, consumed: bool) {
+		self.lines.push(LineState {
+			hash,
+			in_code_block,
+			consumed,
+		});
+	}
+
+	/// Returns the lines of the section of the heading at the given index, from the heading line up to the
+	/// next heading of the same or a higher level.
+	pub fn section_range(&self, index: usize, total_lines: usize) -> Range<usize> {
+		let heading = &self.headings[index];
+		let end = self.headings[index + 1..]
+			.iter()
+			.find(|next| next.level <= heading.level)
+			.map(|next| next.line)
+			.unwrap_or(total_lines);
+		heading.line..end
+	}
+
+	/// Returns the depth of each heading in the heading tree.
+	pub fn depths(&self) -> Vec<usize> {
+		let mut parents: Vec<u8> = Vec::new();
+		self.headings
+			.iter()
+			.map(|heading| {
+				while parents.last().is_some_and(|level| *level >= heading.level) {
+					parents.pop();
+				}
+				parents.push(heading.level);
+				parents.len() - 1
+			})
+			.collect()
+	}
+
+	/// Builds the edit moving the section of the heading at `from` right before the heading at `to`,
+	/// or to the end of the document if `to` is `None`.
+	pub fn move_section(&self, text: &str, from: usize, to: Option<usize>) -> Option<TextEdit> {
+		let lines: Vec<&str> = text.split('\n').collect();
+		// The empty line after the trailing newline of the document stays at the end.
+		let total_lines = lines.len() - usize::from(text.ends_with('\n'));
+		let section = self.section_range(from, total_lines);
+		let destination = match to {
+			Some(to) => self.headings.get(to)?.line,
+			None => total_lines,
+		};
+		if section.contains(&destination) || destination == section.end {
+			return None;
+		}
+
+		let span = section.start.min(destination)..section.end.max(destination);
+		let (moved, lines_before_section): (Vec<&str>, usize) = if destination < section.start {
+			let moved = lines[section.clone()].iter().chain(&lines[destination..section.start]);
+			(moved.copied().collect(), 0)
+		} else {
+			let moved = lines[section.end..destination].iter().chain(&lines[section.clone()]);
+			(moved.copied().collect(), destination - section.end)
+		};
+
+		let starts = line_starts(text);
+		let span_start = starts[span.start];
+		// The last line of the document has no trailing newline.
+		let (new_text, span_end) = if span.end < lines.len() {
+			(moved.join("\n") + "\n", starts[span.end])
+		} else {
+			(moved.join("\n"), text.chars().count())
+		};
+		let cursor = span_start
+			+ moved[..lines_before_section]
+				.iter()
+				.map(|line| line.chars().count() + 1)
+				.sum::<usize>();
+
+		Some(TextEdit::replace(span_start..span_end, new_text).with_cursor(cursor))
+	}
+}
+
+fn hash_line(line: &str) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	line.hash(&mut hasher);
+	hasher.finish()
+}
+
+/// Returns the line without its indentation if it is indented by at most 3 spaces.
+fn strip_indent(line: &str) -> Option<&str> {
+	let trimmed = line.trim_start_matches(' ');
+	(line.len() - trimmed.len() <= 3).then_some(trimmed)
+}
+
+pub fn is_fence(line: &str) -> bool {
+	strip_indent(line).is_some_and(|line| line.starts_with("```") || line.starts_with("~~~"))
+}
+
+/// Whether the char index is inside a fenced code block, the fences included.
+pub fn in_code_block(text: &str, index: usize) -> bool {
+	let row = text.chars().take(index).filter(|ch| *ch == '\n').count();
+	let mut lines = text.split('\n');
+	let inside = lines.by_ref().take(row).filter(|line| is_fence(line)).count() % 2 == 1;
+	inside || lines.next().is_some_and(is_fence)
+}
+
+/// Parses an ATX heading, `#` to `######` followed by a space and the title.
+pub fn parse_atx_heading(line: &str, line_index: usize) -> Option<Heading> {
+	let line = strip_indent(line)?;
+	let level = line.chars().take_while(|ch| *ch == '#').count();
+	if level == 0 || level > 6 {
+		return None;
+	}
+	let rest = &line[level..];
+	if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+		return None;
+	}
+	// Remove the optional closing sequence of `#`.
+	let mut title = rest.trim();
+	let without_closing = title.trim_end_matches('#');
+	if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+		title = without_closing.trim_end();
+	}
+	Some(Heading {
+		level: level as u8,
+		title: title.to_string(),
+		line: line_index,
+	})
+}
+
+/// Returns the heading level of a Setext underline, `=` gives level 1 and `-` gives level 2.
+fn setext_level(line: &str) -> Option<u8> {
+	let line = strip_indent(line)?.trim_end();
+	if !line.is_empty() && line.chars().all(|ch| ch == '=') {
+		Some(1)
+	} else if line.len() >= 2 && line.chars().all(|ch| ch == '-') {
+		Some(2)
+	} else {
+		None
+	}
+}
+
+/// A line that can be the text of a Setext heading, that is a plain paragraph line.
+fn is_paragraph_line(line: &str) -> bool {
+	let Some(trimmed) = strip_indent(line) else {
+		return false;
+	};
+	let is_list_item = trimmed.starts_with(['-', '*', '+']) && trimmed[1..].starts_with(' ')
+		|| trimmed
+			.split_once(['.', ')'])
+			.is_some_and(|(number, rest)| !number.is_empty() && number.chars().all(|ch| ch.is_ascii_digit()) && rest.starts_with(' '));
+	!trimmed.trim().is_empty() && !trimmed.starts_with('>') && !is_list_item && setext_level(trimmed).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn outline(text: &str) -> Outline {
+		let mut outline = Outline::default();
+		outline.update(&text.split('\n').collect::<Vec<_>>());
+		outline
+	}
+
+	fn titles(outline: &Outline) -> Vec<(u8, &str, usize)> {
+		outline
+			.headings()
+			.iter()
+			.map(|heading| (heading.level, heading.title.as_str(), heading.line))
+			.collect()
+	}
+
+	#[test]
+	fn parses_atx_headings() {
+		let heading = |line: &str| parse_atx_heading(line, 0).map(|heading| (heading.level, heading.title));
+		assert_eq!(heading("## Title ##"), Some((2, "Title".to_string())));
+		assert_eq!(heading("   # C# #"), Some((1, "C#".to_string())));
+		assert_eq!(heading("#"), Some((1, String::new())));
+		assert_eq!(heading("#hashtag"), None);
+		assert_eq!(heading("####### Seven"), None);
+		assert_eq!(heading("    # Indented code"), None);
+	}
+
+	#[test]
+	fn parses_setext_headings_and_skips_code_and_front_matter() {
+		let text = "---\ntitle: x\n---\nTitle\n=====\n```\n# Not a heading\n```\nSub\n---\n- item\n---\n## Last";
+		assert_eq!(titles(&outline(text)), vec![(1, "Title", 3), (2, "Sub", 8), (2, "Last", 12)]);
+	}
+
+	#[test]
+	fn updates_incrementally() {
+		let mut outline = outline("# One\ntext\n## Two");
+		outline.update(&["# One", "text", "===", "## Two"]);
+		assert_eq!(titles(&outline), vec![(1, "One", 0), (1, "text", 1), (2, "Two", 3)]);
+		outline.update(&["# One", "```", "text", "===", "## Two"]);
+		assert_eq!(titles(&outline), vec![(1, "One", 0)]);
+		assert!(outline.is_code_line(1) && outline.is_code_line(4) && !outline.is_code_line(0));
+	}
+
+	#[test]
+	fn builds_the_tree_of_sections() {
+		let outline = outline("# A\n## B\n### C\n## D\n# E");
+		assert_eq!(outline.depths(), vec![0, 1, 2, 1, 0]);
+		assert_eq!(outline.section_range(1, 5), 1..3);
+		assert_eq!(outline.section_range(0, 5), 0..4);
+		assert_eq!(outline.section_range(4, 5), 4..5);
+	}
+
+	#[test]
+	fn moves_sections() {
+		let text = "# A\na\n# B\nb\n# C\nc\n";
+		let outline = outline(text);
+		let apply = |edit: TextEdit| {
+			let mut chars: Vec<char> = text.chars().collect();
+			chars.splice(edit.range, edit.text.chars());
+			chars.into_iter().collect::<String>()
+		};
+		assert_eq!(apply(outline.move_section(text, 2, Some(0)).unwrap()), "# C\nc\n# A\na\n# B\nb\n");
+		assert_eq!(apply(outline.move_section(text, 0, None).unwrap()), "# B\nb\n# C\nc\n# A\na\n");
+		assert_eq!(outline.move_section(text, 0, Some(1)), None);
+	}
+}
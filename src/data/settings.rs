@@ -0,0 +1,528 @@
+//! # Settings.
+//! Preferences of the app, stored in `settings.toml` in the config directory and edited from the settings
+//! window.
+//!
+//! Every setting is read and written as text, so the settings window and the file go through the same
+//! validation. Invalid values of the file fall back to their defaults.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Zoom levels of the editor and of the interface, in percent.
+const MIN_ZOOM: u16 = 50;
+const MAX_ZOOM: u16 = 300;
+const ZOOM_STEP: u16 = 10;
+
+const FILE_HEADER: &str = "\
+# Rhyolite settings.
+# Edited from the settings window, changes made here are applied while the app runs.
+";
+
+/// Pages of the settings window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsPage {
+	Appearance,
+	Editor,
+	Files,
+}
+
+impl SettingsPage {
+	pub const ALL: [SettingsPage; 3] = [SettingsPage::Appearance, SettingsPage::Editor, SettingsPage::Files];
+
+	pub fn title(self) -> &'static str {
+		match self {
+			SettingsPage::Appearance => "Appearance",
+			SettingsPage::Editor => "Editor",
+			SettingsPage::Files => "Files",
+		}
+	}
+}
+
+/// A single setting, as listed in the settings window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+	ThemeMode,
+	LightTheme,
+	DarkTheme,
+	LightFrom,
+	DarkFrom,
+	FontFamily,
+	CodeFontFamily,
+	FontSize,
+	LineHeight,
+	EditorWidth,
+	EditorZoom,
+	UiZoom,
+	CursorBlinkRate,
+	SpellCheck,
+	AutosaveDelay,
+	DefaultTrove,
+}
+
+impl Setting {
+	pub const ALL: [Setting; 16] = [
+		Setting::ThemeMode,
+		Setting::LightTheme,
+		Setting::DarkTheme,
+		Setting::LightFrom,
+		Setting::DarkFrom,
+		Setting::FontFamily,
+		Setting::CodeFontFamily,
+		Setting::FontSize,
+		Setting::LineHeight,
+		Setting::EditorWidth,
+		Setting::EditorZoom,
+		Setting::UiZoom,
+		Setting::CursorBlinkRate,
+		Setting::SpellCheck,
+		Setting::AutosaveDelay,
+		Setting::DefaultTrove,
+	];
+
+	pub fn page(self) -> SettingsPage {
+		match self {
+			Setting::ThemeMode
+			| Setting::LightTheme
+			| Setting::DarkTheme
+			| Setting::LightFrom
+			| Setting::DarkFrom
+			| Setting::FontFamily
+			| Setting::CodeFontFamily
+			| Setting::FontSize
+			| Setting::LineHeight
+			| Setting::EditorWidth
+			| Setting::EditorZoom
+			| Setting::UiZoom => SettingsPage::Appearance,
+			Setting::CursorBlinkRate | Setting::SpellCheck => SettingsPage::Editor,
+			Setting::AutosaveDelay | Setting::DefaultTrove => SettingsPage::Files,
+		}
+	}
+
+	pub fn title(self) -> &'static str {
+		match self {
+			Setting::ThemeMode => "Theme Mode",
+			Setting::LightTheme => "Light Theme",
+			Setting::DarkTheme => "Dark Theme",
+			Setting::LightFrom => "Light From",
+			Setting::DarkFrom => "Dark From",
+			Setting::FontFamily => "Prose Font",
+			Setting::CodeFontFamily => "Code Font",
+			Setting::FontSize => "Font Size",
+			Setting::LineHeight => "Line Height",
+			Setting::EditorWidth => "Editor Width",
+			Setting::EditorZoom => "Editor Zoom",
+			Setting::UiZoom => "Interface Zoom",
+			Setting::CursorBlinkRate => "Cursor Blink Rate",
+			Setting::SpellCheck => "Spell Check",
+			Setting::AutosaveDelay => "Autosave Delay",
+			Setting::DefaultTrove => "Default Trove",
+		}
+	}
+
+	pub fn description(self) -> &'static str {
+		match self {
+			Setting::ThemeMode => {
+				"manual keeps the theme picked in the themes menu, system switches between the light and dark themes with the system appearance, schedule switches at the times below. System uses the times when the system does not tell its appearance."
+			}
+			Setting::LightTheme => "Name of the theme used when the system is light, or during the day.",
+			Setting::DarkTheme => "Name of the theme used when the system is dark, or during the night.",
+			Setting::LightFrom => "Time the light theme starts when following the time of day, like 07:00.",
+			Setting::DarkFrom => "Time the dark theme starts when following the time of day, like 19:00.",
+			Setting::FontFamily => "Font of the editor text, an installed font or one of the fonts folder of the config directory.",
+			Setting::CodeFontFamily => "Font of the code blocks, best a monospace one.",
+			Setting::FontSize => "Size of the editor text, from 8 to 48.",
+			Setting::LineHeight => "Height of the editor lines relative to the font size, from 1 to 3.",
+			Setting::EditorWidth => "Width of the editor in percent of the window, from 30 to 100.",
+			Setting::EditorZoom => "Zoom of the editor text in percent, from 50 to 300. Changed with Ctrl+= and Ctrl+-.",
+			Setting::UiZoom => "Zoom of the tab bar and the status bar in percent, from 50 to 300. Changed with Ctrl+Alt+= and Ctrl+Alt+-.",
+			Setting::CursorBlinkRate => "Milliseconds between cursor blinks, from 100 to 2000, 0 keeps the cursor still.",
+			Setting::SpellCheck => {
+				"Underline the words missing from the dictionary, dictionary.txt of the config directory or else the word list of the system."
+			}
+			Setting::AutosaveDelay => "Seconds without typing before the note is saved, up to 3600, 0 turns autosave off.",
+			Setting::DefaultTrove => "Folder opened on startup, empty for Documents/Rhyolite Trove. Applies on restart.",
+		}
+	}
+
+	/// Whether the setting is on or off, shown as a switch instead of a text field.
+	pub fn is_toggle(self) -> bool {
+		self == Setting::SpellCheck
+	}
+}
+
+/// How the theme is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+	#[default]
+	Manual,
+	System,   // Follows the light or dark appearance of the system
+	Schedule, // Follows the time of day
+}
+
+impl ThemeMode {
+	fn as_str(self) -> &'static str {
+		match self {
+			ThemeMode::Manual => "manual",
+			ThemeMode::System => "system",
+			ThemeMode::Schedule => "schedule",
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppearanceSettings {
+	pub theme_mode: ThemeMode,
+	pub light_theme: String, // Name of a theme
+	pub dark_theme: String,
+	pub light_from: String, // Time of day, HH:MM
+	pub dark_from: String,
+	pub font_family: String, // Font of the prose
+	pub code_font_family: String,
+	pub font_size: f32,
+	pub line_height: f32,
+	pub editor_width: u8, // Percent of the window
+	pub editor_zoom: u16, // Percent
+	pub ui_zoom: u16,     // Percent
+}
+
+impl Default for AppearanceSettings {
+	fn default() -> Self {
+		AppearanceSettings {
+			theme_mode: ThemeMode::Manual,
+			light_theme: "Catppuccin Latte".to_string(),
+			dark_theme: "Catppuccin Mocha".to_string(),
+			light_from: "07:00".to_string(),
+			dark_from: "19:00".to_string(),
+			font_family: "JetBrains Mono".to_string(),
+			code_font_family: "JetBrains Mono".to_string(),
+			font_size: 16.0,
+			line_height: 1.3,
+			editor_width: 80,
+			editor_zoom: 100,
+			ui_zoom: 100,
+		}
+	}
+}
+
+impl AppearanceSettings {
+	/// Size of the editor text, zoom included.
+	pub fn editor_font_size(&self) -> f32 {
+		self.font_size * f32::from(self.editor_zoom) / 100.0
+	}
+
+	/// Scales a size of the interface by its zoom.
+	pub fn ui_size(&self, size: f32) -> f32 {
+		size * f32::from(self.ui_zoom) / 100.0
+	}
+
+	/// The name of the theme the theme mode wants, from whether the system is dark when it tells it and
+	/// from the time of day in minutes. `None` when the theme is picked manually.
+	pub fn wanted_theme(&self, system_is_dark: Option<bool>, minute_of_day: u16) -> Option<&str> {
+		let is_dark = match (self.theme_mode, system_is_dark) {
+			(ThemeMode::Manual, _) => return None,
+			(ThemeMode::System, Some(is_dark)) => is_dark,
+			(ThemeMode::System | ThemeMode::Schedule, _) => {
+				let light_from = parse_time(&self.light_from).ok()?;
+				let dark_from = parse_time(&self.dark_from).ok()?;
+				if light_from <= dark_from {
+					!(light_from..dark_from).contains(&minute_of_day)
+				} else {
+					(dark_from..light_from).contains(&minute_of_day)
+				}
+			}
+		};
+		Some(if is_dark { &self.dark_theme } else { &self.light_theme })
+	}
+}
+
+/// The zoom level after zooming in or out one step.
+pub fn step_zoom(zoom: u16, zoom_in: bool) -> u16 {
+	let zoom = if zoom_in {
+		zoom + ZOOM_STEP
+	} else {
+		zoom.saturating_sub(ZOOM_STEP)
+	};
+	zoom.clamp(MIN_ZOOM, MAX_ZOOM)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorSettings {
+	pub cursor_blink_rate: u64, // Milliseconds, 0 turns blinking off
+	pub spell_check: bool,
+}
+
+impl Default for EditorSettings {
+	fn default() -> Self {
+		EditorSettings {
+			cursor_blink_rate: 500,
+			spell_check: false,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilesSettings {
+	pub autosave_delay: u64, // Seconds, 0 turns autosave off
+	pub default_trove: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+	pub appearance: AppearanceSettings,
+	pub editor: EditorSettings,
+	pub files: FilesSettings,
+}
+
+impl Settings {
+	/// Reads the settings from the content of the settings file. Returns them with warnings about the
+	/// invalid values, which are replaced by their defaults.
+	pub fn load(content: &str) -> (Settings, Vec<String>) {
+		let mut settings = match toml::from_str::<Settings>(content) {
+			Ok(settings) => settings,
+			Err(e) => {
+				return (
+					Settings::default(),
+					vec![format!("Could not read the settings, using the defaults: {e}")],
+				);
+			}
+		};
+		let defaults = Settings::default();
+		let mut warnings = Vec::new();
+		for setting in Setting::ALL {
+			let value = settings.value(setting);
+			if let Err(e) = settings.set(setting, &value) {
+				warnings.push(format!(
+					"Invalid {} \"{value}\" in the settings, using the default: {e}",
+					setting.title()
+				));
+				let _ = settings.set(setting, &defaults.value(setting));
+			}
+		}
+		(settings, warnings)
+	}
+
+	pub fn to_file(&self) -> String {
+		format!("{FILE_HEADER}\n{}", toml::to_string(self).unwrap_or_default())
+	}
+
+	/// The value of a setting as text.
+	pub fn value(&self, setting: Setting) -> String {
+		match setting {
+			Setting::ThemeMode => self.appearance.theme_mode.as_str().to_string(),
+			Setting::LightTheme => self.appearance.light_theme.clone(),
+			Setting::DarkTheme => self.appearance.dark_theme.clone(),
+			Setting::LightFrom => self.appearance.light_from.clone(),
+			Setting::DarkFrom => self.appearance.dark_from.clone(),
+			Setting::FontFamily => self.appearance.font_family.clone(),
+			Setting::CodeFontFamily => self.appearance.code_font_family.clone(),
+			Setting::FontSize => self.appearance.font_size.to_string(),
+			Setting::LineHeight => self.appearance.line_height.to_string(),
+			Setting::EditorWidth => self.appearance.editor_width.to_string(),
+			Setting::EditorZoom => self.appearance.editor_zoom.to_string(),
+			Setting::UiZoom => self.appearance.ui_zoom.to_string(),
+			Setting::CursorBlinkRate => self.editor.cursor_blink_rate.to_string(),
+			Setting::SpellCheck => self.editor.spell_check.to_string(),
+			Setting::AutosaveDelay => self.files.autosave_delay.to_string(),
+			Setting::DefaultTrove => self
+				.files
+				.default_trove
+				.as_ref()
+				.map(|path| path.to_string_lossy().to_string())
+				.unwrap_or_default(),
+		}
+	}
+
+	/// Sets a setting from text, or returns why the text is not a valid value and keeps the setting.
+	pub fn set(&mut self, setting: Setting, text: &str) -> Result<(), String> {
+		let text = text.trim();
+		match setting {
+			Setting::ThemeMode => {
+				self.appearance.theme_mode = match text {
+					"manual" => ThemeMode::Manual,
+					"system" => ThemeMode::System,
+					"schedule" => ThemeMode::Schedule,
+					_ => return Err("expected manual, system or schedule".to_string()),
+				}
+			}
+			Setting::LightTheme => self.appearance.light_theme = parse_theme_name(text)?,
+			Setting::DarkTheme => self.appearance.dark_theme = parse_theme_name(text)?,
+			Setting::LightFrom => {
+				parse_time(text)?;
+				self.appearance.light_from = text.to_string();
+			}
+			Setting::DarkFrom => {
+				parse_time(text)?;
+				self.appearance.dark_from = text.to_string();
+			}
+			Setting::FontFamily => self.appearance.font_family = parse_font(text)?,
+			Setting::CodeFontFamily => self.appearance.code_font_family = parse_font(text)?,
+			Setting::FontSize => self.appearance.font_size = parse_in_range(text, 8.0, 48.0)?,
+			Setting::LineHeight => self.appearance.line_height = parse_in_range(text, 1.0, 3.0)?,
+			Setting::EditorWidth => self.appearance.editor_width = parse_in_range(text, 30, 100)?,
+			Setting::EditorZoom => self.appearance.editor_zoom = parse_in_range(text, MIN_ZOOM, MAX_ZOOM)?,
+			Setting::UiZoom => self.appearance.ui_zoom = parse_in_range(text, MIN_ZOOM, MAX_ZOOM)?,
+			Setting::CursorBlinkRate => {
+				let rate = parse_in_range(text, 0, 2000)?;
+				if rate != 0 && rate < 100 {
+					return Err("expected 0 or a number from 100 to 2000".to_string());
+				}
+				self.editor.cursor_blink_rate = rate;
+			}
+			Setting::SpellCheck => {
+				self.editor.spell_check = text.parse().map_err(|_| "expected true or false".to_string())?;
+			}
+			Setting::AutosaveDelay => self.files.autosave_delay = parse_in_range(text, 0, 3600)?,
+			Setting::DefaultTrove => {
+				if text.is_empty() {
+					self.files.default_trove = None;
+					return Ok(());
+				}
+				let path = PathBuf::from(text);
+				if !path.is_dir() {
+					return Err("no folder at this path".to_string());
+				}
+				self.files.default_trove = Some(path);
+			}
+		}
+		Ok(())
+	}
+}
+
+fn parse_theme_name(text: &str) -> Result<String, String> {
+	if text.is_empty() {
+		return Err("the theme name can not be empty".to_string());
+	}
+	Ok(text.to_string())
+}
+
+/// Parses a time of day written HH:MM into minutes since midnight.
+fn parse_time(text: &str) -> Result<u16, String> {
+	let error = || "expected a time like 07:30".to_string();
+	let (hours, minutes) = text.split_once(':').ok_or_else(error)?;
+	let hours: u16 = hours.parse().map_err(|_| error())?;
+	let minutes: u16 = if minutes.len() == 2 {
+		minutes.parse().map_err(|_| error())?
+	} else {
+		return Err(error());
+	};
+	if hours > 23 || minutes > 59 {
+		return Err(error());
+	}
+	Ok(hours * 60 + minutes)
+}
+
+fn parse_font(text: &str) -> Result<String, String> {
+	if text.is_empty() {
+		return Err("the font can not be empty".to_string());
+	}
+	Ok(text.to_string())
+}
+
+fn parse_in_range<T: std::str::FromStr + PartialOrd + std::fmt::Display>(text: &str, min: T, max: T) -> Result<T, String> {
+	match text.parse::<T>() {
+		Ok(value) if value >= min && value <= max => Ok(value),
+		_ => Err(format!("expected a number from {min} to {max}")),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn missing_values_use_the_defaults() {
+		let (settings, warnings) = Settings::load("[appearance]\nfont_size = 20.0\n");
+		assert!(warnings.is_empty(), "{warnings:?}");
+		assert_eq!(settings.appearance.font_size, 20.0);
+		assert_eq!(settings.appearance.font_family, "JetBrains Mono");
+		assert_eq!(settings.editor, EditorSettings::default());
+		assert_eq!(Settings::load("").0, Settings::default());
+	}
+
+	#[test]
+	fn invalid_values_fall_back_to_their_defaults() {
+		let (settings, warnings) = Settings::load("[appearance]\nfont_size = 100.0\nline_height = 2.0\n[editor]\ncursor_blink_rate = 50\n");
+		assert_eq!(warnings.len(), 2, "{warnings:?}");
+		assert_eq!(settings.appearance.font_size, 16.0);
+		assert_eq!(settings.appearance.line_height, 2.0);
+		assert_eq!(settings.editor.cursor_blink_rate, 500);
+
+		let (settings, warnings) = Settings::load("not toml");
+		assert_eq!(warnings.len(), 1);
+		assert_eq!(settings, Settings::default());
+	}
+
+	#[test]
+	fn sets_values_from_text() {
+		let mut settings = Settings::default();
+		assert!(settings.set(Setting::FontSize, " 12 ").is_ok());
+		assert_eq!(settings.value(Setting::FontSize), "12");
+		assert!(settings.set(Setting::CursorBlinkRate, "0").is_ok());
+		assert!(settings.set(Setting::SpellCheck, "true").is_ok());
+		assert!(settings.editor.spell_check);
+
+		assert!(settings.set(Setting::FontFamily, "  ").is_err());
+		assert!(settings.set(Setting::EditorWidth, "20").is_err());
+		assert!(settings.set(Setting::AutosaveDelay, "soon").is_err());
+		assert_eq!(settings.value(Setting::FontFamily), "JetBrains Mono");
+	}
+
+	#[test]
+	fn the_file_gives_back_the_settings() {
+		let mut settings = Settings::default();
+		settings.appearance.font_family = "Inter".to_string();
+		settings.files.autosave_delay = 30;
+		let (loaded, warnings) = Settings::load(&settings.to_file());
+		assert!(warnings.is_empty(), "{warnings:?}");
+		assert_eq!(loaded, settings);
+	}
+
+	#[test]
+	fn parses_times_of_day() {
+		assert_eq!(parse_time("07:30"), Ok(450));
+		assert_eq!(parse_time("0:00"), Ok(0));
+		assert!(parse_time("24:00").is_err());
+		assert!(parse_time("12:5").is_err());
+		assert!(parse_time("noon").is_err());
+		assert!(parse_theme_name("").is_err());
+	}
+
+	#[test]
+	fn picks_the_theme_of_the_theme_mode() {
+		let mut appearance = AppearanceSettings::default();
+		assert_eq!(appearance.wanted_theme(Some(true), 0), None);
+
+		appearance.theme_mode = ThemeMode::System;
+		assert_eq!(appearance.wanted_theme(Some(true), 12 * 60), Some("Catppuccin Mocha"));
+		assert_eq!(appearance.wanted_theme(Some(false), 0), Some("Catppuccin Latte"));
+		// Without the appearance of the system, the schedule is followed.
+		assert_eq!(appearance.wanted_theme(None, 12 * 60), Some("Catppuccin Latte"));
+
+		appearance.theme_mode = ThemeMode::Schedule;
+		assert_eq!(appearance.wanted_theme(Some(false), 7 * 60), Some("Catppuccin Latte"));
+		assert_eq!(appearance.wanted_theme(Some(false), 19 * 60), Some("Catppuccin Mocha"));
+		assert_eq!(appearance.wanted_theme(None, 3 * 60), Some("Catppuccin Mocha"));
+
+		// A dark period within the day.
+		appearance.light_from = "22:00".to_string();
+		appearance.dark_from = "10:00".to_string();
+		assert_eq!(appearance.wanted_theme(None, 12 * 60), Some("Catppuccin Mocha"));
+		assert_eq!(appearance.wanted_theme(None, 23 * 60), Some("Catppuccin Latte"));
+	}
+
+	#[test]
+	fn sets_the_theme_mode_from_text() {
+		let mut settings = Settings::default();
+		assert!(settings.set(Setting::ThemeMode, "schedule").is_ok());
+		assert_eq!(settings.appearance.theme_mode, ThemeMode::Schedule);
+		assert_eq!(settings.value(Setting::ThemeMode), "schedule");
+		assert!(settings.set(Setting::ThemeMode, "auto").is_err());
+		assert!(settings.set(Setting::DarkFrom, "25:00").is_err());
+		assert_eq!(settings.value(Setting::DarkFrom), "19:00");
+	}
+}
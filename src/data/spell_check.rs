@@ -0,0 +1,95 @@
+//! # Spell Check.
+//! Finds the words of a line missing from a dictionary, a plain list of words with one word per line
+//! like the system word list.
+
+use std::{collections::HashSet, ops::Range};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dictionary {
+	words: HashSet<String>, // Lowercase
+}
+
+impl Dictionary {
+	pub fn from_word_list(content: &str) -> Dictionary {
+		Dictionary {
+			words: content
+				.lines()
+				.map(|word| word.trim().to_lowercase())
+				.filter(|word| !word.is_empty())
+				.collect(),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.words.is_empty()
+	}
+
+	pub fn contains(&self, word: &str) -> bool {
+		let word = word.to_lowercase();
+		// Possessives like "Rhyolite's" are checked without the suffix.
+		self.words.contains(&word) || word.strip_suffix("'s").is_some_and(|stem| self.words.contains(stem))
+	}
+
+	/// Char ranges of the words of the line missing from the dictionary. Words with digits, inline code,
+	/// links and tags are skipped.
+	pub fn misspelled(&self, line: &str) -> Vec<Range<usize>> {
+		if self.is_empty() {
+			return Vec::new();
+		}
+		let chars: Vec<char> = line.chars().collect();
+		let mut ranges = Vec::new();
+		let mut in_code = false;
+		let mut index = 0;
+		while index < chars.len() {
+			let ch = chars[index];
+			if ch == '`' {
+				in_code = !in_code;
+				index += 1;
+				continue;
+			}
+			if !ch.is_alphanumeric() {
+				index += 1;
+				continue;
+			}
+			let start = index;
+			while index < chars.len()
+				&& (chars[index].is_alphanumeric()
+					|| (chars[index] == '\'' && chars.get(index + 1).is_some_and(|next| next.is_alphabetic())))
+			{
+				index += 1;
+			}
+			// A word is part of a link or a tag when it touches one of these chars.
+			let before = start.checked_sub(1).map(|before| chars[before]);
+			let after = chars.get(index).copied();
+			let is_markup = matches!(before, Some('#' | '/' | '.' | '@' | '_'))
+				|| matches!(after, Some('/' | '@' | '_'))
+				|| (after == Some(':') && chars.get(index + 1) == Some(&'/'));
+			let word: String = chars[start..index].iter().collect();
+			if !in_code && !is_markup && !word.chars().any(|ch| ch.is_numeric()) && !self.contains(&word) {
+				ranges.push(start..index);
+			}
+		}
+		ranges
+	}
+}
+
+/// Splits a line into its spans, telling for each one whether it is misspelled.
+pub fn split_misspelled(line: &str, misspelled: &[Range<usize>]) -> Vec<(String, bool)> {
+	let chars: Vec<char> = line.chars().collect();
+	let mut spans = Vec::new();
+	let mut position = 0;
+	for range in misspelled {
+		let (start, end) = (range.start.min(chars.len()), range.end.min(chars.len()));
+		if start > position {
+			spans.push((chars[position..start].iter().collect(), false));
+		}
+		if end > start {
+			spans.push((chars[start..end].iter().collect(), true));
+		}
+		position = position.max(end);
+	}
+	if position < chars.len() {
+		spans.push((chars[position..].iter().collect(), false));
+	}
+	spans
+}
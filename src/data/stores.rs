@@ -38,13 +38,45 @@ TODO: Use Dioxus Radio for complex global states instead of global signals.
 // - Imports
 //-------------------------------------------------------------------------
 use crate::data::{
-	io_utils::{delete_file, generate_available_path, get_default_trove_dir, new_file_from_path, save_file},
-	themes::ThemesStore,
-	types::{DEFAULT_NOTE_TITLE, MarkdownFile, RecentFileInfo, Tab},
+	commands::Command,
+	daily_notes::{DailyNotesConfig, offset_day},
+	fn_utils::apply_text_edit,
+	io_utils::{
+		delete_file, generate_available_path, get_default_trove_dir, get_keybindings_path, get_settings_path, get_templates_dir,
+		load_dictionary, load_keymap, load_settings, load_undo_history, new_file_from_path, new_file_with_content, open_file_from_path,
+		open_log_file, save_file, save_keymap, save_settings, save_userdata,
+	},
+	keymap::{Chord, Keymap, format_sequence},
+	line_editing::{LineOperation, apply_line_operation},
+	multi_cursor::Caret,
+	outline::Outline,
+	settings::{AppearanceSettings, Settings, step_zoom},
+	spell_check::Dictionary,
+	tables::{TableCommand, run_table_command},
+	tags::TagIndex,
+	tasks::{TaskIndex, toggle_task},
+	templates::{TemplateContext, insert_template, render_template},
+	text_edit::TextEdit,
+	theme_contrast::high_contrast_variant,
+	themes::{ThemeSource, ThemesStore, save_new_theme},
+	types::{
+		DEFAULT_NOTE_TITLE, MarkdownFile, PaletteMode, RecentFileInfo, SidePanel, Tab, TemplateTarget, Toast, ToastAction, ToastLevel,
+	},
+	undo_tree::UndoTree,
+	vim::Vim,
 };
+use chrono::{Local, NaiveDate, Timelike};
 use dioxus_clipboard::hooks::{UseClipboard, use_clipboard};
 use freya::prelude::*;
 use slab::Slab;
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap},
+	fs,
+	ops::Range,
+	path::{Path, PathBuf},
+	sync::atomic::{AtomicU8, AtomicUsize, Ordering},
+	time::SystemTime,
+};
 
 //-------------------------------------------------------------------------
 // - Workspace Store
@@ -61,17 +93,400 @@ pub static CURRENT_EDITOR_BUFFER: GlobalSignal<UseEditable> = Signal::global(||
 });
 pub static ACTIVE_DOCUMENT_TITLE: GlobalSignal<String> = Signal::global(String::new);
 
+// Headings of the current document.
+pub static DOCUMENT_OUTLINE: GlobalSignal<Outline> = Signal::global(Outline::default);
+// Ranges of lines that can be folded in the current document, keyed by their first line.
+pub static FOLDABLE_RANGES: GlobalSignal<BTreeMap<usize, Range<usize>>> = Signal::global(BTreeMap::new);
+// Lines where a fold starts, for each file.
+pub static FOLDED_LINES: GlobalSignal<HashMap<PathBuf, BTreeSet<usize>>> = Signal::global(HashMap::new);
+// Cursors of the current document other than the main one of its buffer.
+pub static EXTRA_CURSORS: GlobalSignal<Vec<Caret>> = Signal::global(Vec::new);
+// Undo history of each open file, and whether it is kept across restarts.
+pub static UNDO_TREES: GlobalSignal<HashMap<PathBuf, UndoTree>> = Signal::global(HashMap::new);
+pub static PERSIST_UNDO_HISTORY: GlobalSignal<bool> = Signal::global(|| false);
+
+pub static KEYMAP: GlobalSignal<Keymap> = Signal::global(load_keymap);
+pub static KEYBINDINGS_MODIFIED: GlobalSignal<Option<SystemTime>> = Signal::global(|| None);
+// Command getting a new binding in the keyboard shortcuts overlay, with the chords typed so far.
+pub static RECORDING_BINDING: GlobalSignal<Option<(Command, Vec<Chord>)>> = Signal::global(|| None);
+
+pub static SETTINGS: GlobalSignal<Settings> = Signal::global(load_settings);
+pub static SETTINGS_MODIFIED: GlobalSignal<Option<SystemTime>> = Signal::global(|| None);
+// Word list of the spell check, only read once the spell check is used.
+pub static DICTIONARY: GlobalSignal<Dictionary> = Signal::global(load_dictionary);
+
+pub static VIM_MODE: GlobalSignal<bool> = Signal::global(|| false);
+pub static VIM_STATE: GlobalSignal<Vim> = Signal::global(Vim::default);
+// Line the editor has to scroll to.
+pub static EDITOR_SCROLL_REQUEST: GlobalSignal<Option<usize>> = Signal::global(|| None);
+
 pub static RECENT_FILES: GlobalSignal<Vec<RecentFileInfo>> = Signal::global(Vec::new);
 
+// Where the daily notes are stored and the template they are created from.
+pub static DAILY_NOTES_CONFIG: GlobalSignal<DailyNotesConfig> = Signal::global(DailyNotesConfig::default);
+
+// Index of the tags used in the trove, and the tags selected in the tag browser to filter notes.
+pub static TAG_INDEX: GlobalSignal<TagIndex> = Signal::global(TagIndex::default);
+pub static SELECTED_TAGS: GlobalSignal<Vec<String>> = Signal::global(Vec::new);
+
+// Index of the tasks of every note in the trove.
+pub static TASK_INDEX: GlobalSignal<TaskIndex> = Signal::global(TaskIndex::default);
+
 pub static PLATFORM: GlobalSignal<UsePlatform> = Signal::global(use_platform);
 pub static CLIPBOARD: GlobalSignal<UseClipboard> = Signal::global(use_clipboard);
 
+/// Folds the range starting at the given line of the current file, or unfolds it if it is already folded.
+pub fn toggle_fold(line: usize) {
+	let Some(file) = current_file() else {
+		return;
+	};
+	let mut folded_lines = FOLDED_LINES.write();
+	let folds = folded_lines.entry(file.path).or_default();
+	if !folds.remove(&line) {
+		folds.insert(line);
+	}
+}
+
+/// Moves the cursor of the editor to the start of the given line and scrolls the editor to it, unfolding
+/// the folds hiding the line.
+pub fn jump_to_line(line: usize) {
+	let mut editable = CURRENT_EDITOR_BUFFER();
+	if let Some(file) = current_file()
+		&& let Some(folds) = FOLDED_LINES.write().get_mut(&file.path)
+	{
+		let foldable = FOLDABLE_RANGES.read();
+		folds.retain(|start| foldable.get(start).is_none_or(|range| !(range.start < line && line < range.end)));
+	}
+	{
+		let mut editor = editable.editor_mut().write();
+		let line = line.min(editor.len_lines().saturating_sub(1));
+		let position = editor.line_to_char(line);
+		editor.clear_selection();
+		editor.set_cursor_pos(position);
+	}
+	*EDITOR_SCROLL_REQUEST.write() = Some(line);
+}
+
+/// Toggles the checkbox of the task on the line of the cursor, if the line is a task.
+pub fn toggle_task_at_cursor() {
+	let line = CURRENT_EDITOR_BUFFER().editor().read().cursor_row();
+	toggle_task_on_line(line);
+}
+
+/// Runs a table command on the table under the cursor, if any.
+pub fn run_table_command_at_cursor(command: TableCommand) {
+	let mut editable = CURRENT_EDITOR_BUFFER();
+	let (content, cursor) = {
+		let editor = editable.editor().read();
+		(editor.to_string(), editor.cursor_pos())
+	};
+	if let Some(edit) = run_table_command(&content, cursor, command) {
+		apply_text_edit(&mut editable, edit);
+	}
+}
+
+/// Applies a line operation to the lines of the selection (or of the cursor) of the current file.
+pub fn run_line_operation_at_cursor(operation: LineOperation) {
+	let mut editable = CURRENT_EDITOR_BUFFER();
+	let (content, cursor, selection) = {
+		let editor = editable.editor().read();
+		(editor.to_string(), editor.cursor_pos(), editor.get_selection())
+	};
+	let selection = selection.filter(|(start, end)| start != end);
+	if let Some(edit) = apply_line_operation(&content, cursor, selection, operation) {
+		apply_text_edit(&mut editable, edit);
+	}
+}
+
+/// Records the current text of a file in its undo history, creating the history the first time the file
+/// is seen.
+pub fn record_edit(path: &Path, text: &str) {
+	if UNDO_TREES.peek().get(path).is_some_and(|tree| tree.text() == text) {
+		return;
+	}
+	let mut trees = UNDO_TREES.write();
+	match trees.get_mut(path) {
+		Some(tree) => tree.record(text, Local::now().timestamp_millis()),
+		None => {
+			let saved_tree = if PERSIST_UNDO_HISTORY() {
+				load_undo_history(path, text)
+			} else {
+				None
+			};
+			trees.insert(path.to_path_buf(), saved_tree.unwrap_or_else(|| UndoTree::new(text)));
+		}
+	}
+}
+
+/// Records the edits of the current file that are not in its undo history yet.
+pub fn record_current_edit() {
+	if let Some(file) = current_file() {
+		record_edit(&file.path, &file.editable.editor().read().to_string());
+	}
+}
+
+/// Moves in the undo history of the current file and applies the resulting edit to its buffer.
+fn apply_history_edit(move_in_history: impl FnOnce(&mut UndoTree) -> Option<TextEdit>) {
+	let Some(file) = current_file() else {
+		return;
+	};
+	// The buffer may have changed since the edit was last recorded.
+	record_current_edit();
+	let edit = UNDO_TREES.write().get_mut(&file.path).and_then(move_in_history);
+	if let Some(edit) = edit {
+		apply_text_edit(&mut CURRENT_EDITOR_BUFFER(), edit);
+	}
+}
+
+pub fn undo_edit() {
+	apply_history_edit(UndoTree::undo);
+}
+
+pub fn redo_edit() {
+	apply_history_edit(UndoTree::redo);
+}
+
+pub fn undo_to_last_save() {
+	apply_history_edit(UndoTree::undo_to_last_save);
+}
+
+/// Restores the current file to an edit of its undo history.
+pub fn jump_to_history_entry(index: usize) {
+	apply_history_edit(|tree| tree.jump_to(index));
+}
+
+pub fn toggle_persistent_undo_history() {
+	let current = *PERSIST_UNDO_HISTORY.read();
+	*PERSIST_UNDO_HISTORY.write() = !current;
+}
+
+pub fn reload_keymap() {
+	*KEYMAP.write() = load_keymap();
+	log::debug!("Reloaded the key bindings.");
+}
+
+/// Reloads the key bindings if their file changed since the last check.
+pub fn reload_keymap_if_changed() {
+	let modified = fs::metadata(get_keybindings_path()).and_then(|metadata| metadata.modified()).ok();
+	if modified != *KEYBINDINGS_MODIFIED.peek() {
+		let first_check = KEYBINDINGS_MODIFIED.peek().is_none();
+		*KEYBINDINGS_MODIFIED.write() = modified;
+		if !first_check {
+			reload_keymap();
+		}
+	}
+}
+
+/// Reloads the settings if their file changed since the last check, which applies the changes made in the
+/// settings window.
+pub fn reload_settings_if_changed() {
+	let modified = fs::metadata(get_settings_path()).and_then(|metadata| metadata.modified()).ok();
+	if modified != *SETTINGS_MODIFIED.peek() {
+		let first_check = SETTINGS_MODIFIED.peek().is_none();
+		*SETTINGS_MODIFIED.write() = modified;
+		if !first_check {
+			*SETTINGS.write() = load_settings();
+			log::debug!("Reloaded the settings.");
+		}
+	}
+}
+
+/// Lists the themes again and reloads the current theme when a file of the themes directory is added,
+/// removed or edited.
+pub fn reload_themes_if_changed() {
+	let snapshot = Some(THEME_STORE.peek().files_snapshot());
+	if snapshot != *THEMES_SNAPSHOT.peek() {
+		let first_check = THEMES_SNAPSHOT.peek().is_none();
+		*THEMES_SNAPSHOT.write() = snapshot;
+		// The themes failing to load at startup are reported on the first check, then only the new failures.
+		let previously_invalid = if first_check {
+			Vec::new()
+		} else {
+			THEME_STORE.peek().invalid_themes.clone()
+		};
+		if !first_check {
+			if let Err(errors) = THEME_STORE.write().refresh() {
+				push_toast(
+					ToastLevel::Warning,
+					format!("The current theme has {} errors, its previous colors are kept.", errors.len()),
+					vec![ToastAction::OpenLog],
+				);
+			}
+			log::debug!("Reloaded the themes.");
+		}
+		let invalid_themes = THEME_STORE.peek().invalid_themes.clone();
+		for (source, errors) in invalid_themes.iter().filter(|invalid| !previously_invalid.contains(invalid)) {
+			let first_error = errors.first().map(String::as_str).unwrap_or_default();
+			push_toast(
+				ToastLevel::Warning,
+				format!("The theme {source} is not listed, it has {} errors: {first_error}", errors.len()),
+				vec![ToastAction::OpenLog],
+			);
+		}
+	}
+}
+
+/// Records the light or dark appearance of the system, as reported by the theme of the window. Freya does not
+/// pass the `ThemeChanged` events of the window on to the app, so the app polls the theme. winit knows no theme
+/// on most Linux desktops, where the system mode follows the schedule instead.
+pub fn record_system_appearance(theme: Option<winit::window::Theme>) {
+	let appearance = match theme {
+		None => 0,
+		Some(winit::window::Theme::Light) => 1,
+		Some(winit::window::Theme::Dark) => 2,
+	};
+	SYSTEM_APPEARANCE.store(appearance, Ordering::Relaxed);
+}
+
+/// Switches to the light or dark theme of the settings when the theme mode follows the system or the time
+/// of day.
+pub fn apply_theme_mode() {
+	let system_is_dark = match SYSTEM_APPEARANCE.load(Ordering::Relaxed) {
+		1 => Some(false),
+		2 => Some(true),
+		_ => None,
+	};
+	let now = Local::now();
+	let minute_of_day = (now.hour() * 60 + now.minute()) as u16;
+	let wanted_theme = SETTINGS
+		.peek()
+		.appearance
+		.wanted_theme(system_is_dark, minute_of_day)
+		.map(str::to_string);
+	let Some(name) = wanted_theme else {
+		// Back to manual, the next mode applies its theme right away.
+		if AUTO_THEME.peek().is_some() {
+			*AUTO_THEME.write() = None;
+		}
+		return;
+	};
+	if AUTO_THEME.peek().as_ref() == Some(&name) || THEME_STORE.peek().is_previewing() {
+		return;
+	}
+	*AUTO_THEME.write() = Some(name.clone());
+	let source = THEME_STORE.peek().source_of(&name);
+	match source {
+		Some(source) => {
+			log::info!("Switching to the theme {name} of the theme mode.");
+			spawn(commit_theme(source));
+		}
+		None => log::warn!("There is no theme named {name} to switch to, keeping the current theme."),
+	}
+}
+
+/// Changes the settings and saves them. The change is applied to the settings of the file, which has the
+/// changes of the settings window not reloaded yet.
+pub fn update_settings(change: impl FnOnce(&mut Settings)) {
+	let mut settings = load_settings();
+	change(&mut settings);
+	save_settings(&settings);
+	*SETTINGS.write() = settings;
+	// The file changed because of this update, no need to reload it.
+	*SETTINGS_MODIFIED.write() = fs::metadata(get_settings_path()).and_then(|metadata| metadata.modified()).ok();
+}
+
+/// Zooms the editor text in or out one step.
+pub fn zoom_editor(zoom_in: bool) {
+	update_settings(|settings| settings.appearance.editor_zoom = step_zoom(settings.appearance.editor_zoom, zoom_in));
+}
+
+/// Zooms the tab bar and the status bar in or out one step.
+pub fn zoom_ui(zoom_in: bool) {
+	update_settings(|settings| settings.appearance.ui_zoom = step_zoom(settings.appearance.ui_zoom, zoom_in));
+}
+
+pub fn reset_zoom() {
+	update_settings(|settings| settings.appearance.editor_zoom = AppearanceSettings::default().editor_zoom);
+}
+
+pub fn reset_ui_zoom() {
+	update_settings(|settings| settings.appearance.ui_zoom = AppearanceSettings::default().ui_zoom);
+}
+
+/// Starts recording a new binding for the command, replacing the one being recorded.
+pub fn record_binding(command: Command) {
+	*RECORDING_BINDING.write() = Some((command, Vec::new()));
+}
+
+pub fn record_chord(chord: Chord) {
+	if let Some((_, chords)) = RECORDING_BINDING.write().as_mut() {
+		chords.push(chord);
+	}
+}
+
+pub fn cancel_binding_recording() {
+	*RECORDING_BINDING.write() = None;
+}
+
+/// Binds the recorded chords to their command, replacing its previous bindings, and saves them to the
+/// key bindings file.
+pub fn save_recorded_binding() {
+	let Some((command, chords)) = RECORDING_BINDING.write().take() else {
+		return;
+	};
+	if chords.is_empty() {
+		return;
+	}
+	let warnings = KEYMAP.write().rebind(&command.id(), vec![format_sequence(&chords)], Command::is_id);
+	for warning in warnings {
+		log::warn!("{warning}");
+	}
+	save_keymap(&KEYMAP.peek());
+	// The file changed because of the new binding, no need to reload it.
+	*KEYBINDINGS_MODIFIED.write() = fs::metadata(get_keybindings_path()).and_then(|metadata| metadata.modified()).ok();
+	log::debug!("Bound {} to {}", command.title(), format_sequence(&chords));
+}
+
+/// Turns the Vim modal editing layer on or off, starting in normal mode.
+pub fn toggle_vim_mode() {
+	let current = *VIM_MODE.read();
+	*VIM_MODE.write() = !current;
+	VIM_STATE.write().reset();
+}
+
+/// Opens the note of the task and toggles its checkbox.
+pub(crate) async fn toggle_task_in_file(path: PathBuf, line: usize) {
+	open_file_tab(path).await;
+	toggle_task_on_line(line);
+}
+
+/// Toggles the checkbox of the task on the given line of the current file, keeping the cursor in place.
+fn toggle_task_on_line(line: usize) {
+	let mut editable = CURRENT_EDITOR_BUFFER();
+	let (content, cursor) = {
+		let editor = editable.editor().read();
+		(editor.to_string(), editor.cursor_pos())
+	};
+	let Some(edit) = toggle_task(&content, line) else {
+		return;
+	};
+	apply_text_edit(&mut editable, edit.with_cursor(cursor));
+
+	if let Some(file) = current_file() {
+		TASK_INDEX
+			.write()
+			.update_file(&file.path, &file.editable.editor().read().to_string());
+	}
+}
+
+/// Opens the note of the task and moves the cursor to its line.
+pub(crate) async fn open_task(path: PathBuf, line: usize) {
+	open_file_tab(path).await;
+	jump_to_line(line);
+}
+
 //-------------------------------------------------------------------------
 // - Tabs Store
 //-------------------------------------------------------------------------
 pub(crate) static TABS: GlobalSignal<Vec<Tab>> = Signal::global(Vec::new);
 pub(crate) static CURRENT_TAB: GlobalSignal<Option<usize>> = Signal::global(|| None);
 
+/// Returns the file open in the current tab.
+pub fn current_file() -> Option<MarkdownFile> {
+	let tab = TABS().get(CURRENT_TAB()?)?.clone();
+	FILES_ARENA.read().get(tab.file_key).cloned()
+}
+
 /// Creates a new tab with a new Markdown file.
 pub(crate) async fn new_tab() {
 	let document_path = generate_available_path(get_default_trove_dir().join(String::from(DEFAULT_NOTE_TITLE) + ".md"));
@@ -92,6 +507,128 @@ pub(crate) async fn new_tab() {
 	log::debug!("Opened New Tab: {log_title}");
 }
 
+/// Creates a new tab with a new Markdown file filled with the template at the given path.
+pub(crate) async fn new_tab_from_template(template_path: PathBuf) {
+	let document_path = generate_available_path(get_default_trove_dir().join(String::from(DEFAULT_NOTE_TITLE) + ".md"));
+	create_note_tab(document_path, Some(template_path)).await;
+}
+
+/// Creates the note at the given path, filled with the template if one is given, and opens it in a new tab.
+async fn create_note_tab(document_path: PathBuf, template_path: Option<PathBuf>) {
+	let template = match template_path {
+		Some(template_path) => fs::read_to_string(&template_path).unwrap_or_else(|e| {
+			log::error!("Failed to read the template {template_path:?}: {e}");
+			String::new()
+		}),
+		None => String::new(),
+	};
+	let title = document_path
+		.file_stem()
+		.map(|stem| stem.to_string_lossy().into_owned())
+		.unwrap_or_default();
+	let rendered = render_template(
+		&template,
+		&TemplateContext {
+			title,
+			now: Local::now().naive_local(),
+		},
+	);
+	let cursor = rendered.cursor.unwrap_or_else(|| rendered.text.chars().count());
+
+	if let Some(parent) = document_path.parent()
+		&& let Err(e) = fs::create_dir_all(parent)
+	{
+		log::error!("Failed to create the folder {parent:?}: {e}");
+		return;
+	}
+
+	let Some(mut markdownfile) = new_file_with_content(document_path, rendered.text) else {
+		log::error!("Failed to create a new tab, due to a previous error!");
+		return;
+	};
+	markdownfile.editable.editor_mut().write().set_cursor_pos(cursor);
+
+	let file_key = FILES_ARENA.write().insert(markdownfile.clone());
+	push_tab(markdownfile.title.clone(), file_key).await;
+	switch_tab(TABS().len() - 1).await;
+	save_file(markdownfile).await;
+}
+
+/// Opens the daily note of the given date, creating it from the daily note template if it does not exist.
+pub(crate) async fn open_daily_note(date: NaiveDate) {
+	let config = DAILY_NOTES_CONFIG();
+	let trove_dir = get_default_trove_dir();
+	let path = config.note_path(&trove_dir, date);
+	if path.is_file() {
+		open_file_tab(path).await;
+		return;
+	}
+
+	let template_path = config
+		.template
+		.as_deref()
+		.map(str::trim)
+		.filter(|name| !name.is_empty())
+		.map(|name| get_templates_dir().join(format!("{name}.md")));
+	create_note_tab(path, template_path).await;
+}
+
+/// Opens the daily note of the day before or after the current daily note, or today if the current note
+/// is not a daily note.
+pub(crate) async fn open_adjacent_daily_note(offset: i64) {
+	let today = Local::now().date_naive();
+	let current_date = current_file()
+		.and_then(|file| DAILY_NOTES_CONFIG().note_date(&get_default_trove_dir(), &file.path))
+		.unwrap_or(today);
+	open_daily_note(offset_day(current_date, offset)).await;
+}
+
+/// Inserts the template at the given path at the cursor of the current note.
+pub(crate) fn insert_template_at_cursor(template_path: PathBuf) {
+	let Some(file) = current_file() else {
+		return;
+	};
+	let template = match fs::read_to_string(&template_path) {
+		Ok(template) => template,
+		Err(e) => {
+			log::error!("Failed to read the template {template_path:?}: {e}");
+			return;
+		}
+	};
+	let rendered = render_template(
+		&template,
+		&TemplateContext {
+			title: file.title.clone(),
+			now: Local::now().naive_local(),
+		},
+	);
+
+	let mut editable = file.editable;
+	let (content, cursor) = {
+		let editor = editable.editor().read();
+		(editor.to_string(), editor.cursor_pos())
+	};
+	apply_text_edit(&mut editable, insert_template(&content, cursor, &rendered));
+}
+
+/// Opens the file at the given path in a new tab, or switches to its tab if it is already open.
+pub(crate) async fn open_file_tab(path: PathBuf) {
+	if let Some(index) = TABS().iter().position(|tab| tab.file_path == path) {
+		switch_tab(index).await;
+		return;
+	}
+
+	let Some(markdownfile) = open_file_from_path(path.clone()) else {
+		log::error!("Failed to open {path:?} in a new tab!");
+		return;
+	};
+
+	let file_key = FILES_ARENA.write().insert(markdownfile.clone());
+	push_tab(markdownfile.title.clone(), file_key).await;
+	switch_tab(TABS().len() - 1).await;
+	log::debug!("Opened Tab: {}", markdownfile.title);
+}
+
 /// Closes the tab at the given index also freeing its buffer from FILES_BUFFER.
 pub async fn close_tab(index: usize) {
 	if let Some(tab) = TABS().get(index) {
@@ -107,7 +644,9 @@ pub async fn close_tab(index: usize) {
 				return;
 			}
 		}
-		save_file(FILES_ARENA().get(buffer_index).unwrap().clone()).await;
+		let markdown_file = FILES_ARENA().get(buffer_index).unwrap().clone();
+		save_file(markdown_file.clone()).await;
+		UNDO_TREES.write().remove(&markdown_file.path);
 		TABS.write().remove(index);
 		FILES_ARENA.write().remove(buffer_index);
 		log::debug!("Closed tab: {tab_title}");
@@ -123,6 +662,21 @@ pub async fn delete_tab(index: usize) {
 		return;
 	};
 
+	let markdown_file = FILES_ARENA().get(tab.file_key).unwrap().clone();
+	let content = markdown_file.editable.editor().to_string();
+	// The tab and its buffer are kept when the file could not be deleted, `delete_file` tells why.
+	if !delete_file(markdown_file).await {
+		return;
+	}
+	push_toast(
+		ToastLevel::Info,
+		format!("Deleted {}.", tab.title),
+		vec![ToastAction::UndoDelete {
+			path: tab.file_path.clone(),
+			content,
+		}],
+	);
+
 	let tab_count = TABS().len();
 	let current_tab_index = CURRENT_TAB();
 
@@ -131,7 +685,6 @@ pub async fn delete_tab(index: usize) {
 	}
 
 	TABS.write().remove(index);
-	delete_file(FILES_ARENA().get(tab.file_key).unwrap().clone()).await;
 	FILES_ARENA.write().remove(tab.file_key);
 
 	match current_tab_index {
@@ -150,6 +703,30 @@ pub async fn delete_tab(index: usize) {
 	log::debug!("Closed tab: {}", tab.title);
 }
 
+/// Writes a deleted note back to its path and opens it in a new tab.
+async fn restore_deleted_note(path: PathBuf, content: String) {
+	if path.exists() {
+		push_toast(
+			ToastLevel::Warning,
+			format!("{} exists again, it was not overwritten.", path.display()),
+			Vec::new(),
+		);
+		return;
+	}
+	if let Err(e) = tokio::fs::write(&path, &content).await {
+		log::error!("Failed to restore the deleted note {path:?}: {e}");
+		push_toast(
+			ToastLevel::Error,
+			format!("Could not restore the note: {e}"),
+			vec![ToastAction::OpenLog],
+		);
+		return;
+	}
+	TAG_INDEX.write().update_file(&path, &content);
+	TASK_INDEX.write().update_file(&path, &content);
+	open_file_tab(path).await;
+}
+
 /// Appends a tab of the given title and document index to the TABS vec.
 pub async fn push_tab(title: String, file_key: usize) {
 	let file_path = FILES_ARENA().get(file_key).unwrap().path.clone();
@@ -171,6 +748,8 @@ pub async fn switch_tab(index: usize) {
 			.editable;
 
 		*CURRENT_EDITOR_BUFFER.write() = current_tab_content;
+		EXTRA_CURSORS.write().clear();
+		VIM_STATE.write().reset();
 		log::debug!("Switched to tab: {}", tab.title.clone());
 	} else {
 		log::error!("Failed to switch to the tab: Invalid tab index! (out of bounds)")
@@ -199,18 +778,80 @@ pub static JET_BRAINS_MONO: &[u8] = include_bytes!("../static/fonts/JetBrainsMon
 
 // Stores the current App Theme
 pub static THEME_STORE: GlobalSignal<ThemesStore> = Signal::global(ThemesStore::init);
+// Theme files with the time they were last modified, to reload the themes when the directory changes.
+pub static THEMES_SNAPSHOT: GlobalSignal<Option<Vec<(PathBuf, Option<SystemTime>)>>> = Signal::global(|| None);
+// Appearance of the system as told by the window: 0 while unknown, 1 for light and 2 for dark. It is
+// written from the event loop of the window, which can not write the signals.
+static SYSTEM_APPEARANCE: AtomicU8 = AtomicU8::new(0);
+// Theme last switched to by the theme mode, a theme picked in the menu is kept until the mode wants another.
+pub static AUTO_THEME: GlobalSignal<Option<String>> = Signal::global(|| None);
 
 // Sidebar Store:
 pub static SHOW_SETTINGS_DROPUP: GlobalSignal<bool> = Signal::global(|| false);
 pub static SHOW_THEMES_DROPUP: GlobalSignal<bool> = Signal::global(|| false);
 pub static SHOW_COMMAND_PALETTE: GlobalSignal<bool> = Signal::global(|| false);
 pub static SHOW_RECENT_FILES: GlobalSignal<bool> = Signal::global(|| false);
+pub static SHOW_SHORTCUTS: GlobalSignal<bool> = Signal::global(|| false);
+pub static PALETTE_MODE: GlobalSignal<PaletteMode> = Signal::global(|| PaletteMode::Commands);
+
+// Editor Store:
+pub static SHOW_PROPERTIES: GlobalSignal<bool> = Signal::global(|| false);
+
+// Panel shown next to the editor, if any.
+pub static ACTIVE_SIDE_PANEL: GlobalSignal<Option<SidePanel>> = Signal::global(|| None);
+
+// Toasts stacked over the app, the oldest first, and the id of the next toast.
+pub static TOASTS: GlobalSignal<Vec<Toast>> = Signal::global(Vec::new);
+static NEXT_TOAST_ID: AtomicUsize = AtomicUsize::new(0);
+const MAX_TOASTS: usize = 5;
+
+// Toast Store Methods:
+/// Shows a toast over the app, the oldest toast leaving when there are too many. A toast already shown is
+/// not repeated, like the errors of the autosave.
+pub fn push_toast(level: ToastLevel, message: impl Into<String>, actions: Vec<ToastAction>) {
+	let message = message.into();
+	if TOASTS.peek().iter().any(|toast| toast.level == level && toast.message == message) {
+		return;
+	}
+	let mut toasts = TOASTS.write();
+	toasts.push(Toast {
+		id: NEXT_TOAST_ID.fetch_add(1, Ordering::Relaxed),
+		level,
+		message,
+		actions,
+	});
+	if toasts.len() > MAX_TOASTS {
+		toasts.remove(0);
+	}
+}
+
+pub fn dismiss_toast(id: usize) {
+	TOASTS.write().retain(|toast| toast.id != id);
+}
+
+/// Runs the action of a toast button and dismisses the toast.
+pub async fn run_toast_action(id: usize, action: ToastAction) {
+	dismiss_toast(id);
+	match action {
+		ToastAction::RetrySave(path) => {
+			let file = FILES_ARENA().iter().map(|(_, file)| file).find(|file| file.path == path).cloned();
+			match file {
+				Some(file) => save_file(file).await,
+				None => log::warn!("{path:?} is not open anymore, there is nothing to save."),
+			}
+		}
+		ToastAction::RetrySaveUserdata => save_userdata().await,
+		ToastAction::UndoDelete { path, content } => restore_deleted_note(path, content).await,
+		ToastAction::OpenLog => open_log_file(),
+	}
+}
 
 // Sidebar Store Methods:
 pub fn toggle_settings_dropup() {
 	let current_state = SHOW_SETTINGS_DROPUP();
 	if current_state && SHOW_THEMES_DROPUP() {
 		*SHOW_THEMES_DROPUP.write() = !current_state;
+		end_theme_preview();
 	}
 	*SHOW_SETTINGS_DROPUP.write() = !current_state;
 }
@@ -218,19 +859,114 @@ pub fn toggle_settings_dropup() {
 pub fn toggle_themes_dropup() {
 	let current = *SHOW_THEMES_DROPUP.read();
 	*SHOW_THEMES_DROPUP.write() = !current;
+	if current {
+		end_theme_preview();
+	}
 }
 
 pub fn close_settings_dropup() {
 	*SHOW_SETTINGS_DROPUP.write() = false;
 	*SHOW_THEMES_DROPUP.write() = false;
+	end_theme_preview();
+}
+
+/// Restores the theme in use before hovering the themes menu.
+pub fn end_theme_preview() {
+	if THEME_STORE.peek().is_previewing() {
+		THEME_STORE.write().end_preview();
+	}
+}
+
+/// Makes a theme of the themes menu the current theme and remembers it for the next start.
+pub async fn commit_theme(source: ThemeSource) {
+	if let Err(errors) = THEME_STORE.write().change_current_theme(source.clone()).await {
+		push_toast(
+			ToastLevel::Error,
+			format!("The theme {source} has {} errors, it was not applied.", errors.len()),
+			vec![ToastAction::OpenLog],
+		);
+		return;
+	}
+	save_userdata().await;
+}
+
+/// Saves a high contrast variant of the current theme to the themes directory and switches to it.
+pub async fn save_high_contrast_variant() {
+	end_theme_preview();
+	let variant = high_contrast_variant(&THEME_STORE.peek().current_theme);
+	let themes_dir = THEME_STORE.peek().themes_dir.clone();
+	match save_new_theme(&themes_dir, &variant) {
+		Ok(path) => {
+			let _ = THEME_STORE.write().refresh();
+			*THEMES_SNAPSHOT.write() = Some(THEME_STORE.peek().files_snapshot());
+			commit_theme(ThemeSource::File(path)).await;
+		}
+		Err(e) => {
+			log::error!("Failed to save the high contrast variant {}: {e}", variant.info.name);
+			push_toast(
+				ToastLevel::Error,
+				format!("Could not save {}: {e}", variant.info.name),
+				vec![ToastAction::OpenLog],
+			);
+		}
+	}
 }
 
 pub fn toggle_command_palette() {
 	let current = *SHOW_COMMAND_PALETTE.read();
 	*SHOW_COMMAND_PALETTE.write() = !current;
+	*PALETTE_MODE.write() = PaletteMode::Commands;
+	close_shortcuts();
+}
+
+/// Shows the command palette listing the templates of the trove.
+pub fn open_template_picker(target: TemplateTarget) {
+	*PALETTE_MODE.write() = PaletteMode::Templates(target);
+	*SHOW_COMMAND_PALETTE.write() = true;
+	close_shortcuts();
+}
+
+/// Shows the keyboard shortcuts overlay in place of the palettes, or hides it and drops the binding being
+/// recorded.
+pub fn toggle_shortcuts() {
+	let current = *SHOW_SHORTCUTS.read();
+	*SHOW_SHORTCUTS.write() = !current;
+	*SHOW_COMMAND_PALETTE.write() = false;
+	*SHOW_RECENT_FILES.write() = false;
+	cancel_binding_recording();
+	close_settings_dropup();
+}
+
+fn close_shortcuts() {
+	if *SHOW_SHORTCUTS.peek() {
+		*SHOW_SHORTCUTS.write() = false;
+		cancel_binding_recording();
+	}
 }
 
 pub fn toggle_recent_files() {
 	let current = *SHOW_RECENT_FILES.read();
 	*SHOW_RECENT_FILES.write() = !current;
+	close_shortcuts();
+}
+
+pub fn toggle_properties() {
+	let current = *SHOW_PROPERTIES.read();
+	*SHOW_PROPERTIES.write() = !current;
+}
+
+/// Shows the given side panel, or hides it if it is already shown.
+pub fn toggle_side_panel(panel: SidePanel) {
+	let current = *ACTIVE_SIDE_PANEL.read();
+	*ACTIVE_SIDE_PANEL.write() = if current == Some(panel) { None } else { Some(panel) };
+}
+
+/// Adds the tag to the tag browser filter, or removes it if it is already selected.
+pub fn toggle_selected_tag(tag: String) {
+	let mut selected_tags = SELECTED_TAGS.write();
+	if let Some(index) = selected_tags.iter().position(|selected| *selected == tag) {
+		selected_tags.remove(index);
+	} else {
+		selected_tags.push(tag);
+	}
 }
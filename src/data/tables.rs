@@ -0,0 +1,596 @@
+//! # Tables.
+//! Editing and formatting of GFM pipe tables.
+//!
+//! A table is a block of lines starting with `|`, the second one being the delimiter row which holds
+//! the alignment of the columns (`:--`, `:-:` or `--:`). Every edit re-renders the whole table with its
+//! columns aligned. Tab and Shift+Tab move between cells, Enter adds a row, and CSV or TSV data pasted
+//! in a note is converted to a table.
+
+use crate::data::{
+	outline::in_code_block,
+	text_edit::{TextEdit, line_starts},
+};
+use std::ops::Range;
+
+/// Minimum width of a column, the delimiter row needs at least three dashes.
+const MIN_COLUMN_WIDTH: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+	None,
+	Left,
+	Center,
+	Right,
+}
+
+/// Structural edits of the table under the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableCommand {
+	InsertRowAbove,
+	InsertRowBelow,
+	DeleteRow,
+	MoveRowUp,
+	MoveRowDown,
+	InsertColumnLeft,
+	InsertColumnRight,
+	DeleteColumn,
+	MoveColumnLeft,
+	MoveColumnRight,
+	Align(Alignment),
+	Format,
+}
+
+/// A table, the first row being the header.
+#[derive(Debug, Clone, PartialEq)]
+struct Table {
+	lines: Range<usize>, // Lines of the table in the document
+	rows: Vec<Vec<String>>,
+	alignments: Vec<Alignment>,
+}
+
+impl Table {
+	fn columns(&self) -> usize {
+		self.alignments.len()
+	}
+
+	fn empty_row(&self) -> Vec<String> {
+		vec![String::new(); self.columns()]
+	}
+
+	fn widths(&self) -> Vec<usize> {
+		(0..self.columns())
+			.map(|column| {
+				self.rows
+					.iter()
+					.map(|row| row[column].chars().count())
+					.max()
+					.unwrap_or(0)
+					.max(MIN_COLUMN_WIDTH)
+			})
+			.collect()
+	}
+
+	/// Renders the table with aligned columns, one string per line.
+	fn render(&self) -> Vec<String> {
+		let widths = self.widths();
+		let render_row = |row: &Vec<String>| {
+			let cells: Vec<String> = row
+				.iter()
+				.zip(&widths)
+				.zip(&self.alignments)
+				.map(|((cell, width), alignment)| {
+					let (left, right) = padding(cell.chars().count(), *width, *alignment);
+					format!("{}{cell}{}", " ".repeat(left), " ".repeat(right))
+				})
+				.collect();
+			format!("| {} |", cells.join(" | "))
+		};
+		let delimiters: Vec<String> = widths
+			.iter()
+			.zip(&self.alignments)
+			.map(|(width, alignment)| match alignment {
+				Alignment::None => "-".repeat(*width),
+				Alignment::Left => format!(":{}", "-".repeat(width - 1)),
+				Alignment::Center => format!(":{}:", "-".repeat(width - 2)),
+				Alignment::Right => format!("{}:", "-".repeat(width - 1)),
+			})
+			.collect();
+
+		let mut lines = vec![render_row(&self.rows[0]), format!("| {} |", delimiters.join(" | "))];
+		lines.extend(self.rows[1..].iter().map(render_row));
+		lines
+	}
+
+	/// Returns the row of the table on the given line of the document, the delimiter row belongs to the header.
+	fn row_of_line(&self, line: usize) -> usize {
+		match line - self.lines.start {
+			0 | 1 => 0,
+			row => row - 1,
+		}
+	}
+
+	/// Returns the column of the start of the content of a cell in its rendered line, and its length.
+	fn cell_position(&self, row: usize, column: usize) -> (usize, usize) {
+		let widths = self.widths();
+		let length = self.rows[row][column].chars().count();
+		let (left, _) = padding(length, widths[column], self.alignments[column]);
+		let start = 2 + widths[..column].iter().map(|width| width + 3).sum::<usize>() + left;
+		(start, length)
+	}
+}
+
+/// Spaces on the left and on the right of a cell content.
+fn padding(length: usize, width: usize, alignment: Alignment) -> (usize, usize) {
+	let space = width.saturating_sub(length);
+	match alignment {
+		Alignment::Right => (space, 0),
+		Alignment::Center => (space / 2, space - space / 2),
+		Alignment::None | Alignment::Left => (0, space),
+	}
+}
+
+fn is_table_line(line: &str) -> bool {
+	line.trim_start().starts_with('|')
+}
+
+/// Splits a table row in its cells, escaped pipes (`\|`) are part of the cells.
+fn split_row(line: &str) -> Vec<String> {
+	let line = line.trim();
+	let line = line.strip_prefix('|').unwrap_or(line);
+	let line = match line.strip_suffix('|') {
+		Some(stripped) if !stripped.ends_with('\\') => stripped,
+		_ => line,
+	};
+
+	let mut cells = Vec::new();
+	let mut cell = String::new();
+	let mut escaped = false;
+	for ch in line.chars() {
+		if ch == '|' && !escaped {
+			cells.push(cell.trim().to_string());
+			cell.clear();
+		} else {
+			cell.push(ch);
+		}
+		escaped = ch == '\\' && !escaped;
+	}
+	cells.push(cell.trim().to_string());
+	cells
+}
+
+fn parse_alignment(cell: &str) -> Option<Alignment> {
+	let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+	if dashes.is_empty() || !dashes.chars().all(|ch| ch == '-') {
+		return None;
+	}
+	Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+		(true, true) => Alignment::Center,
+		(true, false) => Alignment::Left,
+		(false, true) => Alignment::Right,
+		(false, false) => Alignment::None,
+	})
+}
+
+/// Parses the table containing the given line, if any.
+fn find_table(lines: &[&str], row: usize) -> Option<Table> {
+	if !is_table_line(lines.get(row)?) {
+		return None;
+	}
+	let start = (0..row).rev().take_while(|row| is_table_line(lines[*row])).last().unwrap_or(row);
+	let end = (row..lines.len()).take_while(|row| is_table_line(lines[*row])).last()? + 1;
+	if end - start < 2 {
+		return None;
+	}
+
+	let alignments: Vec<Alignment> = split_row(lines[start + 1])
+		.iter()
+		.map(|cell| parse_alignment(cell))
+		.collect::<Option<_>>()?;
+	let mut rows: Vec<Vec<String>> = std::iter::once(start)
+		.chain(start + 2..end)
+		.map(|row| split_row(lines[row]))
+		.collect();
+
+	// Rows with missing cells are completed, and columns only some rows have are kept.
+	let columns = rows.iter().map(Vec::len).max().unwrap_or(0).max(alignments.len());
+	let mut table = Table {
+		lines: start..end,
+		rows: Vec::new(),
+		alignments,
+	};
+	table.alignments.resize(columns, Alignment::None);
+	rows.iter_mut().for_each(|row| row.resize(columns, String::new()));
+	table.rows = rows;
+	Some(table)
+}
+
+/// Returns the index of the cell at the given column of a table line, counting the cells the line has
+/// beyond the columns of its table.
+pub fn cell_index(line: &str, column: usize) -> usize {
+	let mut pipes: usize = 0;
+	let mut escaped = false;
+	for ch in line.chars().take(column) {
+		if ch == '|' && !escaped {
+			pipes += 1;
+		}
+		escaped = ch == '\\' && !escaped;
+	}
+	// The leading pipe opens the first cell.
+	pipes.saturating_sub(1)
+}
+
+/// Returns the index of the cell at the given column of a table line.
+fn cell_at(line: &str, column: usize, columns: usize) -> usize {
+	cell_index(line, column).min(columns - 1)
+}
+
+/// Finds the table under the cursor, lets `change` edit it and move the cursor to another cell, then
+/// renders the table back in the document with the content of the target cell selected. Lines starting with
+/// `|` in a fenced code block are not a table.
+fn edit_table(text: &str, cursor: usize, change: impl FnOnce(&mut Table, &mut (usize, usize)) -> bool) -> Option<TextEdit> {
+	if in_code_block(text, cursor) {
+		return None;
+	}
+	let starts = line_starts(text);
+	let row = starts.partition_point(|start| *start <= cursor).saturating_sub(1);
+	let lines: Vec<&str> = text.split('\n').collect();
+	let mut table = find_table(&lines, row)?;
+
+	let mut target = (table.row_of_line(row), cell_at(lines[row], cursor - starts[row], table.columns()));
+	if !change(&mut table, &mut target) {
+		return None;
+	}
+
+	let mut new_lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+	new_lines.splice(table.lines.clone(), table.render());
+	let new_text = new_lines.join("\n");
+
+	let (target_row, target_column) = target;
+	let target_line = table.lines.start + if target_row == 0 { 0 } else { target_row + 1 };
+	let (column, length) = table.cell_position(target_row, target_column);
+	let start = line_starts(&new_text)[target_line] + column;
+
+	let edit = TextEdit::between(text, &new_text).with_cursor(start + length);
+	Some(if length > 0 {
+		edit.with_selection(start, start + length)
+	} else {
+		edit
+	})
+}
+
+/// Re-aligns the table the cursor was in once it leaves a cell, keeping the cursor at the start of the cell
+/// it moved to. Returns `None` if the cursor is still in the same cell or the table is already aligned.
+pub fn realign_table(text: &str, previous_cursor: usize, cursor: usize) -> Option<TextEdit> {
+	if in_code_block(text, previous_cursor) {
+		return None;
+	}
+	let starts = line_starts(text);
+	let row_of = |index: usize| starts.partition_point(|start| *start <= index).saturating_sub(1);
+	let lines: Vec<&str> = text.split('\n').collect();
+	let (previous_row, row) = (row_of(previous_cursor), row_of(cursor));
+	let table = find_table(&lines, previous_row)?;
+
+	let cell_of = |row: usize, index: usize| cell_at(lines[row], index - starts[row], table.columns());
+	let in_table = table.lines.contains(&row);
+	if in_table
+		&& table.row_of_line(row) == table.row_of_line(previous_row)
+		&& cell_of(row, cursor) == cell_of(previous_row, previous_cursor)
+	{
+		return None;
+	}
+	let rendered = table.render();
+	if rendered.iter().zip(&lines[table.lines.clone()]).all(|(new, old)| new == old) {
+		return None;
+	}
+
+	let mut new_lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+	new_lines.splice(table.lines.clone(), rendered);
+	let new_text = new_lines.join("\n");
+	let new_cursor = if in_table {
+		let (column, _) = table.cell_position(table.row_of_line(row), cell_of(row, cursor));
+		line_starts(&new_text)[row] + column
+	} else if row >= table.lines.end {
+		cursor + new_text.chars().count() - text.chars().count()
+	} else {
+		cursor
+	};
+	Some(TextEdit::between(text, &new_text).with_cursor(new_cursor))
+}
+
+/// Moves to the next (or previous) cell, adding a row when moving forward from the last cell.
+pub fn move_to_cell(text: &str, cursor: usize, backwards: bool) -> Option<TextEdit> {
+	edit_table(text, cursor, |table, (row, column)| {
+		let columns = table.columns();
+		if backwards {
+			if *column > 0 {
+				*column -= 1;
+			} else if *row > 0 {
+				*row -= 1;
+				*column = columns - 1;
+			}
+		} else if *column + 1 < columns {
+			*column += 1;
+		} else {
+			if *row + 1 == table.rows.len() {
+				table.rows.push(table.empty_row());
+			}
+			*row += 1;
+			*column = 0;
+		}
+		true
+	})
+}
+
+/// Adds a row below the current one and moves to its first cell.
+pub fn add_row(text: &str, cursor: usize) -> Option<TextEdit> {
+	edit_table(text, cursor, |table, (row, column)| {
+		table.rows.insert(*row + 1, table.empty_row());
+		*row += 1;
+		*column = 0;
+		true
+	})
+}
+
+/// Runs a table command on the table under the cursor.
+pub fn run_table_command(text: &str, cursor: usize, command: TableCommand) -> Option<TextEdit> {
+	edit_table(text, cursor, |table, (row, column)| {
+		let (r, c) = (*row, *column);
+		match command {
+			TableCommand::InsertRowAbove | TableCommand::InsertRowBelow => {
+				// Rows can not be added above the header.
+				let index = if command == TableCommand::InsertRowAbove && r > 0 {
+					r
+				} else {
+					r + 1
+				};
+				table.rows.insert(index, table.empty_row());
+				*row = index;
+			}
+			TableCommand::DeleteRow if r > 0 => {
+				table.rows.remove(r);
+				*row = r.min(table.rows.len() - 1);
+			}
+			TableCommand::MoveRowUp if r > 1 => {
+				table.rows.swap(r, r - 1);
+				*row = r - 1;
+			}
+			TableCommand::MoveRowDown if r > 0 && r + 1 < table.rows.len() => {
+				table.rows.swap(r, r + 1);
+				*row = r + 1;
+			}
+			TableCommand::InsertColumnLeft | TableCommand::InsertColumnRight => {
+				let index = if command == TableCommand::InsertColumnLeft { c } else { c + 1 };
+				table.rows.iter_mut().for_each(|cells| cells.insert(index, String::new()));
+				table.alignments.insert(index, Alignment::None);
+				*column = index;
+			}
+			TableCommand::DeleteColumn if table.columns() > 1 => {
+				table.rows.iter_mut().for_each(|cells| {
+					cells.remove(c);
+				});
+				table.alignments.remove(c);
+				*column = c.min(table.columns() - 1);
+			}
+			TableCommand::MoveColumnLeft if c > 0 => {
+				table.rows.iter_mut().for_each(|cells| cells.swap(c, c - 1));
+				table.alignments.swap(c, c - 1);
+				*column = c - 1;
+			}
+			TableCommand::MoveColumnRight if c + 1 < table.columns() => {
+				table.rows.iter_mut().for_each(|cells| cells.swap(c, c + 1));
+				table.alignments.swap(c, c + 1);
+				*column = c + 1;
+			}
+			TableCommand::Align(alignment) => table.alignments[c] = alignment,
+			TableCommand::Format => (),
+			_ => return false,
+		}
+		true
+	})
+}
+
+/// Converts CSV or TSV text to a markdown table, the first row being the header. Returns `None` if the
+/// text does not look like delimited data.
+///
+/// Every line of TSV has a tab. Commas are also found in prose, so CSV must have a header of distinct
+/// names, and no space after its commas, which prose has.
+pub fn delimited_to_table(text: &str) -> Option<String> {
+	let text = text.trim_matches(['\n', '\r']);
+	if text.contains('|') {
+		return None;
+	}
+	let lines: Vec<&str> = text.lines().collect();
+	if lines.len() < 2 {
+		return None;
+	}
+	let tabbed = lines.iter().all(|line| line.contains('\t'));
+	let delimiter = if tabbed { '\t' } else { ',' };
+
+	let fields: Vec<Vec<String>> = lines.iter().map(|line| split_delimited(line, delimiter)).collect();
+	let columns = fields[0].len();
+	if columns < 2 || fields.iter().any(|row| row.len() != columns) {
+		return None;
+	}
+	if !tabbed {
+		let spaced = fields.iter().any(|row| row[1..].iter().any(|field| field.starts_with([' ', '\t'])));
+		let header: std::collections::HashSet<&str> = fields[0].iter().map(|name| name.trim()).collect();
+		if spaced || header.contains("") || header.len() != columns {
+			return None;
+		}
+	}
+	let rows: Vec<Vec<String>> = fields
+		.into_iter()
+		.map(|row| row.into_iter().map(|field| field.trim().to_string()).collect())
+		.collect();
+
+	let table = Table {
+		lines: 0..0,
+		rows,
+		alignments: vec![Alignment::None; columns],
+	};
+	Some(table.render().join("\n"))
+}
+
+/// Splits a line of delimited data, fields can be quoted with `"` and quotes are doubled inside them. The
+/// whitespace around the fields is kept.
+fn split_delimited(line: &str, delimiter: char) -> Vec<String> {
+	let mut fields = Vec::new();
+	let mut field = String::new();
+	let mut quoted = false;
+	let mut chars = line.trim_end_matches('\r').chars().peekable();
+
+	while let Some(ch) = chars.next() {
+		match ch {
+			'"' if quoted && chars.peek() == Some(&'"') => {
+				field.push('"');
+				chars.next();
+			}
+			'"' if quoted || field.trim().is_empty() => quoted = !quoted,
+			ch if ch == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+			ch => field.push(ch),
+		}
+	}
+	fields.push(field);
+	fields
+}
+
+/// Builds the edit pasting CSV or TSV data over the given range as a markdown table, on its own lines.
+/// Data pasted in a fenced code block is left as it is.
+pub fn paste_as_table(text: &str, range: Range<usize>, pasted: &str) -> Option<TextEdit> {
+	if in_code_block(text, range.start) {
+		return None;
+	}
+	let table = delimited_to_table(pasted)?;
+	let before = range.start.checked_sub(1).and_then(|index| text.chars().nth(index));
+	let after = text.chars().nth(range.end);
+	let leading = if before.is_some_and(|ch| ch != '\n') { "\n" } else { "" };
+	let trailing = if after.is_some_and(|ch| ch != '\n') { "\n" } else { "" };
+	Some(TextEdit::replace(range, format!("{leading}{table}{trailing}")))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn converts_csv_with_a_header() {
+		assert_eq!(
+			delimited_to_table("name,age\nAda,36\n\"Smith, John\",41").as_deref(),
+			Some("| name        | age |\n| ----------- | --- |\n| Ada         | 36  |\n| Smith, John | 41  |")
+		);
+	}
+
+	#[test]
+	fn converts_tsv() {
+		assert_eq!(
+			delimited_to_table("a\tb\n1\t2").as_deref(),
+			Some("| a   | b   |\n| --- | --- |\n| 1   | 2   |")
+		);
+	}
+
+	#[test]
+	fn leaves_prose_with_commas() {
+		assert_eq!(delimited_to_table("Hello, world\nFoo, bar"), None);
+		assert_eq!(delimited_to_table("one line, with a comma"), None);
+		assert_eq!(delimited_to_table("a,b,c\n1,2"), None);
+	}
+
+	#[test]
+	fn needs_a_header_of_distinct_names() {
+		assert_eq!(delimited_to_table("a,a\n1,2"), None);
+		assert_eq!(delimited_to_table("a,\n1,2"), None);
+	}
+
+	#[test]
+	fn pastes_on_its_own_lines() {
+		let edit = paste_as_table("before after", 6..7, "a,b\n1,2").expect("a table");
+		assert_eq!(edit.text, "\n| a   | b   |\n| --- | --- |\n| 1   | 2   |\n");
+	}
+
+	#[test]
+	fn does_not_paste_in_fenced_code() {
+		let text = "```csv\n\n```\n";
+		assert_eq!(paste_as_table(text, 7..7, "a,b\n1,2"), None);
+		assert_eq!(paste_as_table(text, 0..0, "a,b\n1,2"), None);
+		assert!(paste_as_table(text, 12..12, "a,b\n1,2").is_some());
+	}
+
+	#[test]
+	fn finds_the_cell_of_a_column() {
+		let line = "| a | b \\| c | d |";
+		assert_eq!(cell_index(line, 0), 0);
+		assert_eq!(cell_index(line, 2), 0);
+		assert_eq!(cell_index(line, 6), 1);
+		assert_eq!(cell_index(line, 12), 1);
+		assert_eq!(cell_index(line, 15), 2);
+	}
+
+	const TABLE: &str = "| a | b |\n| --- | --- |\n| 1 | 2 |";
+
+	fn apply(text: &str, edit: TextEdit) -> String {
+		let mut chars: Vec<char> = text.chars().collect();
+		chars.splice(edit.range, edit.text.chars());
+		chars.into_iter().collect()
+	}
+
+	#[test]
+	fn moves_between_cells() {
+		let edit = move_to_cell(TABLE, 2, false).expect("an edit");
+		assert_eq!(edit.selection, Some((8, 9)));
+		assert_eq!(apply(TABLE, edit), "| a   | b   |\n| --- | --- |\n| 1   | 2   |");
+
+		// Backwards from the first cell of a row to the last cell of the row above.
+		let edit = move_to_cell(TABLE, 26, true).expect("an edit");
+		assert_eq!(edit.selection, Some((8, 9)));
+	}
+
+	#[test]
+	fn moving_forward_from_the_last_cell_adds_a_row() {
+		let edit = move_to_cell(TABLE, 30, false).expect("an edit");
+		assert_eq!((edit.cursor, edit.selection), (44, None));
+		assert_eq!(apply(TABLE, edit), "| a   | b   |\n| --- | --- |\n| 1   | 2   |\n|     |     |");
+	}
+
+	#[test]
+	fn adds_a_row_below_the_current_one() {
+		let edit = add_row(TABLE, 2).expect("an edit");
+		assert_eq!(edit.cursor, 30);
+		assert_eq!(apply(TABLE, edit), "| a   | b   |\n| --- | --- |\n|     |     |\n| 1   | 2   |");
+		assert_eq!(add_row("no table", 2), None);
+	}
+
+	#[test]
+	fn edits_the_table_under_the_cursor() {
+		let edit = run_table_command(TABLE, 6, TableCommand::Align(Alignment::Center)).expect("an edit");
+		assert_eq!(apply(TABLE, edit), "| a   |  b  |\n| --- | :-: |\n| 1   |  2  |");
+		let edit = run_table_command(TABLE, 2, TableCommand::DeleteColumn).expect("an edit");
+		assert_eq!(apply(TABLE, edit), "| b   |\n| --- |\n| 2   |");
+		// The header can not be deleted.
+		assert_eq!(run_table_command(TABLE, 2, TableCommand::DeleteRow), None);
+	}
+
+	#[test]
+	fn realigns_once_the_cursor_leaves_its_cell() {
+		assert_eq!(realign_table(TABLE, 2, 3), None);
+		let edit = realign_table(TABLE, 2, 26).expect("an edit");
+		assert_eq!(edit.cursor, 30);
+		assert_eq!(apply(TABLE, edit), "| a   | b   |\n| --- | --- |\n| 1   | 2   |");
+
+		// The cursor below the table moves with the text.
+		let text = format!("{TABLE}\nafter");
+		let edit = realign_table(&text, 2, 36).expect("an edit");
+		assert_eq!(edit.cursor, 44);
+
+		let aligned = "| a   | b   |\n| --- | --- |";
+		assert_eq!(realign_table(aligned, 2, 8), None);
+	}
+
+	#[test]
+	fn does_not_edit_tables_in_fenced_code() {
+		let text = format!("```\n{TABLE}\n```");
+		assert_eq!(move_to_cell(&text, 6, false), None);
+		assert_eq!(add_row(&text, 6), None);
+		assert_eq!(run_table_command(&text, 6, TableCommand::Format), None);
+		assert_eq!(realign_table(&text, 6, 30), None);
+	}
+}
@@ -0,0 +1,270 @@
+//! # Tags.
+//! Parsing of inline `#tags` and front matter tags, and the trove wide tag index.
+//!
+//! Tags are case insensitive and stored in lowercase without the leading `#`. Nested tags like
+//! `#area/sub` also count towards all their parents (`area`).
+
+use crate::data::frontmatter::{PropertyValue, parse_front_matter};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Maximum number of suggestions returned by the tag autocomplete.
+const MAX_TAG_SUGGESTIONS: usize = 8;
+
+/// Index of the tags used by every note in the trove.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagIndex {
+	files: BTreeMap<PathBuf, BTreeSet<String>>,
+}
+
+impl TagIndex {
+	/// Parses the content of the file and replaces its entry in the index.
+	pub fn update_file(&mut self, path: &Path, content: &str) {
+		self.set_tags(path, parse_tags(content));
+	}
+
+	/// Replaces the tags of the file in the index.
+	pub fn set_tags(&mut self, path: &Path, tags: BTreeSet<String>) {
+		if tags.is_empty() {
+			self.files.remove(path);
+		} else {
+			self.files.insert(path.to_path_buf(), tags);
+		}
+	}
+
+	/// Returns true if the index holds these tags for the file.
+	pub fn has_tags(&self, path: &Path, tags: &BTreeSet<String>) -> bool {
+		self.files.get(path).map_or(tags.is_empty(), |indexed| indexed == tags)
+	}
+
+	pub fn remove_file(&mut self, path: &Path) {
+		self.files.remove(path);
+	}
+
+	pub fn rename_file(&mut self, old_path: &Path, new_path: &Path) {
+		if let Some(tags) = self.files.remove(old_path) {
+			self.files.insert(new_path.to_path_buf(), tags);
+		}
+	}
+
+	/// Returns every tag with the number of files using it, parent tags count the files of their children too.
+	pub fn tag_counts(&self) -> BTreeMap<String, usize> {
+		let mut counts = BTreeMap::new();
+		for tags in self.files.values() {
+			// A file tagged with both `#area` and `#area/sub` must only be counted once for `area`.
+			let expanded: BTreeSet<&str> = tags.iter().flat_map(|tag| tag_ancestors(tag)).collect();
+			for tag in expanded {
+				*counts.entry(tag.to_string()).or_insert(0) += 1;
+			}
+		}
+		counts
+	}
+
+	/// Returns the files that have all of the given tags (or one of their nested tags).
+	pub fn files_with_tags(&self, query: &[String]) -> Vec<PathBuf> {
+		self.files
+			.iter()
+			.filter(|(_, tags)| query.iter().all(|wanted| tags.iter().any(|tag| tag_matches(tag, wanted))))
+			.map(|(path, _)| path.clone())
+			.collect()
+	}
+
+	/// Returns the known tags starting with the given prefix, most used first.
+	pub fn complete(&self, prefix: &str) -> Vec<String> {
+		let prefix = prefix.to_lowercase();
+		let mut matches: Vec<(String, usize)> = self
+			.tag_counts()
+			.into_iter()
+			.filter(|(tag, _)| tag.starts_with(&prefix) && *tag != prefix)
+			.collect();
+		matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+		matches.into_iter().take(MAX_TAG_SUGGESTIONS).map(|(tag, _)| tag).collect()
+	}
+}
+
+/// Returns true if the tag is the wanted tag or one of its nested tags.
+pub fn tag_matches(tag: &str, wanted: &str) -> bool {
+	tag == wanted || (tag.starts_with(wanted) && tag[wanted.len()..].starts_with('/'))
+}
+
+/// Returns the tag and all of its parents, `a/b/c` gives `a`, `a/b` and `a/b/c`.
+fn tag_ancestors(tag: &str) -> impl Iterator<Item = &str> {
+	tag.match_indices('/').map(|(index, _)| &tag[..index]).chain(std::iter::once(tag))
+}
+
+/// Extracts every tag used in a note, both from the front matter and from the body.
+pub fn parse_tags(content: &str) -> BTreeSet<String> {
+	let mut tags = BTreeSet::new();
+
+	let body = match parse_front_matter(content) {
+		Some(front_matter) => {
+			for value in ["tags", "tag"].into_iter().filter_map(|key| front_matter.get(key)) {
+				match value {
+					PropertyValue::List(items) => items.iter().for_each(|item| push_tag(&mut tags, item)),
+					PropertyValue::Text(text) => text.split([',', ' ']).for_each(|item| push_tag(&mut tags, item)),
+					_ => (),
+				}
+			}
+			front_matter.body(content)
+		}
+		None => content,
+	};
+
+	let mut in_code_block = false;
+	for line in body.lines() {
+		let trimmed = line.trim_start();
+		if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+			in_code_block = !in_code_block;
+			continue;
+		}
+		if !in_code_block {
+			inline_tags(line, &mut tags);
+		}
+	}
+
+	tags
+}
+
+/// Adds a front matter tag value to the set, stripping quotes and the optional `#`.
+fn push_tag(tags: &mut BTreeSet<String>, value: &str) {
+	let tag = value.trim().trim_matches(['"', '\'']).trim_start_matches('#');
+	if is_valid_tag(tag) {
+		tags.insert(tag.to_lowercase());
+	}
+}
+
+/// Collects the inline tags of a single line, ignoring inline code spans.
+fn inline_tags(line: &str, tags: &mut BTreeSet<String>) {
+	let chars: Vec<char> = line.chars().collect();
+	let mut in_code_span = false;
+	let mut index = 0;
+
+	while index < chars.len() {
+		let ch = chars[index];
+		if ch == '`' {
+			in_code_span = !in_code_span;
+		} else if ch == '#' && !in_code_span && is_tag_boundary(index.checked_sub(1).map(|i| chars[i])) {
+			let end = (index + 1..chars.len()).find(|&i| !is_tag_char(chars[i])).unwrap_or(chars.len());
+			let tag: String = chars[index + 1..end].iter().collect();
+			let tag = tag.trim_end_matches('/');
+			if is_valid_tag(tag) {
+				tags.insert(tag.to_lowercase());
+			}
+			index = end;
+			continue;
+		}
+		index += 1;
+	}
+}
+
+/// Returns the column where the tag being typed at the given column starts (after the `#`) and the typed prefix.
+pub fn tag_prefix_at(line: &str, column: usize) -> Option<(usize, String)> {
+	let chars: Vec<char> = line.chars().collect();
+	if column > chars.len() {
+		return None;
+	}
+	let start = (0..column).rev().find(|&i| !is_tag_char(chars[i])).map(|i| i + 1).unwrap_or(0);
+	if start == 0 || chars[start - 1] != '#' || !is_tag_boundary(start.checked_sub(2).map(|i| chars[i])) {
+		return None;
+	}
+	// Do not suggest tags inside inline code spans.
+	if chars[..start - 1].iter().filter(|&&ch| ch == '`').count() % 2 == 1 {
+		return None;
+	}
+	Some((start, chars[start..column].iter().collect()))
+}
+
+fn is_tag_char(ch: char) -> bool {
+	ch.is_alphanumeric() || matches!(ch, '_' | '-' | '/')
+}
+
+fn is_tag_boundary(previous: Option<char>) -> bool {
+	previous.is_none_or(|ch| ch.is_whitespace() || matches!(ch, '(' | '[' | '{' | ',' | ';' | '"' | '\''))
+}
+
+/// A valid tag is not empty, is not only made of digits (`#1` is not a tag) and has no empty segments.
+fn is_valid_tag(tag: &str) -> bool {
+	!tag.is_empty()
+		&& !tag.chars().all(|ch| ch.is_ascii_digit())
+		&& tag.chars().all(is_tag_char)
+		&& tag.split('/').all(|segment| !segment.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn tags(content: &str) -> Vec<String> {
+		parse_tags(content).into_iter().collect()
+	}
+
+	#[test]
+	fn parses_inline_tags() {
+		assert_eq!(tags("Some #Rust and (#area/Sub) #rust"), vec!["area/sub", "rust"]);
+		assert_eq!(tags("#heading-like at the start, #todo/"), vec!["heading-like", "todo"]);
+		// Numbers, anchors, headings and code are not tags.
+		assert!(tags("Issue #12, page.html#anchor, # Heading, `#code`").is_empty());
+		assert!(tags("```\n#fenced\n```\n~~~\n#tilde\n~~~").is_empty());
+		assert!(tags("#a//b").is_empty());
+	}
+
+	#[test]
+	fn parses_front_matter_tags() {
+		assert_eq!(tags("---\ntags: [one, \"#Two\"]\n---\n#three"), vec!["one", "three", "two"]);
+		assert_eq!(tags("---\ntitle: x\ntags:\n  - one\n  - 'two'\nalias: y\n---"), vec!["one", "two"]);
+		assert_eq!(tags("---\ntag: one two\n---"), vec!["one", "two"]);
+		// Only the first line opens a front matter.
+		assert!(tags("\n---\ntags: [one]\n---").is_empty());
+	}
+
+	#[test]
+	fn counts_nested_tags_towards_their_parents() {
+		let mut index = TagIndex::default();
+		index.update_file(Path::new("a.md"), "#area #area/sub");
+		index.update_file(Path::new("b.md"), "#area/sub/deep #other");
+		let counts = index.tag_counts();
+		assert_eq!(counts["area"], 2);
+		assert_eq!(counts["area/sub"], 2);
+		assert_eq!(counts["area/sub/deep"], 1);
+		assert_eq!(index.files_with_tags(&["area/sub".to_string()]).len(), 2);
+		assert_eq!(
+			index.files_with_tags(&["area".to_string(), "other".to_string()]),
+			vec![PathBuf::from("b.md")]
+		);
+		assert!(index.files_with_tags(&["are".to_string()]).is_empty());
+	}
+
+	#[test]
+	fn updates_renames_and_removes_files() {
+		let mut index = TagIndex::default();
+		index.update_file(Path::new("a.md"), "#one");
+		index.rename_file(Path::new("a.md"), Path::new("b.md"));
+		assert_eq!(index.files_with_tags(&["one".to_string()]), vec![PathBuf::from("b.md")]);
+		index.update_file(Path::new("b.md"), "no tags");
+		assert!(index.tag_counts().is_empty());
+		index.update_file(Path::new("c.md"), "#two");
+		assert!(index.has_tags(Path::new("c.md"), &parse_tags("#two")));
+		assert!(!index.has_tags(Path::new("c.md"), &parse_tags("#two #three")));
+		index.remove_file(Path::new("c.md"));
+		assert!(index.has_tags(Path::new("c.md"), &BTreeSet::new()));
+		assert_eq!(index, TagIndex::default());
+	}
+
+	#[test]
+	fn completes_the_most_used_tags_first() {
+		let mut index = TagIndex::default();
+		index.update_file(Path::new("a.md"), "#project #programming");
+		index.update_file(Path::new("b.md"), "#programming");
+		assert_eq!(index.complete("PR"), vec!["programming", "project"]);
+		assert!(index.complete("programming").is_empty());
+	}
+
+	#[test]
+	fn finds_the_tag_being_typed() {
+		assert_eq!(tag_prefix_at("see #ru", 7), Some((5, "ru".to_string())));
+		assert_eq!(tag_prefix_at("#", 1), Some((1, String::new())));
+		assert_eq!(tag_prefix_at("a#ru", 4), None);
+		assert_eq!(tag_prefix_at("`#ru", 4), None);
+		assert_eq!(tag_prefix_at("#ru", 9), None);
+	}
+}
@@ -0,0 +1,217 @@
+//! # Tasks.
+//! GFM task list items (`- [ ] Task` and `- [x] Done`) and the trove wide task index.
+//!
+//! The due date of a task is written in its text, either as `📅 2026-10-20` or as `due:2026-10-20`.
+
+use crate::data::{frontmatter::parse_front_matter, outline::is_fence, text_edit::TextEdit};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+	pub path: PathBuf,
+	pub line: usize,
+	pub text: String,
+	pub done: bool,
+	pub due: Option<NaiveDate>,
+}
+
+/// Which tasks are shown, depending on whether they are done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFilter {
+	All,
+	Open,
+	Done,
+}
+
+/// Which tasks are shown, depending on their due date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueFilter {
+	Any,
+	Overdue,
+	Today,
+	Upcoming,
+	NoDate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskFilter {
+	pub status: StatusFilter,
+	pub due: DueFilter,
+}
+
+impl TaskFilter {
+	pub fn matches(&self, task: &Task, today: NaiveDate) -> bool {
+		let status = match self.status {
+			StatusFilter::All => true,
+			StatusFilter::Open => !task.done,
+			StatusFilter::Done => task.done,
+		};
+		let due = match self.due {
+			DueFilter::Any => true,
+			DueFilter::Overdue => task.due.is_some_and(|due| due < today),
+			DueFilter::Today => task.due == Some(today),
+			DueFilter::Upcoming => task.due.is_some_and(|due| due > today),
+			DueFilter::NoDate => task.due.is_none(),
+		};
+		status && due
+	}
+}
+
+/// Index of the tasks of every note in the trove.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskIndex {
+	files: BTreeMap<PathBuf, Vec<Task>>,
+}
+
+impl TaskIndex {
+	/// Parses the content of the file and replaces its entry in the index.
+	pub fn update_file(&mut self, path: &Path, content: &str) {
+		let tasks = parse_tasks(path, content);
+		if tasks.is_empty() {
+			self.files.remove(path);
+		} else {
+			self.files.insert(path.to_path_buf(), tasks);
+		}
+	}
+
+	pub fn remove_file(&mut self, path: &Path) {
+		self.files.remove(path);
+	}
+
+	pub fn rename_file(&mut self, old_path: &Path, new_path: &Path) {
+		if let Some(mut tasks) = self.files.remove(old_path) {
+			tasks.iter_mut().for_each(|task| task.path = new_path.to_path_buf());
+			self.files.insert(new_path.to_path_buf(), tasks);
+		}
+	}
+
+	/// Returns the tasks matching the filter, open tasks first, then by due date and by file.
+	pub fn tasks(&self, filter: &TaskFilter, today: NaiveDate) -> Vec<Task> {
+		let mut tasks: Vec<Task> = self
+			.files
+			.values()
+			.flatten()
+			.filter(|task| filter.matches(task, today))
+			.cloned()
+			.collect();
+		// Tasks without a due date come after the dated ones.
+		tasks.sort_by(|a, b| {
+			a.done
+				.cmp(&b.done)
+				.then_with(|| a.due.is_none().cmp(&b.due.is_none()))
+				.then_with(|| a.due.cmp(&b.due))
+				.then_with(|| a.path.cmp(&b.path))
+				.then_with(|| a.line.cmp(&b.line))
+		});
+		tasks
+	}
+}
+
+/// Extracts the tasks of a note, ignoring the front matter and fenced code blocks.
+pub fn parse_tasks(path: &Path, content: &str) -> Vec<Task> {
+	let skipped_lines = parse_front_matter(content)
+		.map(|front_matter| content[..content.len() - front_matter.body(content).len()].lines().count())
+		.unwrap_or(0);
+
+	let mut tasks = Vec::new();
+	let mut in_code_block = false;
+	for (line_index, line) in content.split('\n').enumerate().skip(skipped_lines) {
+		if is_fence(line) {
+			in_code_block = !in_code_block;
+			continue;
+		}
+		if in_code_block {
+			continue;
+		}
+		if let Some(checkbox) = find_checkbox(line) {
+			let text = line[checkbox + 3..].trim().to_string();
+			tasks.push(Task {
+				path: path.to_path_buf(),
+				line: line_index,
+				due: parse_due_date(&text),
+				done: line[checkbox + 1..].starts_with(['x', 'X']),
+				text,
+			});
+		}
+	}
+	tasks
+}
+
+/// Returns the byte index of the `[` of the checkbox if the line is a task list item.
+fn find_checkbox(line: &str) -> Option<usize> {
+	let trimmed = line.trim_start();
+	let indent = line.len() - trimmed.len();
+	let marker_len = if trimmed.starts_with(['-', '*', '+']) {
+		1
+	} else {
+		let digits = trimmed.chars().take_while(|ch| ch.is_ascii_digit()).count();
+		if digits == 0 || !trimmed[digits..].starts_with(['.', ')']) {
+			return None;
+		}
+		digits + 1
+	};
+
+	let after_marker = &trimmed[marker_len..];
+	let rest = after_marker.trim_start_matches(' ');
+	let spaces = after_marker.len() - rest.len();
+	let is_checkbox = spaces > 0
+		&& (rest.starts_with("[ ]") || rest.starts_with("[x]") || rest.starts_with("[X]"))
+		&& (rest.len() == 3 || rest[3..].starts_with([' ', '\t']));
+	is_checkbox.then_some(indent + marker_len + spaces)
+}
+
+/// Parses the due date of a task, written as `📅 2026-10-20` or `due:2026-10-20`. The `due:` marker has to start a
+/// word, so `overdue:2026-10-20` is not a due date.
+pub fn parse_due_date(text: &str) -> Option<NaiveDate> {
+	["📅", "due:"].into_iter().find_map(|marker| {
+		text.match_indices(marker).find_map(|(index, _)| {
+			let starts_word = text[..index].chars().next_back().is_none_or(|c| !c.is_alphanumeric() && c != '_');
+			if marker == "due:" && !starts_word {
+				return None;
+			}
+			let date = text[index + marker.len()..].trim_start().get(..10)?;
+			NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+		})
+	})
+}
+
+/// Builds the edit toggling the checkbox of the task on the given line, if the line is a task.
+pub fn toggle_task(content: &str, line_index: usize) -> Option<TextEdit> {
+	let line = content.split('\n').nth(line_index)?;
+	let checkbox = find_checkbox(line)?;
+	let line_start: usize = content.split('\n').take(line_index).map(|line| line.chars().count() + 1).sum();
+	// The mark is the char inside the brackets, the checkbox prefix is ASCII except for the indentation.
+	let mark = line_start + line[..checkbox].chars().count() + 1;
+	let new_mark = if line[checkbox + 1..].starts_with(' ') { "x" } else { " " };
+	Some(TextEdit::replace(mark..mark + 1, new_mark))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+		NaiveDate::from_ymd_opt(year, month, day)
+	}
+
+	#[test]
+	fn parses_due_dates() {
+		assert_eq!(parse_due_date("Pay rent 📅 2026-10-20"), date(2026, 10, 20));
+		assert_eq!(parse_due_date("Pay rent 📅2026-10-20"), date(2026, 10, 20));
+		assert_eq!(parse_due_date("Pay rent due:2026-10-20"), date(2026, 10, 20));
+		assert_eq!(parse_due_date("due: 2026-10-20 pay rent"), date(2026, 10, 20));
+		assert_eq!(parse_due_date("Pay rent (due:2026-10-20)"), date(2026, 10, 20));
+		assert_eq!(parse_due_date("Pay rent"), None);
+		assert_eq!(parse_due_date("Pay rent due:tomorrow"), None);
+		assert_eq!(parse_due_date("Pay rent due:2026-13-01"), None);
+	}
+
+	#[test]
+	fn due_marker_starts_a_word() {
+		assert_eq!(parse_due_date("Pay overdue:2024-01-01 bills"), None);
+		assert_eq!(parse_due_date("Pay overdue:2024-01-01 bills due:2026-10-20"), date(2026, 10, 20));
+		assert_eq!(parse_due_date("Pay is_due:2024-01-01"), None);
+	}
+}
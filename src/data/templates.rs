@@ -0,0 +1,228 @@
+//! # Note Templates.
+//! Rendering of the note templates stored in the templates folder of the trove.
+//!
+//! Templates are markdown files with `{{variables}}`:
+//! - `{{date}}` or `{{date:YYYY-MM-DD}}`, the current date with an optional format.
+//! - `{{time}}` or `{{time:HH:mm}}`, the current time with an optional format.
+//! - `{{title}}`, the title of the note.
+//! - `{{cursor}}`, where the cursor is placed once the template is inserted.
+//!
+//! The front matter of a template holds default properties, they are added to the note unless it
+//! already has them.
+
+use crate::data::{
+	frontmatter::{FrontMatter, parse_front_matter},
+	text_edit::TextEdit,
+};
+use chrono::NaiveDateTime;
+
+const CURSOR_MARKER: &str = "{{cursor}}";
+
+/// Values of the template variables.
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+	pub title: String,
+	pub now: NaiveDateTime,
+}
+
+/// Text of a rendered template and the position of the cursor in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedTemplate {
+	pub text: String,
+	pub cursor: Option<usize>, // Char index of the `{{cursor}}` marker, if any
+}
+
+/// Replaces the variables of the template, unknown variables are kept as they are.
+pub fn render_template(template: &str, context: &TemplateContext) -> RenderedTemplate {
+	let mut text = String::with_capacity(template.len());
+	let mut rest = template;
+
+	while let Some(start) = rest.find("{{") {
+		let Some(end) = rest[start..].find("}}").map(|end| start + end) else {
+			break;
+		};
+		text.push_str(&rest[..start]);
+		let variable = &rest[start + 2..end];
+		match render_variable(variable.trim(), context) {
+			Some(value) => text.push_str(&value),
+			None => text.push_str(&rest[start..end + 2]),
+		}
+		rest = &rest[end + 2..];
+	}
+	text.push_str(rest);
+
+	// Only the first marker places the cursor, the others are removed.
+	let cursor = text.find(CURSOR_MARKER).map(|index| text[..index].chars().count());
+	let text = text.replace(CURSOR_MARKER, "");
+	RenderedTemplate { text, cursor }
+}
+
+fn render_variable(variable: &str, context: &TemplateContext) -> Option<String> {
+	let (name, format) = match variable.split_once(':') {
+		Some((name, format)) => (name.trim(), Some(format.trim())),
+		None => (variable, None),
+	};
+	match name {
+		"date" => Some(format_date_time(&context.now, format.unwrap_or("YYYY-MM-DD"))),
+		"time" => Some(format_date_time(&context.now, format.unwrap_or("HH:mm"))),
+		"title" => Some(context.title.clone()),
+		// Kept until the template is inserted.
+		"cursor" => Some(CURSOR_MARKER.to_string()),
+		_ => None,
+	}
+}
+
+/// Formats the date with a Moment.js style format like `YYYY-MM-DD`, as used by other note apps.
+/// Text in `[brackets]` is kept literally.
+pub fn format_date_time(date_time: &NaiveDateTime, format: &str) -> String {
+	const TOKENS: [(&str, &str); 19] = [
+		("YYYY", "%Y"),
+		("YY", "%y"),
+		("MMMM", "%B"),
+		("MMM", "%b"),
+		("MM", "%m"),
+		("M", "%-m"),
+		("dddd", "%A"),
+		("ddd", "%a"),
+		("DD", "%d"),
+		("D", "%-d"),
+		("HH", "%H"),
+		("H", "%-H"),
+		("hh", "%I"),
+		("h", "%-I"),
+		("mm", "%M"),
+		("ss", "%S"),
+		("A", "%p"),
+		("a", "%P"),
+		("Z", "%:z"),
+	];
+
+	let mut chrono_format = String::new();
+	let mut rest = format;
+	while let Some(ch) = rest.chars().next() {
+		if ch == '['
+			&& let Some(end) = rest.find(']')
+		{
+			chrono_format.push_str(&rest[1..end].replace('%', "%%"));
+			rest = &rest[end + 1..];
+			continue;
+		}
+		match TOKENS.iter().find(|(token, _)| rest.starts_with(token)) {
+			Some((token, specifier)) => {
+				chrono_format.push_str(specifier);
+				rest = &rest[token.len()..];
+			}
+			None => {
+				if ch == '%' {
+					chrono_format.push('%');
+				}
+				chrono_format.push(ch);
+				rest = &rest[ch.len_utf8()..];
+			}
+		}
+	}
+	date_time.format(&chrono_format).to_string()
+}
+
+/// Builds the edit inserting the rendered template at the cursor of the note. The properties of the
+/// template front matter are added to the note front matter when the note does not have them yet.
+pub fn insert_template(content: &str, cursor: usize, template: &RenderedTemplate) -> TextEdit {
+	let Some(template_front_matter) = parse_front_matter(&template.text) else {
+		let edit = TextEdit::replace(cursor..cursor, template.text.clone());
+		return match template.cursor {
+			Some(offset) => edit.with_cursor(cursor + offset),
+			None => edit,
+		};
+	};
+
+	let template_body = template_front_matter.body(&template.text);
+	let template_body_start = template.text.chars().count() - template_body.chars().count();
+	let body_cursor = template.cursor.map(|offset| offset.saturating_sub(template_body_start));
+
+	let mut front_matter = parse_front_matter(content).unwrap_or_else(FrontMatter::empty);
+	for (key, value) in template_front_matter.properties() {
+		if front_matter.get(key).is_none() {
+			front_matter.set(key, value.clone());
+		}
+	}
+
+	// The edit replaces everything from the start of the note up to the cursor, so that both the front
+	// matter and the inserted text end up in a single edit.
+	let note_body = front_matter.body(content);
+	let note_body_start = content.chars().count() - note_body.chars().count();
+	let cursor = cursor.max(note_body_start);
+	let before_cursor: String = content.chars().skip(note_body_start).take(cursor - note_body_start).collect();
+
+	let prefix = front_matter.render() + &before_cursor;
+	let prefix_len = prefix.chars().count();
+	let new_cursor = prefix_len + body_cursor.unwrap_or_else(|| template_body.chars().count());
+	TextEdit::replace(0..cursor, prefix + template_body).with_cursor(new_cursor)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::NaiveDate;
+
+	fn context() -> TemplateContext {
+		TemplateContext {
+			title: "Weekly".to_string(),
+			now: NaiveDate::from_ymd_opt(2025, 3, 4).unwrap().and_hms_opt(9, 5, 0).unwrap(),
+		}
+	}
+
+	fn apply(text: &str, edit: &TextEdit) -> String {
+		let mut chars: Vec<char> = text.chars().collect();
+		chars.splice(edit.range.clone(), edit.text.chars());
+		chars.into_iter().collect()
+	}
+
+	#[test]
+	fn renders_the_variables() {
+		let rendered = render_template(
+			"# {{title}} {{ date }} {{time}}\n{{date:dddd D MMMM [week] YY}} {{unknown}} {{",
+			&context(),
+		);
+		assert_eq!(rendered.text, "# Weekly 2025-03-04 09:05\nTuesday 4 March week 25 {{unknown}} {{");
+		assert_eq!(rendered.cursor, None);
+	}
+
+	#[test]
+	fn formats_dates_like_moment() {
+		let now = context().now;
+		assert_eq!(format_date_time(&now, "YYYY-MM-DD HH:mm:ss"), "2025-03-04 09:05:00");
+		assert_eq!(format_date_time(&now, "h:mm a, MMM D"), "9:05 am, Mar 4");
+		assert_eq!(format_date_time(&now, "[100%] M/D"), "100% 3/4");
+	}
+
+	#[test]
+	fn places_the_cursor_at_the_first_marker() {
+		let rendered = render_template("ä{{cursor}}b{{cursor}}", &context());
+		assert_eq!(
+			rendered,
+			RenderedTemplate {
+				text: "äb".to_string(),
+				cursor: Some(1)
+			}
+		);
+
+		let edit = insert_template("Hello ", 6, &rendered);
+		assert_eq!(apply("Hello ", &edit), "Hello äb");
+		assert_eq!(edit.cursor, 7);
+	}
+
+	#[test]
+	fn merges_the_front_matter_of_the_template() {
+		let template = render_template("---\ntags: [weekly]\ntitle: {{title}}\n---\n## {{cursor}}\n", &context());
+		let content = "---\ntitle: Mine\n---\nBody ";
+		let edit = insert_template(content, 25, &template);
+		let result = apply(content, &edit);
+		assert_eq!(result, "---\ntitle: Mine\ntags: [weekly]\n---\nBody ## \n");
+		assert_eq!(result.chars().nth(edit.cursor - 1), Some(' '));
+		assert_eq!(edit.cursor, result.chars().count() - 1);
+
+		// A note without front matter gets the one of the template.
+		let edit = insert_template("", 0, &template);
+		assert_eq!(apply("", &edit), "---\ntags: [weekly]\ntitle: Weekly\n---\n## \n");
+	}
+}
@@ -0,0 +1,70 @@
+//! # Text Edits.
+//! Editor agnostic description of a change to a document buffer.
+//!
+//! Markdown aware features compute a `TextEdit` from plain text, which is then applied to the
+//! `UseEditable` buffer of the document by `fn_utils::apply_text_edit`.
+
+use std::ops::Range;
+
+/// A single replacement in a document, all the indices are char indices into the whole document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+	pub range: Range<usize>,               // Chars removed from the document
+	pub text: String,                      // Text inserted at the start of the range
+	pub cursor: usize,                     // Position of the cursor after the edit
+	pub selection: Option<(usize, usize)>, // Selection after the edit, if any
+}
+
+impl TextEdit {
+	/// Replaces the given range with the text, leaving the cursor at the end of the inserted text.
+	pub fn replace(range: Range<usize>, text: impl Into<String>) -> TextEdit {
+		let text = text.into();
+		let cursor = range.start + text.chars().count();
+		TextEdit {
+			range,
+			text,
+			cursor,
+			selection: None,
+		}
+	}
+
+	/// Builds the smallest edit turning the old text into the new one, by skipping their common prefix and suffix.
+	pub fn between(old: &str, new: &str) -> TextEdit {
+		let old: Vec<char> = old.chars().collect();
+		let new: Vec<char> = new.chars().collect();
+		let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+		let max_suffix = old.len().min(new.len()) - prefix;
+		let suffix = old
+			.iter()
+			.rev()
+			.zip(new.iter().rev())
+			.take(max_suffix)
+			.take_while(|(a, b)| a == b)
+			.count();
+		let text: String = new[prefix..new.len() - suffix].iter().collect();
+		TextEdit::replace(prefix..old.len() - suffix, text)
+	}
+
+	/// Sets the cursor position after the edit.
+	pub fn with_cursor(mut self, cursor: usize) -> TextEdit {
+		self.cursor = cursor;
+		self
+	}
+
+	/// Sets the selection after the edit.
+	pub fn with_selection(mut self, start: usize, end: usize) -> TextEdit {
+		self.selection = Some((start, end));
+		self
+	}
+}
+
+/// Returns the char index of the start of every line in the text.
+pub fn line_starts(text: &str) -> Vec<usize> {
+	let mut starts = vec![0];
+	for (index, ch) in text.chars().enumerate() {
+		if ch == '\n' {
+			starts.push(index + 1);
+		}
+	}
+	starts
+}
@@ -0,0 +1,240 @@
+//! # Theme contrast.
+//! Checks the contrast ratios of the color pairs the app draws its text with, after the WCAG 2 formula, and
+//! makes a high contrast variant of a theme by moving its colors towards black or white until every pair
+//! passes the enhanced (AAA) level.
+
+use crate::data::themes::{Colors, Theme, hex_color, mix_colors, parse_hex_color, relative_luminance};
+
+/// A color checked against the color it is drawn on or next to.
+pub struct ContrastPair {
+	pub color: &'static str,
+	pub against: &'static str,
+	pub minimum: f64,  // WCAG AA
+	pub enhanced: f64, // WCAG AAA, the target of the high contrast variants
+}
+
+/// The critical pairs: the text and the subtext are body text, the highlight marks spans of text and the
+/// accent is used for links and the selected controls, which WCAG holds to the non-text contrast of 3:1.
+pub const CONTRAST_PAIRS: [ContrastPair; 4] = [
+	ContrastPair {
+		color: "text",
+		against: "base",
+		minimum: 4.5,
+		enhanced: 7.0,
+	},
+	ContrastPair {
+		color: "subtext0",
+		against: "crust",
+		minimum: 4.5,
+		enhanced: 7.0,
+	},
+	ContrastPair {
+		color: "highlight",
+		against: "text",
+		minimum: 3.0,
+		enhanced: 4.5,
+	},
+	ContrastPair {
+		color: "accent",
+		against: "base",
+		minimum: 3.0,
+		enhanced: 4.5,
+	},
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContrastFailure {
+	pub color: &'static str,
+	pub against: &'static str,
+	pub ratio: f64,
+	pub minimum: f64,
+}
+
+impl std::fmt::Display for ContrastFailure {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}/{} is {:.2}:1, below {}:1",
+			self.color, self.against, self.ratio, self.minimum
+		)
+	}
+}
+
+/// WCAG contrast ratio of two colors, from 1 for the same luminance to 21 for black on white.
+pub fn contrast_ratio(first: [u8; 3], second: [u8; 3]) -> f64 {
+	let (first, second) = (relative_luminance(first), relative_luminance(second));
+	(first.max(second) + 0.05) / (first.min(second) + 0.05)
+}
+
+/// The critical pairs of the colors below the AA level, the colors that are not hex codes are skipped.
+pub fn check_contrast(colors: &Colors) -> Vec<ContrastFailure> {
+	CONTRAST_PAIRS
+		.iter()
+		.filter_map(|pair| {
+			let color = parse_hex_color(colors.get(pair.color)?)?;
+			let against = parse_hex_color(colors.get(pair.against)?)?;
+			let ratio = contrast_ratio(color, against);
+			(ratio < pair.minimum).then_some(ContrastFailure {
+				color: pair.color,
+				against: pair.against,
+				ratio,
+				minimum: pair.minimum,
+			})
+		})
+		.collect()
+}
+
+/// A copy of the theme whose critical pairs all reach the AAA level. The first color of each pair is moved
+/// towards black or white, the subtexts along with `subtext0` without losing their order, and the markdown and
+/// syntax tokens of an advanced theme are made readable on `crust`.
+pub fn high_contrast_variant(theme: &Theme) -> Theme {
+	let mut variant = theme.clone();
+	variant.info.name = format!("{} High Contrast", theme.info.name);
+	let colors = &mut variant.colors;
+	for pair in &CONTRAST_PAIRS {
+		let fields: &[&str] = match pair.color {
+			"subtext0" => &["subtext0", "subtext1", "subtext2"],
+			field => &[field][..],
+		};
+		let Some(against) = colors.get(pair.against).and_then(parse_hex_color) else {
+			continue;
+		};
+		let mut ratio = pair.enhanced;
+		for field in fields {
+			if let Some(color) = colors.get(field).and_then(parse_hex_color) {
+				let color = raise_contrast(color, against, ratio);
+				ratio = ratio.max(contrast_ratio(color, against));
+				colors.set(field, hex_color(color));
+			}
+		}
+	}
+
+	if let (Some(tokens), Some(crust)) = (&variant.tokens, parse_hex_color(&variant.colors.crust)) {
+		let mut table = toml::Table::try_from(tokens).unwrap_or_default();
+		for section in ["markdown", "syntax"] {
+			let Some(toml::Value::Table(colors)) = table.get_mut(section) else {
+				continue;
+			};
+			for (_, value) in colors.iter_mut() {
+				if let Some(color) = value.as_str().and_then(parse_hex_color) {
+					*value = toml::Value::String(hex_color(raise_contrast(color, crust, 4.5)));
+				}
+			}
+		}
+		match table.try_into() {
+			Ok(tokens) => variant.tokens = Some(tokens),
+			Err(e) => log::warn!("Kept the tokens of {} as they are: {e}", theme.info.name),
+		}
+	}
+	variant
+}
+
+/// Moves a color towards white or black, the least that gives the ratio against the other color. Goes
+/// away from the luminance of the other color when both ways reach the ratio.
+fn raise_contrast(color: [u8; 3], against: [u8; 3], ratio: f64) -> [u8; 3] {
+	if contrast_ratio(color, against) >= ratio {
+		return color;
+	}
+	let lighter_first = relative_luminance(color) >= relative_luminance(against);
+	let extremes = if lighter_first { [[255; 3], [0; 3]] } else { [[0; 3], [255; 3]] };
+	let Some(extreme) = extremes.into_iter().find(|extreme| contrast_ratio(*extreme, against) >= ratio) else {
+		return extremes
+			.into_iter()
+			.max_by(|a, b| contrast_ratio(*a, against).total_cmp(&contrast_ratio(*b, against)))
+			.unwrap_or(color);
+	};
+	let (mut low, mut high) = (0.0, 1.0);
+	for _ in 0..16 {
+		let middle = (low + high) / 2.0;
+		if contrast_ratio(mix_colors(color, extreme, middle), against) >= ratio {
+			high = middle;
+		} else {
+			low = middle;
+		}
+	}
+	mix_colors(color, extreme, high)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data::themes::{ThemeType, Tokens};
+
+	fn ratio_of(colors: &Colors, color: &str, against: &str) -> f64 {
+		let color = parse_hex_color(colors.get(color).unwrap()).unwrap();
+		let against = parse_hex_color(colors.get(against).unwrap()).unwrap();
+		contrast_ratio(color, against)
+	}
+
+	#[test]
+	fn computes_wcag_ratios() {
+		assert!((contrast_ratio([0; 3], [255; 3]) - 21.0).abs() < 1e-9);
+		assert!((contrast_ratio([255; 3], [0; 3]) - 21.0).abs() < 1e-9);
+		assert!((contrast_ratio([0x77; 3], [0x77; 3]) - 1.0).abs() < 1e-9);
+		// #767676 is the lightest grey passing AA on white.
+		assert!(contrast_ratio([0x76; 3], [255; 3]) >= 4.5);
+		assert!(contrast_ratio([0x77; 3], [255; 3]) < 4.5);
+	}
+
+	#[test]
+	fn flags_the_pairs_below_aa() {
+		let mut colors = Theme::default().colors;
+		assert_eq!(check_contrast(&colors), vec![]);
+		// White text on a light orange highlight.
+		colors.highlight = "#ffa726".to_string();
+		let failures = check_contrast(&colors);
+		assert_eq!(failures.len(), 1);
+		assert_eq!((failures[0].color, failures[0].against), ("highlight", "text"));
+
+		colors.text = "#404040".to_string();
+		colors.accent = "transparent".to_string();
+		let failures = check_contrast(&colors);
+		assert_eq!(failures.len(), 1);
+		assert_eq!((failures[0].color, failures[0].against), ("text", "base"));
+		assert_eq!(
+			failures[0].to_string(),
+			format!("text/base is {:.2}:1, below 4.5:1", failures[0].ratio)
+		);
+	}
+
+	#[test]
+	fn high_contrast_variant_reaches_aaa() {
+		let mut theme = Theme::default();
+		theme.colors.text = "#777777".to_string();
+		theme.colors.subtext0 = "#555555".to_string();
+		theme.colors.accent = "#444444".to_string();
+		let variant = high_contrast_variant(&theme);
+		assert_eq!(variant.info.name, "Default High Contrast");
+		for pair in &CONTRAST_PAIRS {
+			let ratio = ratio_of(&variant.colors, pair.color, pair.against);
+			assert!(ratio >= pair.enhanced, "{}/{} is {ratio}", pair.color, pair.against);
+		}
+		// The subtexts keep their order, the colors already passing are left as they are.
+		assert!(ratio_of(&variant.colors, "subtext1", "crust") >= ratio_of(&variant.colors, "subtext0", "crust"));
+		assert!(ratio_of(&variant.colors, "subtext2", "crust") >= ratio_of(&variant.colors, "subtext1", "crust"));
+		assert_eq!(variant.colors.crust, theme.colors.crust);
+		assert_eq!(variant.colors.border, theme.colors.border);
+	}
+
+	#[test]
+	fn high_contrast_variant_raises_the_tokens() {
+		let mut theme = Theme::default();
+		let mut tokens = Tokens::derive(&theme.colors);
+		tokens.syntax.comment = theme.colors.crust.clone();
+		theme.info.themetype = ThemeType::Advance;
+		theme.tokens = Some(tokens);
+		let variant = high_contrast_variant(&theme);
+		let comment = parse_hex_color(&variant.tokens.unwrap().syntax.comment).unwrap();
+		assert!(contrast_ratio(comment, parse_hex_color(&variant.colors.crust).unwrap()) >= 4.5);
+	}
+
+	#[test]
+	fn raise_contrast_goes_the_other_way_when_needed() {
+		// Black can not reach 7:1 on a dark grey, white can.
+		let raised = raise_contrast([0x30; 3], [0x40; 3], 7.0);
+		assert!(relative_luminance(raised) > relative_luminance([0x40; 3]));
+		assert!(contrast_ratio(raised, [0x40; 3]) >= 7.0);
+		// Neither reaches 21:1 on a mid grey, the best one is returned.
+		assert_eq!(raise_contrast([0x70; 3], [0x80; 3], 21.0), [0; 3]);
+	}
+}
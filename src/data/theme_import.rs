@@ -0,0 +1,636 @@
+//! # Theme import.
+//! Converts the color schemes of other editors into Rhyolite themes, and exports themes to Base16.
+//!
+//! The colors of a Rhyolite theme go from the background to the text in this order: `mantle`, `crust`
+//! (the background of the editor), `base`, `surface0` to `surface2`, `overlay0` to `overlay2`, `subtext0`
+//! to `subtext2` and `text`. The colors a scheme does not give are mixed from its background and its
+//! text, at the same place of this ramp as in the bundled themes.
+//!
+//! | Rhyolite    | Base16 / Base24               | VS Code                                        | Obsidian                                   |
+//! |-------------|-------------------------------|------------------------------------------------|--------------------------------------------|
+//! | `crust`     | `base00`                      | `editor.background`                            | `--background-primary`                     |
+//! | `mantle`    | `base10` (Base24), or derived | `sideBar.background`                           | `--background-secondary`                   |
+//! | `base`      | `base01`                      | `editorWidget.background`, `tab.inactiveBackground` | `--background-primary-alt`            |
+//! | `surface0`  | `base02`                      | derived                                        | derived                                    |
+//! | `surface1`  | between `base02` and `base03` | derived                                        | derived                                    |
+//! | `surface2`  | `base03`                      | derived                                        | derived                                    |
+//! | `overlay0`  | between `base03` and `base04` | derived                                        | derived                                    |
+//! | `overlay1`  | between `base03` and `base04` | derived                                        | `--text-faint`                             |
+//! | `overlay2`  | `base04`                      | derived                                        | derived                                    |
+//! | `subtext0`  | between `base04` and `base05` | derived                                        | derived                                    |
+//! | `subtext1`  | between `base04` and `base05` | `descriptionForeground`                        | `--text-muted`                             |
+//! | `subtext2`  | between `base04` and `base05` | derived                                        | derived                                    |
+//! | `text`      | `base05`                      | `editor.foreground`, `foreground`              | `--text-normal`                            |
+//! | `accent`    | `base0E`                      | `focusBorder`, `button.background`, `textLink.foreground` | `--text-accent`, `--interactive-accent` |
+//! | `highlight` | `base09`                      | `editorWarning.foreground`, `terminal.ansiYellow` | `--text-highlight-bg`, `--color-orange` |
+//! | `border`    | `base02`                      | `panel.border`, `editorGroup.border`           | `--background-modifier-border`             |
+//!
+//! VS Code themes with `tokenColors` become advanced themes: the markdown and syntax tokens are taken from
+//! the `markup.*`, `keyword`, `string`, `comment`, `constant.numeric`, `entity.name.function`,
+//! `entity.name.type` and `punctuation` scopes, and the cursor from `editorCursor.foreground`.
+//!
+//! The Base16 export writes `crust`, `base`, `surface0`, `surface2`, `overlay2` and `text` as `base00` to
+//! `base05`, and `text` again as `base06` and `base07`. Rhyolite has no red, green or blue of its own, so
+//! `base08` to `base0F` come from the accent, the highlight and the syntax tokens.
+
+use crate::data::themes::{
+	ColorScheme, Colors, Theme, ThemeInfo, ThemeType, Tokens, hex_color, mix_colors, parse_hex_color, relative_luminance,
+};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Place of the colors between the background and the text.
+const RAMP: [(&str, f64); 9] = [
+	("surface0", 0.15),
+	("surface1", 0.25),
+	("surface2", 0.35),
+	("overlay0", 0.45),
+	("overlay1", 0.55),
+	("overlay2", 0.62),
+	("subtext0", 0.72),
+	("subtext1", 0.8),
+	("subtext2", 0.88),
+];
+
+const DEFAULT_ACCENT: &str = "#ff4081";
+const DEFAULT_HIGHLIGHT: &str = "#d27000";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeFormat {
+	Base16,
+	VsCode,
+	Obsidian,
+}
+
+impl ThemeFormat {
+	pub const ALL: [ThemeFormat; 3] = [ThemeFormat::Base16, ThemeFormat::VsCode, ThemeFormat::Obsidian];
+
+	pub fn title(self) -> &'static str {
+		match self {
+			ThemeFormat::Base16 => "Base16 / Base24 YAML",
+			ThemeFormat::VsCode => "VS Code JSON",
+			ThemeFormat::Obsidian => "Obsidian CSS",
+		}
+	}
+
+	/// Guesses the format of a scheme from its file extension.
+	pub fn detect(path: &Path) -> Option<ThemeFormat> {
+		match path.extension()?.to_str()?.to_lowercase().as_str() {
+			"yaml" | "yml" => Some(ThemeFormat::Base16),
+			"json" | "jsonc" => Some(ThemeFormat::VsCode),
+			"css" => Some(ThemeFormat::Obsidian),
+			_ => None,
+		}
+	}
+}
+
+/// Converts a scheme into themes, an Obsidian theme giving a dark and a light one. The name is used when
+/// the scheme has none, like the file name of an Obsidian theme.
+pub fn import_themes(format: ThemeFormat, content: &str, name: &str) -> Result<Vec<Theme>, String> {
+	match format {
+		ThemeFormat::Base16 => import_base16(content, name).map(|theme| vec![theme]),
+		ThemeFormat::VsCode => import_vscode(content, name).map(|theme| vec![theme]),
+		ThemeFormat::Obsidian => import_obsidian(content, name),
+	}
+}
+
+/// Writes a theme as a Base16 scheme, in the YAML format of tinted-theming.
+pub fn export_base16(theme: &Theme) -> String {
+	let colors = &theme.colors;
+	let tokens = theme.tokens();
+	let palette = [
+		&colors.crust,
+		&colors.base,
+		&colors.surface0,
+		&colors.surface2,
+		&colors.overlay2,
+		&colors.text,
+		&colors.text,
+		&colors.text,
+		&colors.accent,
+		&colors.highlight,
+		&tokens.syntax.number,
+		&tokens.syntax.string,
+		&tokens.syntax.type_name,
+		&tokens.syntax.function,
+		&tokens.syntax.keyword,
+		&colors.border,
+	];
+	let variant = match theme.info.colorscheme {
+		ColorScheme::Light => "light",
+		ColorScheme::Dark => "dark",
+	};
+	let mut yaml = format!(
+		"system: \"base16\"\nname: \"{}\"\nauthor: \"{}\"\nvariant: \"{variant}\"\npalette:\n",
+		yaml_escape(&theme.info.name),
+		yaml_escape(&theme.info.author)
+	);
+	for (index, color) in palette.into_iter().enumerate() {
+		let color = parse_hex_color(color).map_or_else(|| color.to_string(), hex_color);
+		yaml.push_str(&format!("  base{index:02X}: \"{color}\"\n"));
+	}
+	yaml
+}
+
+fn yaml_escape(text: &str) -> String {
+	text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Reads the `key: value` lines of a Base16 or Base24 scheme, in the old flat format or with the colors
+/// under `palette:`.
+fn import_base16(content: &str, name: &str) -> Result<Theme, String> {
+	let mut values: HashMap<String, String> = HashMap::new();
+	for line in content.lines() {
+		let Some((key, value)) = line.trim().split_once(':') else {
+			continue;
+		};
+		let value = value.trim();
+		let value = match value.chars().next() {
+			Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+			_ => value.split(" #").next().unwrap_or_default().trim(),
+		};
+		values.insert(key.trim().to_string(), value.to_string());
+	}
+	let color = |key: &str| -> Option<[u8; 3]> {
+		let value = values.get(key)?;
+		parse_hex_color(value).or_else(|| parse_hex_color(&format!("#{value}")))
+	};
+	let mut missing = Vec::new();
+	let mut base = [[0; 3]; 16];
+	for (index, slot) in base.iter_mut().enumerate() {
+		let key = format!("base{index:02X}");
+		match color(&key).or_else(|| color(&key.to_lowercase())) {
+			Some(rgb) => *slot = rgb,
+			None => missing.push(key),
+		}
+	}
+	if !missing.is_empty() {
+		return Err(format!("missing or invalid colors: {}", missing.join(", ")));
+	}
+
+	let background = base[0x0];
+	let mut colors = ramp_colors(background, base[0x5]);
+	colors.mantle = hex_color(color("base10").unwrap_or_else(|| mix_colors(background, base[0x1], -0.3)));
+	colors.base = hex_color(base[0x1]);
+	colors.surface0 = hex_color(base[0x2]);
+	colors.surface1 = hex_color(mix_colors(base[0x2], base[0x3], 0.5));
+	colors.surface2 = hex_color(base[0x3]);
+	colors.overlay0 = hex_color(mix_colors(base[0x3], base[0x4], 1.0 / 3.0));
+	colors.overlay1 = hex_color(mix_colors(base[0x3], base[0x4], 2.0 / 3.0));
+	colors.overlay2 = hex_color(base[0x4]);
+	colors.subtext0 = hex_color(mix_colors(base[0x4], base[0x5], 0.25));
+	colors.subtext1 = hex_color(mix_colors(base[0x4], base[0x5], 0.5));
+	colors.subtext2 = hex_color(mix_colors(base[0x4], base[0x5], 0.75));
+	colors.accent = hex_color(base[0xE]);
+	colors.highlight = hex_color(base[0x9]);
+	colors.border = hex_color(base[0x2]);
+
+	let colorscheme = match values.get("variant").map(String::as_str) {
+		Some("light") => ColorScheme::Light,
+		Some("dark") => ColorScheme::Dark,
+		_ => scheme_of(background),
+	};
+	let name = values.get("name").or_else(|| values.get("scheme")).map_or(name, String::as_str);
+	let author = values.get("author").map_or("", String::as_str);
+	Ok(basic_theme(name, author, colorscheme, colors))
+}
+
+/// Reads a VS Code color theme, comments and trailing commas allowed.
+fn import_vscode(content: &str, name: &str) -> Result<Theme, String> {
+	let json: serde_json::Value = serde_json::from_str(&strip_jsonc(content)).map_err(|e| format!("invalid JSON: {e}"))?;
+	let workbench = json.get("colors").and_then(|colors| colors.as_object());
+	let color = |keys: &[&str]| -> Option<[u8; 3]> { keys.iter().find_map(|key| workbench?.get(*key)?.as_str().and_then(parse_hex_color)) };
+
+	let background = color(&["editor.background"]).ok_or("the theme has no editor.background color")?;
+	let foreground = color(&["editor.foreground", "foreground"]).ok_or("the theme has no editor.foreground color")?;
+	let mut colors = ramp_colors(background, foreground);
+	colors.mantle =
+		hex_color(color(&["sideBar.background", "activityBar.background"]).unwrap_or_else(|| mix_colors(background, foreground, -0.1)));
+	colors.base = hex_color(
+		color(&["editorWidget.background", "tab.inactiveBackground", "titleBar.activeBackground"])
+			.unwrap_or_else(|| mix_colors(background, foreground, 0.07)),
+	);
+	if let Some(description) = color(&["descriptionForeground"]) {
+		colors.subtext1 = hex_color(description);
+	}
+	let accent = color(&[
+		"focusBorder",
+		"button.background",
+		"textLink.foreground",
+		"activityBarBadge.background",
+	]);
+	colors.accent = accent.map_or_else(|| DEFAULT_ACCENT.to_string(), hex_color);
+	colors.highlight = color(&["editorWarning.foreground", "terminal.ansiYellow"]).map_or_else(|| DEFAULT_HIGHLIGHT.to_string(), hex_color);
+	colors.border = color(&["panel.border", "editorGroup.border", "contrastBorder"]).map_or_else(|| colors.surface0.clone(), hex_color);
+
+	let colorscheme = match json.get("type").and_then(|kind| kind.as_str()) {
+		Some("light" | "hcLight") => ColorScheme::Light,
+		Some(_) => ColorScheme::Dark,
+		None => scheme_of(background),
+	};
+	let name = json.get("name").and_then(|name| name.as_str()).unwrap_or(name);
+	let author = json.get("author").and_then(|author| author.as_str()).unwrap_or_default();
+	let mut theme = basic_theme(name, author, colorscheme, colors);
+
+	if let Some(rules) = json.get("tokenColors").and_then(|rules| rules.as_array()) {
+		let scope_color = |scope: &str| -> Option<String> {
+			rules.iter().find_map(|rule| {
+				let scopes: Vec<&str> = match rule.get("scope")? {
+					serde_json::Value::String(scopes) => scopes.split(',').map(str::trim).collect(),
+					serde_json::Value::Array(scopes) => scopes.iter().filter_map(|scope| scope.as_str()).collect(),
+					_ => return None,
+				};
+				scopes
+					.iter()
+					.any(|rule_scope| *rule_scope == scope || rule_scope.starts_with(&format!("{scope}.")))
+					.then(|| rule.get("settings")?.get("foreground")?.as_str().and_then(parse_hex_color))
+					.flatten()
+					.map(hex_color)
+			})
+		};
+		let mut tokens = Tokens::derive(&theme.colors);
+		let markdown = &mut tokens.markdown;
+		let syntax = &mut tokens.syntax;
+		for (slot, scope) in [
+			(&mut markdown.heading1, "markup.heading"),
+			(&mut markdown.emphasis, "markup.italic"),
+			(&mut markdown.strong, "markup.bold"),
+			(&mut markdown.link, "markup.underline.link"),
+			(&mut markdown.inline_code, "markup.inline.raw"),
+			(&mut markdown.quote, "markup.quote"),
+			(&mut markdown.list_marker, "punctuation.definition.list"),
+			(&mut syntax.keyword, "keyword"),
+			(&mut syntax.string, "string"),
+			(&mut syntax.comment, "comment"),
+			(&mut syntax.number, "constant.numeric"),
+			(&mut syntax.function, "entity.name.function"),
+			(&mut syntax.type_name, "entity.name.type"),
+			(&mut syntax.punctuation, "punctuation"),
+		] {
+			if let Some(color) = scope_color(scope) {
+				*slot = color;
+			}
+		}
+		let heading = markdown.heading1.clone();
+		for level in [&mut markdown.heading2, &mut markdown.heading3] {
+			*level = heading.clone();
+		}
+		if let Some(cursor) = color(&["editorCursor.foreground"]) {
+			tokens.ui.cursor = hex_color(cursor);
+		}
+		theme.info.themetype = ThemeType::Advance;
+		theme.tokens = Some(tokens);
+	}
+	Ok(theme)
+}
+
+/// Reads the CSS variables of an Obsidian theme, giving its dark theme and its light theme.
+fn import_obsidian(content: &str, name: &str) -> Result<Vec<Theme>, String> {
+	let mut common: HashMap<String, String> = HashMap::new();
+	let mut dark: HashMap<String, String> = HashMap::new();
+	let mut light: HashMap<String, String> = HashMap::new();
+	let content = strip_css_comments(content);
+	let mut rest = content.as_str();
+	while let Some(open) = rest.find('{') {
+		let selector = rest[..open].rsplit('}').next().unwrap_or_default();
+		let Some(close) = rest[open..].find('}') else {
+			break;
+		};
+		let block = &rest[open + 1..open + close];
+		let variables = if selector.contains("theme-dark") {
+			&mut dark
+		} else if selector.contains("theme-light") {
+			&mut light
+		} else {
+			&mut common
+		};
+		for declaration in block.split(';') {
+			if let Some((key, value)) = declaration.split_once(':')
+				&& key.trim().starts_with("--")
+			{
+				variables.insert(key.trim().to_string(), value.trim().to_string());
+			}
+		}
+		rest = &rest[open + close + 1..];
+	}
+
+	let mut themes = Vec::new();
+	for (variables, colorscheme, suffix) in [(dark, ColorScheme::Dark, "Dark"), (light, ColorScheme::Light, "Light")] {
+		let mut merged = common.clone();
+		merged.extend(variables);
+		match obsidian_theme(&merged, &format!("{name} {suffix}"), colorscheme) {
+			Some(theme) => themes.push(theme),
+			None => continue,
+		}
+	}
+	if themes.is_empty() {
+		return Err("no --background-primary and --text-normal variables were found".to_string());
+	}
+	Ok(themes)
+}
+
+fn obsidian_theme(variables: &HashMap<String, String>, name: &str, colorscheme: ColorScheme) -> Option<Theme> {
+	let color = |keys: &[&str]| keys.iter().find_map(|key| css_variable(variables, key, 0));
+	let background = color(&["--background-primary", "--color-base-00"])?;
+	let foreground = color(&["--text-normal", "--color-base-100"])?;
+	let mut colors = ramp_colors(background, foreground);
+	colors.mantle =
+		hex_color(color(&["--background-secondary", "--color-base-20"]).unwrap_or_else(|| mix_colors(background, foreground, -0.1)));
+	colors.base = hex_color(
+		color(&["--background-primary-alt", "--background-secondary-alt", "--color-base-10"])
+			.unwrap_or_else(|| mix_colors(background, foreground, 0.07)),
+	);
+	if let Some(faint) = color(&["--text-faint"]) {
+		colors.overlay1 = hex_color(faint);
+	}
+	if let Some(muted) = color(&["--text-muted"]) {
+		colors.subtext1 = hex_color(muted);
+	}
+	colors.accent =
+		color(&["--text-accent", "--interactive-accent", "--color-accent"]).map_or_else(|| DEFAULT_ACCENT.to_string(), hex_color);
+	colors.highlight =
+		color(&["--text-highlight-bg", "--color-orange", "--color-yellow"]).map_or_else(|| DEFAULT_HIGHLIGHT.to_string(), hex_color);
+	colors.border = color(&["--background-modifier-border", "--color-base-30"]).map_or_else(|| colors.surface0.clone(), hex_color);
+	Some(basic_theme(name, "", colorscheme, colors))
+}
+
+/// The color of a CSS variable, following the `var(--other)` references.
+fn css_variable(variables: &HashMap<String, String>, key: &str, depth: usize) -> Option<[u8; 3]> {
+	let value = variables.get(key)?.trim();
+	if let Some(reference) = value.strip_prefix("var(").and_then(|value| value.strip_suffix(')')) {
+		let reference = reference.split(',').next()?.trim();
+		return if depth < 8 {
+			css_variable(variables, reference, depth + 1)
+		} else {
+			None
+		};
+	}
+	parse_css_color(value)
+}
+
+/// Parses the hex, `rgb()` and `hsl()` colors of CSS, the alpha is dropped.
+fn parse_css_color(value: &str) -> Option<[u8; 3]> {
+	if value.starts_with('#') {
+		return parse_hex_color(value);
+	}
+	let (function, arguments) = value.split_once('(')?;
+	let numbers: Vec<f64> = arguments
+		.trim_end_matches(')')
+		.split([',', ' ', '/'])
+		.filter(|part| !part.is_empty())
+		.map(|part| part.trim_end_matches(['%', 'g', 'd', 'e']).parse::<f64>())
+		.collect::<Result<_, _>>()
+		.ok()?;
+	if numbers.len() < 3 {
+		return None;
+	}
+	match function.trim() {
+		"rgb" | "rgba" => Some([numbers[0], numbers[1], numbers[2]].map(|channel| channel.clamp(0.0, 255.0).round() as u8)),
+		"hsl" | "hsla" => Some(hsl_to_rgb(numbers[0], numbers[1] / 100.0, numbers[2] / 100.0)),
+		_ => None,
+	}
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [u8; 3] {
+	let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+	let hue = hue.rem_euclid(360.0) / 60.0;
+	let second = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+	let (red, green, blue) = match hue as u8 {
+		0 => (chroma, second, 0.0),
+		1 => (second, chroma, 0.0),
+		2 => (0.0, chroma, second),
+		3 => (0.0, second, chroma),
+		4 => (second, 0.0, chroma),
+		_ => (chroma, 0.0, second),
+	};
+	let lightness_match = lightness - chroma / 2.0;
+	[red, green, blue].map(|channel| ((channel + lightness_match) * 255.0).clamp(0.0, 255.0).round() as u8)
+}
+
+/// Removes the comments and the trailing commas of JSON with comments, leaving the strings untouched.
+fn strip_jsonc(content: &str) -> String {
+	let chars: Vec<char> = content.chars().collect();
+	let mut json: Vec<char> = Vec::with_capacity(chars.len());
+	let mut index = 0;
+	while index < chars.len() {
+		match (chars[index], chars.get(index + 1)) {
+			('"', _) => {
+				let start = index;
+				index += 1;
+				while index < chars.len() && chars[index] != '"' {
+					index += if chars[index] == '\\' { 2 } else { 1 };
+				}
+				index = (index + 1).min(chars.len());
+				json.extend(&chars[start..index]);
+				continue;
+			}
+			('/', Some('/')) => {
+				while index < chars.len() && chars[index] != '\n' {
+					index += 1;
+				}
+				continue;
+			}
+			('/', Some('*')) => {
+				index += 2;
+				while index < chars.len() && !(chars[index] == '*' && chars.get(index + 1) == Some(&'/')) {
+					index += 1;
+				}
+				index += 2;
+				continue;
+			}
+			(ch @ ('}' | ']'), _) => {
+				// The comments are gone by now, so a comma before the closing bracket is a trailing one.
+				if let Some(last) = json.iter().rposition(|ch| !ch.is_whitespace())
+					&& json[last] == ','
+				{
+					json.remove(last);
+				}
+				json.push(ch);
+			}
+			(ch, _) => json.push(ch),
+		}
+		index += 1;
+	}
+	json.into_iter().collect()
+}
+
+fn strip_css_comments(content: &str) -> String {
+	let mut css = String::with_capacity(content.len());
+	let mut rest = content;
+	while let Some(start) = rest.find("/*") {
+		css.push_str(&rest[..start]);
+		rest = rest[start + 2..].split_once("*/").map_or("", |(_, after)| after);
+	}
+	css.push_str(rest);
+	css
+}
+
+/// The colors mixed from a background and a text color, the caller setting the ones its scheme gives.
+fn ramp_colors(background: [u8; 3], foreground: [u8; 3]) -> Colors {
+	let mut colors = Colors {
+		text: hex_color(foreground),
+		crust: hex_color(background),
+		..Colors::default()
+	};
+	for (field, place) in RAMP {
+		colors.set(field, hex_color(mix_colors(background, foreground, place)));
+	}
+	colors
+}
+
+fn scheme_of(background: [u8; 3]) -> ColorScheme {
+	if relative_luminance(background) > 0.4 {
+		ColorScheme::Light
+	} else {
+		ColorScheme::Dark
+	}
+}
+
+fn basic_theme(name: &str, author: &str, colorscheme: ColorScheme, colors: Colors) -> Theme {
+	Theme {
+		info: ThemeInfo {
+			name: name.to_string(),
+			author: author.to_string(),
+			themetype: ThemeType::Basic,
+			colorscheme,
+		},
+		colors,
+		tokens: None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const BASE16: &str = r##"system: "base16"
+name: "Test Scheme"
+author: "Someone"
+variant: "dark"
+palette:
+  base00: "#000000"
+  base01: "#111111"
+  base02: "#222222"
+  base03: "#333333"
+  base04: "#444444"
+  base05: "#ffffff" # text
+  base06: "#eeeeee"
+  base07: "#dddddd"
+  base08: "#ff0000"
+  base09: "#ff8800"
+  base0A: "#ffff00"
+  base0B: "#00ff00"
+  base0C: "#00ffff"
+  base0D: "#0000ff"
+  base0E: "#8800ff"
+  base0F: "#880000"
+"##;
+
+	#[test]
+	fn detects_formats_by_extension() {
+		assert_eq!(ThemeFormat::detect(Path::new("scheme.YML")), Some(ThemeFormat::Base16));
+		assert_eq!(ThemeFormat::detect(Path::new("theme.jsonc")), Some(ThemeFormat::VsCode));
+		assert_eq!(ThemeFormat::detect(Path::new("theme.css")), Some(ThemeFormat::Obsidian));
+		assert_eq!(ThemeFormat::detect(Path::new("theme.toml")), None);
+	}
+
+	#[test]
+	fn imports_base16() {
+		let themes = import_themes(ThemeFormat::Base16, BASE16, "file").unwrap();
+		let theme = &themes[0];
+		assert_eq!(theme.info.name, "Test Scheme");
+		assert_eq!(theme.info.author, "Someone");
+		assert_eq!(theme.info.colorscheme, ColorScheme::Dark);
+		assert_eq!(theme.colors.crust, "#000000");
+		assert_eq!(theme.colors.base, "#111111");
+		assert_eq!(theme.colors.text, "#ffffff");
+		assert_eq!(theme.colors.accent, "#8800ff");
+		assert_eq!(theme.colors.highlight, "#ff8800");
+		assert_eq!(theme.colors.surface1, "#2b2b2b");
+
+		// The old flat format, with the colors unprefixed.
+		let flat = BASE16.replace("palette:\n", "").replace("\"#", "\"");
+		assert_eq!(import_themes(ThemeFormat::Base16, &flat, "file").unwrap(), themes);
+	}
+
+	#[test]
+	fn base16_needs_its_sixteen_colors() {
+		let content = BASE16.replace("  base0A: \"#ffff00\"\n", "").replace("#00ff00", "green");
+		assert_eq!(
+			import_themes(ThemeFormat::Base16, &content, "file"),
+			Err("missing or invalid colors: base0A, base0B".to_string())
+		);
+	}
+
+	#[test]
+	fn exported_base16_imports_back() {
+		let theme = &import_themes(ThemeFormat::Base16, BASE16, "file").unwrap()[0];
+		let exported = export_base16(theme);
+		assert!(exported.contains("  base0E: \"#8800ff\"\n"));
+		let imported = &import_themes(ThemeFormat::Base16, &exported, "file").unwrap()[0];
+		assert_eq!(imported.info, theme.info);
+		for field in ["crust", "base", "surface0", "surface2", "overlay2", "text", "accent", "highlight"] {
+			assert_eq!(imported.colors.get(field), theme.colors.get(field), "{field}");
+		}
+	}
+
+	#[test]
+	fn imports_vscode_with_comments_and_trailing_commas() {
+		let content = r##"{
+			// A comment, and a "string" with // in it
+			"name": "Code // Theme",
+			"type": "light",
+			"colors": {
+				"editor.background": "#ffffff",
+				"editor.foreground": "#000000", /* block comment */
+				"focusBorder": "#0066ff",
+			},
+			"tokenColors": [
+				{ "scope": ["keyword.control", "storage"], "settings": { "foreground": "#aa00aa" } },
+				{ "scope": "markup.heading, markup.bold", "settings": { "foreground": "#cc0000" } },
+			],
+		}"##;
+		let theme = &import_themes(ThemeFormat::VsCode, content, "file").unwrap()[0];
+		assert_eq!(theme.info.name, "Code // Theme");
+		assert_eq!(theme.info.colorscheme, ColorScheme::Light);
+		assert_eq!(theme.info.themetype, ThemeType::Advance);
+		assert_eq!(theme.colors.accent, "#0066ff");
+		assert_eq!(theme.colors.highlight, DEFAULT_HIGHLIGHT);
+		let tokens = theme.tokens.as_ref().unwrap();
+		assert_eq!(tokens.syntax.keyword, "#aa00aa");
+		assert_eq!(tokens.markdown.heading3, "#cc0000");
+		assert_eq!(tokens.markdown.strong, "#cc0000");
+
+		let no_background = content.replace("editor.background", "sideBar.background");
+		assert!(import_themes(ThemeFormat::VsCode, &no_background, "file").is_err());
+	}
+
+	#[test]
+	fn imports_obsidian_dark_and_light() {
+		let content = r#"
+			/* Shared */
+			body { --accent: #ff0000; --text-accent: var(--accent); }
+			.theme-dark { --background-primary: rgb(16, 16, 16); --text-normal: hsl(0, 0%, 100%); }
+			.theme-light { --background-primary: #fafafa; }
+		"#;
+		let themes = import_themes(ThemeFormat::Obsidian, content, "Minimal").unwrap();
+		assert_eq!(themes.len(), 1);
+		assert_eq!(themes[0].info.name, "Minimal Dark");
+		assert_eq!(themes[0].colors.crust, "#101010");
+		assert_eq!(themes[0].colors.text, "#ffffff");
+		assert_eq!(themes[0].colors.accent, "#ff0000");
+
+		assert!(import_themes(ThemeFormat::Obsidian, "body { color: red; }", "Minimal").is_err());
+	}
+
+	#[test]
+	fn parses_css_colors() {
+		assert_eq!(parse_css_color("#abc"), Some([0xaa, 0xbb, 0xcc]));
+		assert_eq!(parse_css_color("rgba(255, 128, 0, 0.5)"), Some([255, 128, 0]));
+		assert_eq!(parse_css_color("rgb(255 128 0 / 50%)"), Some([255, 128, 0]));
+		assert_eq!(parse_css_color("hsl(120deg 100% 50%)"), Some([0, 255, 0]));
+		assert_eq!(parse_css_color("hsl(240, 100%, 25%)"), Some([0, 0, 128]));
+		assert_eq!(parse_css_color("red"), None);
+		assert_eq!(parse_css_color("rgb(1, 2)"), None);
+	}
+}
@@ -1,13 +1,102 @@
-use crate::data::types::APP_DATA_DIR;
+use crate::data::{
+	markdown_style::{SpanStyle, SyntaxScope},
+	theme_contrast::check_contrast,
+	types::APP_DATA_DIR,
+};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Themes compiled into the app, listed along with the themes of the themes directory.
+const BUILT_IN_THEMES: [(&str, &str); 14] = [
+	("catppuccinLatte.toml", include_str!("../../app_themes/catppuccinLatte.toml")),
+	(
+		"catppuccinMacchiato.toml",
+		include_str!("../../app_themes/catppuccinMacchiato.toml"),
+	),
+	("catppuccinMocha.toml", include_str!("../../app_themes/catppuccinMocha.toml")),
+	("coffee.toml", include_str!("../../app_themes/coffee.toml")),
+	("crimsonNocturne.toml", include_str!("../../app_themes/crimsonNocturne.toml")),
+	("default.toml", include_str!("../../app_themes/default.toml")),
+	("greenScreen.toml", include_str!("../../app_themes/greenScreen.toml")),
+	(
+		"gruvboxMaterialDark(Hard).toml",
+		include_str!("../../app_themes/gruvboxMaterialDark(Hard).toml"),
+	),
+	(
+		"gruvboxMaterialLight(Hard).toml",
+		include_str!("../../app_themes/gruvboxMaterialLight(Hard).toml"),
+	),
+	("highContrast.toml", include_str!("../../app_themes/highContrast.toml")),
+	("rosePineDawn.toml", include_str!("../../app_themes/rosePineDawn.toml")),
+	("rosePineMain.toml", include_str!("../../app_themes/rosePineMain.toml")),
+	("rosePineMoon.toml", include_str!("../../app_themes/rosePineMoon.toml")),
+	("tokyoNightDark.toml", include_str!("../../app_themes/tokyoNightDark.toml")),
+];
+
+/// Where a theme of the list comes from: one of the read-only built-in themes, by its file name, or a
+/// file of the themes directory.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ThemeSource {
+	BuiltIn(String),
+	File(PathBuf),
+}
+
+impl ThemeSource {
+	pub fn is_built_in(&self) -> bool {
+		matches!(self, ThemeSource::BuiltIn(_))
+	}
+
+	fn exists(&self) -> bool {
+		match self {
+			ThemeSource::BuiltIn(_) => true,
+			ThemeSource::File(path) => path.exists(),
+		}
+	}
+
+	fn load(&self) -> Result<Theme, Vec<String>> {
+		match self {
+			ThemeSource::BuiltIn(file_name) => {
+				let content = BUILT_IN_THEMES
+					.iter()
+					.find(|(name, _)| name == file_name)
+					.map(|(_, content)| *content)
+					.ok_or_else(|| vec![format!("there is no built-in theme {file_name}")])?;
+				parse_theme(content)
+			}
+			ThemeSource::File(path) => {
+				let content = fs::read_to_string(path).map_err(|e| vec![format!("the file can not be read: {e}")])?;
+				parse_theme(&content)
+			}
+		}
+	}
+}
+
+impl std::fmt::Display for ThemeSource {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ThemeSource::BuiltIn(file_name) => write!(f, "built-in {file_name}"),
+			ThemeSource::File(path) => write!(f, "{}", path.display()),
+		}
+	}
+}
+
+/// A theme that can not be loaded, with its errors.
+pub type InvalidTheme = (ThemeSource, Vec<String>);
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct ThemesStore {
 	pub themes_dir: PathBuf,
-	pub themes_list: Vec<(String, PathBuf)>,
+	pub themes_list: Vec<(String, ThemeSource)>,
 	pub current_theme: Theme,
+	pub current_theme_source: Option<ThemeSource>, // Reloaded when its file changes
+	#[serde(skip)]
+	pub previewed_from: Option<(Theme, Option<ThemeSource>)>, // Theme in use before the preview
+	#[serde(skip)]
+	pub low_contrast: Vec<String>,  // Names of the themes failing the contrast checks
+	#[serde(skip)]
+	pub invalid_themes: Vec<InvalidTheme>, // Theme files left out of the list, with their errors
 }
 impl ThemesStore {
 	// TODO: Make this as new function and make a new default function.
@@ -28,65 +117,353 @@ impl ThemesStore {
 			themes_dir
 		};
 
-		let themes_list_result = list_toml_names(&themes_dir);
-
-		if let Err(e) = themes_list_result {
-			log::error!("Failed to list themes in directory: {}", e);
-			log::warn!("Using default theme.");
-			log::warn!("No themes to select from..");
-			return ThemesStore {
-				themes_dir,
-				themes_list: vec![],
-				current_theme: Theme::default(),
-			};
-		}
-
+		let (themes_list, invalid_themes) = list_themes(&themes_dir);
 		ThemesStore {
+			low_contrast: low_contrast_themes(&themes_list),
+			themes_list,
+			invalid_themes,
 			themes_dir,
-			themes_list: themes_list_result.unwrap(),
 			current_theme: Theme::default(),
+			current_theme_source: None,
+			previewed_from: None,
 		}
 	}
 
-	pub async fn change_current_theme(&mut self, theme_path: PathBuf) {
-		if let Some(theme) = self.load_theme_from_file(theme_path) {
-			self.current_theme = theme.clone();
-		} else {
-			log::error!("The given theme does not exists or is corrupted.");
+	/// Switches to a theme of the list, or keeps the current theme and returns the errors of the new one.
+	pub async fn change_current_theme(&mut self, source: ThemeSource) -> Result<(), Vec<String>> {
+		match source.load() {
+			Ok(theme) => {
+				for failure in check_contrast(&theme.colors) {
+					log::warn!("The theme {} has a low contrast: {failure}", theme.info.name);
+				}
+				self.current_theme = theme;
+				self.current_theme_source = Some(source);
+				self.previewed_from = None;
+				Ok(())
+			}
+			Err(errors) => {
+				log_theme_errors(&source, &errors);
+				Err(errors)
+			}
 		}
 	}
 
-	// pub async fn _preview_theme(&mut self, preview: bool, theme_index: usize, original_theme: &Option<Theme>) {
-	// 	if preview {
-	// 		if original_theme.is_none() {
-	// 			// This is the first preview, store current and switch to preview
-	// 			self.current_theme = self.themes_path.get(theme_index).unwrap().clone();
-	// 		} else {
-	// 			// Already previewing, just switch to new preview theme
-	// 			self.current_theme = self.themes_path.get(theme_index).unwrap().clone();
-	// 		}
-	// 	} else {
-	// 		// Restore original theme
-	// 		if let Some(original) = original_theme {
-	// 			self.current_theme = original.clone();
-	// 		}
-	// 	}
-	// }
+	/// Sets the theme saved in the user data, its source is the theme of the list with the same name.
+	pub fn restore_theme(&mut self, theme: Theme) {
+		self.current_theme_source = self.source_of(&theme.info.name);
+		self.current_theme = theme;
+	}
 
-	// TODO: Handle errors.
-	fn load_theme_from_file(&self, path: PathBuf) -> Option<Theme> {
-		let theme_content = fs::read_to_string(path).unwrap_or_default();
-		toml::from_str(&theme_content).ok()
+	/// The source of the theme of the list with the given name.
+	pub fn source_of(&self, name: &str) -> Option<ThemeSource> {
+		self.themes_list
+			.iter()
+			.find(|(theme_name, _)| theme_name == name)
+			.map(|(_, source)| source.clone())
+	}
+
+	/// The theme files with the time they were last modified, to tell when the themes directory changes.
+	pub fn files_snapshot(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+		let Ok(entries) = fs::read_dir(&self.themes_dir) else {
+			return Vec::new();
+		};
+		let mut files: Vec<(PathBuf, Option<SystemTime>)> = entries
+			.flatten()
+			.map(|entry| entry.path())
+			.filter(|path| path.extension().and_then(|e| e.to_str()) == Some("toml"))
+			.map(|path| {
+				let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+				(path, modified)
+			})
+			.collect();
+		files.sort();
+		files
+	}
+
+	/// Lists the themes again and reloads the current theme, from the user theme now overriding it if there
+	/// is one. Returns the errors of the current theme when it can not be reloaded.
+	pub fn refresh(&mut self) -> Result<(), Vec<String>> {
+		(self.themes_list, self.invalid_themes) = list_themes(&self.themes_dir);
+		self.low_contrast = low_contrast_themes(&self.themes_list);
+		// A renamed theme is still found by its file.
+		let source = self
+			.source_of(&self.current_theme.info.name)
+			.or_else(|| self.current_theme_source.clone().filter(ThemeSource::exists));
+		let Some(source) = source else {
+			return Ok(());
+		};
+		match source.load() {
+			Ok(theme) => {
+				self.current_theme = theme;
+				self.current_theme_source = Some(source);
+				Ok(())
+			}
+			Err(errors) => {
+				log_theme_errors(&source, &errors);
+				log::warn!("Keeping the previous colors of the theme.");
+				Err(errors)
+			}
+		}
+	}
+
+	/// Applies a theme of the list until the preview ends, the theme in use is kept to be restored.
+	pub fn preview_theme(&mut self, source: ThemeSource) {
+		let theme = match source.load() {
+			Ok(theme) => theme,
+			Err(errors) => return log_theme_errors(&source, &errors),
+		};
+		if self.previewed_from.is_none() {
+			self.previewed_from = Some((self.current_theme.clone(), self.current_theme_source.clone()));
+		}
+		self.current_theme = theme;
+		self.current_theme_source = Some(source);
+	}
+
+	pub fn is_previewing(&self) -> bool {
+		self.previewed_from.is_some()
+	}
+
+	/// The theme picked by the user, which is the one in use before the preview while previewing.
+	pub fn committed_theme(&self) -> &Theme {
+		self.previewed_from.as_ref().map_or(&self.current_theme, |(theme, _)| theme)
+	}
+
+	/// Restores the theme in use before the preview.
+	pub fn end_preview(&mut self) {
+		if let Some((theme, source)) = self.previewed_from.take() {
+			self.current_theme = theme;
+			self.current_theme_source = source;
+		}
 	}
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+/// Names of the themes of the list whose colors fail the contrast checks.
+fn low_contrast_themes(themes_list: &[(String, ThemeSource)]) -> Vec<String> {
+	themes_list
+		.iter()
+		.filter(|(_, source)| source.load().is_ok_and(|theme| !check_contrast(&theme.colors).is_empty()))
+		.map(|(name, _)| name.clone())
+		.collect()
+}
+
+fn log_theme_errors(source: &ThemeSource, errors: &[String]) {
+	log::error!("The theme {source} is invalid:");
+	for error in errors {
+		log::error!("  {error}");
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct Theme {
 	pub info: ThemeInfo,
 	pub colors: Colors,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub tokens: Option<Tokens>, // Only used by advanced themes
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+impl Theme {
+	/// The tokens of an advanced theme, or the ones derived from the colors of a basic theme.
+	pub fn tokens(&self) -> Tokens {
+		match (&self.info.themetype, &self.tokens) {
+			(ThemeType::Advance, Some(tokens)) => tokens.clone(),
+			_ => Tokens::derive(&self.colors),
+		}
+	}
+}
+
+/// Reads a theme file, or returns every missing or invalid key of it.
+pub fn parse_theme(content: &str) -> Result<Theme, Vec<String>> {
+	let table: toml::Table = content
+		.parse()
+		.map_err(|e: toml::de::Error| vec![format!("invalid TOML: {}", e.message())])?;
+	let mut errors = Vec::new();
+
+	let mut themetype = "Basic";
+	match table.get("info").and_then(|info| info.as_table()) {
+		Some(info) => {
+			for key in ["name", "author", "themetype", "colorscheme"] {
+				if !info.get(key).is_some_and(|value| value.is_str()) {
+					errors.push(format!("missing key info.{key}, expected a string"));
+				}
+			}
+			if let Some(value) = info.get("themetype").and_then(|value| value.as_str()) {
+				themetype = value;
+				if !["Basic", "Advance"].contains(&value) {
+					errors.push(format!("info.themetype = \"{value}\" is not \"Basic\" or \"Advance\""));
+				}
+			}
+			if let Some(value) = info.get("colorscheme").and_then(|value| value.as_str())
+				&& !["Light", "Dark"].contains(&value)
+			{
+				errors.push(format!("info.colorscheme = \"{value}\" is not \"Light\" or \"Dark\""));
+			}
+		}
+		None => errors.push("missing [info] table".to_string()),
+	}
+
+	check_colors(table.get("colors"), "colors", &Colors::FIELDS, &mut errors);
+	if themetype == "Advance" {
+		// The keys of the token tables are the fields of the tokens.
+		let token_sections = toml::Table::try_from(Tokens::default()).unwrap_or_default();
+		for (section, keys) in token_sections
+			.iter()
+			.filter_map(|(section, keys)| Some((section, keys.as_table()?)))
+		{
+			let keys: Vec<&str> = keys.keys().map(String::as_str).collect();
+			let colors = table.get("tokens").and_then(|tokens| tokens.get(section));
+			check_colors(colors, &format!("tokens.{section}"), &keys, &mut errors);
+		}
+	}
+
+	if !errors.is_empty() {
+		return Err(errors);
+	}
+	toml::from_str(content).map_err(|e: toml::de::Error| vec![e.message().to_string()])
+}
+
+/// Checks that a table of the theme has each key, with a color as value.
+fn check_colors(colors: Option<&toml::Value>, section: &str, keys: &[&str], errors: &mut Vec<String>) {
+	let Some(colors) = colors.and_then(|colors| colors.as_table()) else {
+		errors.push(format!("missing [{section}] table"));
+		return;
+	};
+	for key in keys {
+		match colors.get(*key).map(|value| value.as_str()) {
+			None => errors.push(format!("missing key {section}.{key}")),
+			Some(None) => errors.push(format!("{section}.{key} must be a color string like \"#1e1e2e\"")),
+			Some(Some(color)) if !is_theme_color(color) => {
+				errors.push(format!("{section}.{key} = \"{color}\" is not a color like \"#1e1e2e\""));
+			}
+			Some(Some(_)) => (),
+		}
+	}
+}
+
+/// Whether a value of a theme is a hex color, or transparent.
+pub fn is_theme_color(text: &str) -> bool {
+	text == "transparent" || parse_hex_color(text).is_some()
+}
+
+/// Colors of the markdown elements, the code scopes and the parts of the editor. Advanced themes give
+/// them in their `[tokens.*]` tables, basic themes derive them from their colors.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Tokens {
+	pub markdown: MarkdownTokens,
+	pub syntax: SyntaxTokens,
+	pub ui: UiTokens,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MarkdownTokens {
+	pub text: String,
+	pub heading1: String,
+	pub heading2: String,
+	pub heading3: String,
+	pub heading4: String,
+	pub heading5: String,
+	pub heading6: String,
+	pub emphasis: String,
+	pub strong: String,
+	pub link: String,
+	pub inline_code: String,
+	pub code_block: String,
+	pub quote: String,
+	pub list_marker: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SyntaxTokens {
+	pub keyword: String,
+	pub string: String,
+	pub comment: String,
+	pub number: String,
+	pub function: String,
+	#[serde(rename = "type")]
+	pub type_name: String,
+	pub punctuation: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct UiTokens {
+	pub selection: String,
+	pub cursor: String,
+	pub line_highlight: String, // Background of the line of the cursor, can be transparent
+	pub scrollbar_thumb: String,
+	pub scrollbar_thumb_hover: String,
+	pub scrollbar_thumb_active: String,
+}
+
+impl Tokens {
+	/// Tokens of a basic theme.
+	pub fn derive(colors: &Colors) -> Tokens {
+		Tokens {
+			markdown: MarkdownTokens {
+				text: colors.text.clone(),
+				heading1: colors.text.clone(),
+				heading2: colors.text.clone(),
+				heading3: colors.text.clone(),
+				heading4: colors.subtext2.clone(),
+				heading5: colors.subtext2.clone(),
+				heading6: colors.subtext1.clone(),
+				emphasis: colors.text.clone(),
+				strong: colors.text.clone(),
+				link: colors.accent.clone(),
+				inline_code: colors.highlight.clone(),
+				code_block: colors.subtext2.clone(),
+				quote: colors.subtext0.clone(),
+				list_marker: colors.overlay2.clone(),
+			},
+			syntax: SyntaxTokens {
+				keyword: colors.accent.clone(),
+				string: colors.highlight.clone(),
+				comment: colors.overlay1.clone(),
+				number: colors.highlight.clone(),
+				function: colors.text.clone(),
+				type_name: colors.subtext1.clone(),
+				punctuation: colors.overlay2.clone(),
+			},
+			ui: UiTokens {
+				selection: colors.subtext1.clone(),
+				cursor: colors.text.clone(),
+				line_highlight: "transparent".to_string(),
+				scrollbar_thumb: colors.surface0.clone(),
+				scrollbar_thumb_hover: colors.surface1.clone(),
+				scrollbar_thumb_active: colors.surface2.clone(),
+			},
+		}
+	}
+
+	/// Color of the text of a style of the editor.
+	pub fn color(&self, style: SpanStyle) -> &str {
+		let markdown = &self.markdown;
+		let syntax = &self.syntax;
+		match style {
+			SpanStyle::Text => &markdown.text,
+			SpanStyle::Heading(1) => &markdown.heading1,
+			SpanStyle::Heading(2) => &markdown.heading2,
+			SpanStyle::Heading(3) => &markdown.heading3,
+			SpanStyle::Heading(4) => &markdown.heading4,
+			SpanStyle::Heading(5) => &markdown.heading5,
+			SpanStyle::Heading(_) => &markdown.heading6,
+			SpanStyle::Emphasis => &markdown.emphasis,
+			SpanStyle::Strong => &markdown.strong,
+			SpanStyle::Link => &markdown.link,
+			SpanStyle::InlineCode => &markdown.inline_code,
+			SpanStyle::Quote => &markdown.quote,
+			SpanStyle::ListMarker => &markdown.list_marker,
+			SpanStyle::CodeFence | SpanStyle::Code(SyntaxScope::Plain) => &markdown.code_block,
+			SpanStyle::Code(SyntaxScope::Keyword) => &syntax.keyword,
+			SpanStyle::Code(SyntaxScope::String) => &syntax.string,
+			SpanStyle::Code(SyntaxScope::Comment) => &syntax.comment,
+			SpanStyle::Code(SyntaxScope::Number) => &syntax.number,
+			SpanStyle::Code(SyntaxScope::Function) => &syntax.function,
+			SpanStyle::Code(SyntaxScope::Type) => &syntax.type_name,
+			SpanStyle::Code(SyntaxScope::Punctuation) => &syntax.punctuation,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct ThemeInfo {
 	pub name: String,
 	pub author: String,
@@ -94,21 +471,21 @@ pub struct ThemeInfo {
 	pub colorscheme: ColorScheme,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub enum ThemeType {
 	#[default]
 	Basic,
 	Advance,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub enum ColorScheme {
 	Light,
 	#[default]
 	Dark,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct Colors {
 	pub text: String,
 	pub subtext2: String,
@@ -128,6 +505,157 @@ pub struct Colors {
 	pub border: String,
 }
 
+impl Colors {
+	/// Names of the colors, as written in the theme files.
+	pub const FIELDS: [&'static str; 16] = [
+		"text",
+		"subtext2",
+		"subtext1",
+		"subtext0",
+		"overlay2",
+		"overlay1",
+		"overlay0",
+		"surface2",
+		"surface1",
+		"surface0",
+		"base",
+		"crust",
+		"mantle",
+		"accent",
+		"highlight",
+		"border",
+	];
+
+	pub fn get(&self, field: &str) -> Option<&str> {
+		let color = match field {
+			"text" => &self.text,
+			"subtext2" => &self.subtext2,
+			"subtext1" => &self.subtext1,
+			"subtext0" => &self.subtext0,
+			"overlay2" => &self.overlay2,
+			"overlay1" => &self.overlay1,
+			"overlay0" => &self.overlay0,
+			"surface2" => &self.surface2,
+			"surface1" => &self.surface1,
+			"surface0" => &self.surface0,
+			"base" => &self.base,
+			"crust" => &self.crust,
+			"mantle" => &self.mantle,
+			"accent" => &self.accent,
+			"highlight" => &self.highlight,
+			"border" => &self.border,
+			_ => return None,
+		};
+		Some(color)
+	}
+
+	pub fn set(&mut self, field: &str, color: String) {
+		let slot = match field {
+			"text" => &mut self.text,
+			"subtext2" => &mut self.subtext2,
+			"subtext1" => &mut self.subtext1,
+			"subtext0" => &mut self.subtext0,
+			"overlay2" => &mut self.overlay2,
+			"overlay1" => &mut self.overlay1,
+			"overlay0" => &mut self.overlay0,
+			"surface2" => &mut self.surface2,
+			"surface1" => &mut self.surface1,
+			"surface0" => &mut self.surface0,
+			"base" => &mut self.base,
+			"crust" => &mut self.crust,
+			"mantle" => &mut self.mantle,
+			"accent" => &mut self.accent,
+			"highlight" => &mut self.highlight,
+			"border" => &mut self.border,
+			_ => return,
+		};
+		*slot = color;
+	}
+}
+
+/// Parses a `#rgb`, `#rrggbb` or `#rrggbbaa` color, the alpha is dropped.
+pub fn parse_hex_color(text: &str) -> Option<[u8; 3]> {
+	let hex = text.trim().strip_prefix('#')?;
+	if !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+		return None;
+	}
+	let channel = |index: usize, width: usize| {
+		let value = u8::from_str_radix(&hex[index * width..(index + 1) * width], 16).ok()?;
+		Some(if width == 1 { value * 17 } else { value })
+	};
+	let width = match hex.len() {
+		3 => 1,
+		6 | 8 => 2,
+		_ => return None,
+	};
+	Some([channel(0, width)?, channel(1, width)?, channel(2, width)?])
+}
+
+pub fn hex_color([red, green, blue]: [u8; 3]) -> String {
+	format!("#{red:02x}{green:02x}{blue:02x}")
+}
+
+/// Mixes two colors, 0 giving the first one and 1 the second, below 0 going away from the second one.
+pub fn mix_colors(from: [u8; 3], to: [u8; 3], amount: f64) -> [u8; 3] {
+	let mut mixed = [0; 3];
+	for channel in 0..3 {
+		let value = f64::from(from[channel]) + (f64::from(to[channel]) - f64::from(from[channel])) * amount;
+		mixed[channel] = value.clamp(0.0, 255.0).round() as u8;
+	}
+	mixed
+}
+
+/// Relative luminance of a color as defined by WCAG, from 0 for black to 1 for white.
+pub fn relative_luminance(rgb: [u8; 3]) -> f64 {
+	let [red, green, blue] = rgb.map(|channel| {
+		let channel = f64::from(channel) / 255.0;
+		if channel <= 0.03928 {
+			channel / 12.92
+		} else {
+			((channel + 0.055) / 1.055).powf(2.4)
+		}
+	});
+	0.2126 * red + 0.7152 * green + 0.0722 * blue
+}
+
+/// Name of the file of a theme, its name in camel case like the bundled themes.
+pub fn theme_file_name(name: &str) -> String {
+	let mut file_name = String::new();
+	for (index, word) in name
+		.split(|ch: char| !ch.is_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.enumerate()
+	{
+		let mut chars = word.chars();
+		if let Some(first) = chars.next() {
+			if index == 0 {
+				file_name.extend(first.to_lowercase());
+			} else {
+				file_name.extend(first.to_uppercase());
+			}
+			file_name.push_str(chars.as_str());
+		}
+	}
+	if file_name.is_empty() {
+		file_name.push_str("theme");
+	}
+	format!("{file_name}.toml")
+}
+
+/// Writes a theme to a new file of the themes directory, named after the theme. Returns the path of the file.
+pub fn save_new_theme(themes_dir: &Path, theme: &Theme) -> Result<PathBuf, String> {
+	let content = toml::to_string(theme).map_err(|e| e.to_string())?;
+	let file_name = theme_file_name(&theme.info.name);
+	let mut path = themes_dir.join(&file_name);
+	let mut number = 1;
+	while path.exists() {
+		number += 1;
+		path = themes_dir.join(file_name.replace(".toml", &format!("{number}.toml")));
+	}
+	fs::write(&path, content).map_err(|e| e.to_string())?;
+	Ok(path)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ThemeListItem {
 	pub filename: String,
@@ -158,31 +686,212 @@ impl Theme {
 				crust: "#202325".to_string(),
 				mantle: "#0d0e0f".to_string(),
 				accent: "#ff4081".to_string(),
-				highlight: "#ffa726".to_string(),
+				highlight: "#d27000".to_string(),
 				border: "#424242".to_string(),
 			},
+			tokens: None,
 		}
 	}
 }
 
-fn list_toml_names(dir: &PathBuf) -> std::io::Result<Vec<(String, PathBuf)>> {
-	let mut names = Vec::new();
-
-	for entry in fs::read_dir(dir)? {
-		let entry = entry?;
-		let path = entry.path();
+/// Lists the built-in themes and the themes of the directory by name, a theme of the directory replacing
+/// the built-in theme with the same name. The themes that can not be loaded are returned with their errors.
+fn list_themes(dir: &Path) -> (Vec<(String, ThemeSource)>, Vec<InvalidTheme>) {
+	let mut themes: Vec<(String, ThemeSource)> = Vec::new();
+	let mut invalid_themes = Vec::new();
+	for (file_name, content) in BUILT_IN_THEMES {
+		let source = ThemeSource::BuiltIn(file_name.to_string());
+		match parse_theme(content) {
+			Ok(theme) => themes.push((theme.info.name, source)),
+			Err(errors) => {
+				log_theme_errors(&source, &errors);
+				invalid_themes.push((source, errors));
+			}
+		}
+	}
 
-		if path.extension().and_then(|e| e.to_str()) == Some("toml") {
-			let theme = fs::read_to_string(&path).unwrap_or_default();
-			if let Ok(theme) = toml::from_str::<Theme>(&theme) {
-				let theme_name = theme.info.name;
-				names.push((theme_name, path));
-			} else {
-				log::error!("{path:?} is not a theme file!")
+	match fs::read_dir(dir) {
+		Ok(entries) => {
+			for path in entries.flatten().map(|entry| entry.path()) {
+				if path.extension().and_then(|e| e.to_str()) != Some("toml") {
+					continue;
+				}
+				let source = ThemeSource::File(path);
+				match source.load() {
+					Ok(theme) => {
+						themes.retain(|(name, _)| *name != theme.info.name);
+						themes.push((theme.info.name, source));
+					}
+					Err(errors) => {
+						log_theme_errors(&source, &errors);
+						invalid_themes.push((source, errors));
+					}
+				}
 			}
 		}
+		Err(e) => log::error!("Failed to list themes in directory: {}", e),
+	}
+	themes.sort_by_key(|(name, _)| name.to_lowercase());
+	(themes, invalid_themes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// An empty directory in the temporary directory of the system.
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("rhyolite-{name}-{}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn parses_the_built_in_themes() {
+		for (file_name, content) in BUILT_IN_THEMES {
+			assert!(parse_theme(content).is_ok(), "{file_name}: {:?}", parse_theme(content).err());
+		}
+	}
+
+	#[test]
+	fn parses_a_saved_theme() {
+		let theme = Theme::default();
+		assert_eq!(parse_theme(&toml::to_string(&theme).unwrap()), Ok(theme));
+	}
+
+	#[test]
+	fn lists_every_error_of_a_theme() {
+		let mut table = toml::Table::try_from(Theme::default()).unwrap();
+		let info = table["info"].as_table_mut().unwrap();
+		info.remove("author");
+		info.insert("colorscheme".to_string(), "Dim".into());
+		let colors = table["colors"].as_table_mut().unwrap();
+		colors.remove("accent");
+		colors.insert("text".to_string(), "white".into());
+		colors.insert("border".to_string(), 1.into());
+
+		let errors = parse_theme(&table.to_string()).unwrap_err();
+		assert_eq!(
+			errors,
+			vec![
+				"missing key info.author, expected a string",
+				"info.colorscheme = \"Dim\" is not \"Light\" or \"Dark\"",
+				"colors.text = \"white\" is not a color like \"#1e1e2e\"",
+				"missing key colors.accent",
+				"colors.border must be a color string like \"#1e1e2e\"",
+			]
+		);
+		assert_eq!(parse_theme("[info").unwrap_err().len(), 1);
+	}
+
+	#[test]
+	fn advanced_themes_need_their_tokens() {
+		let mut theme = Theme::default();
+		theme.info.themetype = ThemeType::Advance;
+		theme.tokens = Some(Tokens::derive(&theme.colors));
+		let content = toml::to_string(&theme).unwrap();
+		assert_eq!(parse_theme(&content), Ok(theme));
+
+		let content = content.replace("[tokens.syntax]", "[tokens.unused]");
+		let errors = parse_theme(&content).unwrap_err();
+		assert_eq!(errors, vec!["missing [tokens.syntax] table"]);
+	}
+
+	#[test]
+	fn gets_and_sets_colors_by_name() {
+		let mut colors = Theme::default().colors;
+		for field in Colors::FIELDS {
+			colors.set(field, format!("#{field}"));
+		}
+		for field in Colors::FIELDS {
+			assert_eq!(colors.get(field), Some(format!("#{field}").as_str()));
+		}
+		assert_eq!(colors.get("unknown"), None);
+	}
+
+	#[test]
+	fn parses_hex_colors() {
+		assert_eq!(parse_hex_color("#1e1e2e"), Some([0x1e, 0x1e, 0x2e]));
+		assert_eq!(parse_hex_color(" #FFF "), Some([255, 255, 255]));
+		assert_eq!(parse_hex_color("#1e1e2e80"), Some([0x1e, 0x1e, 0x2e]));
+		assert_eq!(parse_hex_color("1e1e2e"), None);
+		assert_eq!(parse_hex_color("#1e1e2"), None);
+		assert_eq!(parse_hex_color("#+1e1e2"), None);
+		assert!(is_theme_color("transparent"));
+		assert_eq!(hex_color([0x1e, 0x1e, 0x2e]), "#1e1e2e");
+	}
+
+	#[test]
+	fn names_theme_files_in_camel_case() {
+		assert_eq!(theme_file_name("Rose Pine Moon"), "rosePineMoon.toml");
+		assert_eq!(theme_file_name("one-dark (pro)"), "oneDarkPro.toml");
+		assert_eq!(theme_file_name("!!!"), "theme.toml");
 	}
-	names.sort_by_key(|a| a.0.to_lowercase());
 
-	Ok(names)
+	#[test]
+	fn saves_new_themes_without_overwriting() {
+		let dir = temp_dir("save-new-theme");
+		let mut theme = Theme::default();
+		theme.info.name = "My Theme".to_string();
+		let first = save_new_theme(&dir, &theme).unwrap();
+		let second = save_new_theme(&dir, &theme).unwrap();
+		assert_eq!(first, dir.join("myTheme.toml"));
+		assert_eq!(second, dir.join("myTheme2.toml"));
+		let saved: Theme = toml::from_str(&fs::read_to_string(second).unwrap()).unwrap();
+		assert_eq!(saved, theme);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn previews_themes_and_restores_the_theme_in_use() {
+		let dir = temp_dir("preview-theme");
+		let (mut light, mut dark) = (Theme::default(), Theme::default());
+		light.info.name = "Light".to_string();
+		dark.info.name = "Dark".to_string();
+		let light_source = ThemeSource::File(save_new_theme(&dir, &light).unwrap());
+		let dark_source = ThemeSource::File(save_new_theme(&dir, &dark).unwrap());
+
+		let mut store = ThemesStore {
+			themes_dir: dir.clone(),
+			..Default::default()
+		};
+		let in_use = store.current_theme.clone();
+		store.preview_theme(light_source);
+		store.preview_theme(dark_source.clone());
+		assert!(store.is_previewing());
+		assert_eq!(store.current_theme, dark);
+		assert_eq!(store.current_theme_source, Some(dark_source));
+
+		// The theme restored is the one in use before the first preview.
+		store.end_preview();
+		assert!(!store.is_previewing());
+		assert_eq!(store.current_theme, in_use);
+		assert_eq!(store.current_theme_source, None);
+
+		store.preview_theme(ThemeSource::File(dir.join("missing.toml")));
+		assert!(!store.is_previewing());
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn user_themes_replace_the_built_in_themes_with_their_name() {
+		let dir = temp_dir("list-themes");
+		let mut theme = Theme::default();
+		save_new_theme(&dir, &theme).unwrap();
+		theme.info.name = "Mine".to_string();
+		save_new_theme(&dir, &theme).unwrap();
+		fs::write(dir.join("broken.toml"), "[info").unwrap();
+
+		let (themes, invalid_themes) = list_themes(&dir);
+		assert_eq!(themes.len(), BUILT_IN_THEMES.len() + 1);
+		assert_eq!(invalid_themes.len(), 1);
+		assert_eq!(invalid_themes[0].0, ThemeSource::File(dir.join("broken.toml")));
+		let source_of = |name: &str| themes.iter().find(|(theme_name, _)| theme_name == name).map(|(_, source)| source);
+		assert_eq!(source_of("Default"), Some(&ThemeSource::File(dir.join("default.toml"))));
+		assert_eq!(source_of("Mine"), Some(&ThemeSource::File(dir.join("mine.toml"))));
+		assert!(source_of("Catppuccin Mocha").is_some_and(ThemeSource::is_built_in));
+		assert!(themes.is_sorted_by_key(|(name, _)| name.to_lowercase()));
+		fs::remove_dir_all(dir).unwrap();
+	}
 }
@@ -3,10 +3,18 @@
 //!
 //! All the required global statics/constants are declared in this module.
 
-use crate::data::themes::Theme;
+use crate::data::{
+	daily_notes::DailyNotesConfig,
+	frontmatter::{FrontMatter, parse_front_matter},
+	themes::Theme,
+};
 use freya::hooks::UseEditable;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{
+	collections::{BTreeSet, HashMap},
+	path::PathBuf,
+	time::Duration,
+};
 
 /// Name of the Default Note Title used by the app!
 pub const APP_DATA_DIR: &str = "rhyolite";
@@ -22,6 +30,31 @@ pub const DEFAULT_TROVE_DIR: &str = "Rhyolite Trove";
 /// Name of the Default Note Title used by the app!
 pub const DEFAULT_NOTE_TITLE: &str = "Untitled";
 
+/// Name of the folder of a Trove containing the note templates.
+pub const TEMPLATES_DIR: &str = "Templates";
+
+/// Name of the folder of the app state directory storing the undo history of the notes.
+pub const UNDO_HISTORY_DIR: &str = "undo";
+
+/// Name of the file of the config directory storing the key bindings.
+pub const KEYBINDINGS_FILE: &str = "keybindings.toml";
+
+/// Name of the file of the config directory storing the settings.
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+/// Name of the folder of the config directory holding the fonts of the user.
+pub const FONTS_DIR: &str = "fonts";
+
+/// Name of the word list of the config directory used by the spell check, with one word per line.
+pub const DICTIONARY_FILE: &str = "dictionary.txt";
+
+/// Word lists of the system used by the spell check when the config directory has none. Windows has no
+/// word list.
+#[cfg(unix)]
+pub const SYSTEM_DICTIONARIES: &[&str] = &["/usr/share/dict/words", "/usr/dict/words"];
+#[cfg(not(unix))]
+pub const SYSTEM_DICTIONARIES: &[&str] = &[];
+
 #[derive(Clone, PartialEq)]
 pub struct MarkdownFile {
 	pub path: PathBuf,
@@ -29,6 +62,13 @@ pub struct MarkdownFile {
 	pub editable: UseEditable,
 }
 
+impl MarkdownFile {
+	/// Parses the front matter properties of the current contents of the file.
+	pub fn front_matter(&self) -> Option<FrontMatter> {
+		parse_front_matter(&self.editable.editor().read().to_string())
+	}
+}
+
 /// Denotes a tab in the editor.
 #[derive(Debug, Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub struct Tab {
@@ -52,6 +92,92 @@ pub struct UserData {
 	pub last_open_tab: usize,              // Stores the tab id of the last open tab
 	pub recent_files: Vec<RecentFileInfo>, // Stores the list of recently created files
 	pub current_theme: Theme,              // Stores the current theme color palette
+	#[serde(default)]
+	pub folded_lines: HashMap<PathBuf, BTreeSet<usize>>, // Stores the lines where a fold starts, for each file
+	#[serde(default)]
+	pub daily_notes: DailyNotesConfig, // Stores the folder and the template of the daily notes
+	#[serde(default)]
+	pub persist_undo_history: bool, // Stores whether the undo history of the notes is kept across restarts
+	#[serde(default)]
+	pub vim_mode: bool,  // Stores whether the Vim modal editing layer is on
+}
+
+/// What the command palette lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteMode {
+	Commands,
+	Templates(TemplateTarget),
+}
+
+/// Where a template picked in the command palette is inserted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateTarget {
+	NewNote,
+	CurrentNote,
+}
+
+/// Panels that can be shown next to the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SidePanel {
+	Tags,
+	Outline,
+	Calendar,
+	Tasks,
+	History,
+}
+
+/// A notification stacked over the app until it is dismissed or its time is up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+	pub id: usize,
+	pub level: ToastLevel,
+	pub message: String,
+	pub actions: Vec<ToastAction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+	Info,
+	Warning,
+	Error,
+}
+
+impl ToastLevel {
+	pub fn title(self) -> &'static str {
+		match self {
+			ToastLevel::Info => "Info",
+			ToastLevel::Warning => "Warning",
+			ToastLevel::Error => "Error",
+		}
+	}
+
+	/// How long a toast stays, the errors long enough to reach their buttons.
+	pub fn duration(self) -> Duration {
+		match self {
+			ToastLevel::Info => Duration::from_secs(4),
+			ToastLevel::Warning => Duration::from_secs(7),
+			ToastLevel::Error => Duration::from_secs(12),
+		}
+	}
+}
+
+/// Button of a toast, running it dismisses the toast.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToastAction {
+	RetrySave(PathBuf), // Saves the open note at the path again
+	RetrySaveUserdata,
+	UndoDelete { path: PathBuf, content: String }, // Writes the deleted note back and opens it
+	OpenLog,
+}
+
+impl ToastAction {
+	pub fn label(&self) -> &'static str {
+		match self {
+			ToastAction::RetrySave(_) | ToastAction::RetrySaveUserdata => "Retry",
+			ToastAction::UndoDelete { .. } => "Undo",
+			ToastAction::OpenLog => "Open log",
+		}
+	}
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -0,0 +1,466 @@
+//! # Undo Tree.
+//! History of the edits of a document, as a tree: undoing then editing starts a new branch instead of
+//! discarding the undone edits.
+//!
+//! Consecutive typing (or deleting) in the same place is grouped in a single edit, until a pause, a new
+//! word or a newline. The tree keeps the current text of the document, edits are recorded by comparing
+//! the text of the buffer with it.
+//!
+//! The history saved to disk only holds the edits, with a hash of the text they lead to: it is restored
+//! for the content of the note when the hash matches.
+
+use crate::data::text_edit::TextEdit;
+use serde::{Deserialize, Serialize};
+
+/// Longest pause between two keystrokes grouped in the same edit, in milliseconds.
+const GROUP_TIMEOUT_MS: i64 = 1000;
+
+/// Most edits kept in the history, the oldest ones are forgotten past it.
+const MAX_HISTORY_LENGTH: usize = 1000;
+
+/// Longest text shown in the summary of an edit.
+const SUMMARY_LENGTH: usize = 24;
+
+/// A replacement of text in the document, all indices are char indices.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change {
+	pub start: usize,
+	pub removed: String,
+	pub inserted: String,
+}
+
+impl Change {
+	/// Returns the change turning the old text into the new one, if they differ.
+	fn between(old: &str, new: &str) -> Option<Change> {
+		let edit = TextEdit::between(old, new);
+		if edit.range.is_empty() && edit.text.is_empty() {
+			return None;
+		}
+		Some(Change {
+			start: edit.range.start,
+			removed: old.chars().skip(edit.range.start).take(edit.range.len()).collect(),
+			inserted: edit.text,
+		})
+	}
+
+	fn forward(&self) -> TextEdit {
+		TextEdit::replace(self.start..self.start + self.removed.chars().count(), self.inserted.clone())
+	}
+
+	fn backward(&self) -> TextEdit {
+		TextEdit::replace(self.start..self.start + self.inserted.chars().count(), self.removed.clone())
+	}
+
+	/// Merges the following change in this one if both are part of the same typing or deleting.
+	fn merge(&mut self, next: &Change) -> bool {
+		let is_typing = self.removed.is_empty() && next.removed.is_empty();
+		let is_deleting = self.inserted.is_empty() && next.inserted.is_empty();
+
+		if is_typing && next.start == self.start + self.inserted.chars().count() {
+			// A new word or line starts a new edit.
+			let word_break = next.inserted.starts_with(char::is_whitespace) && !self.inserted.ends_with(char::is_whitespace);
+			if word_break || next.inserted.contains('\n') || self.inserted.contains('\n') {
+				return false;
+			}
+			self.inserted.push_str(&next.inserted);
+			true
+		} else if is_deleting && next.start + next.removed.chars().count() == self.start {
+			// Backspace
+			self.removed.insert_str(0, &next.removed);
+			self.start = next.start;
+			true
+		} else if is_deleting && next.start == self.start {
+			// Delete
+			self.removed.push_str(&next.removed);
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Short description of the change, shown in the history panel.
+	pub fn summary(&self) -> String {
+		let shorten = |text: &str| {
+			let mut short: String = text.chars().take(SUMMARY_LENGTH).collect::<String>().replace('\n', "⏎");
+			if text.chars().count() > SUMMARY_LENGTH {
+				short.push('…');
+			}
+			short
+		};
+		match (self.removed.is_empty(), self.inserted.is_empty()) {
+			(true, _) => format!("Typed \"{}\"", shorten(&self.inserted)),
+			(false, true) => format!("Deleted \"{}\"", shorten(&self.removed)),
+			(false, false) => format!("Replaced \"{}\" with \"{}\"", shorten(&self.removed), shorten(&self.inserted)),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Node {
+	parent: usize,
+	change: Change,
+	time: i64,                 // When the edit was last extended, in milliseconds since the epoch
+	redo_child: Option<usize>, // Child followed by redo, the last one undone or created
+	first_child: bool,         // Whether the node is the first edit made after its parent
+}
+
+/// An entry of the history, as listed in the history panel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+	pub index: usize,
+	pub branch: usize, // Number of alternative branches taken from the root to this edit
+	pub summary: String,
+	pub time: i64,
+	pub current: bool,
+	pub saved: bool,
+}
+
+/// The history of a document. Node 0 is the root, the document before any edit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndoTree {
+	nodes: Vec<Node>,
+	current: usize,
+	saved: Option<usize>,
+	text: String,
+	grouping: bool, // Whether the next edit can be grouped with the current one
+}
+
+/// The history of a document as saved to disk, the text is the content of the note.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedHistory {
+	nodes: Vec<Node>,
+	current: usize,
+	saved: Option<usize>,
+	text_hash: String, // Hash of the text at the current edit
+}
+
+impl UndoTree {
+	pub fn new(text: &str) -> UndoTree {
+		let root = Node {
+			parent: 0,
+			change: Change {
+				start: 0,
+				removed: String::new(),
+				inserted: String::new(),
+			},
+			time: 0,
+			redo_child: None,
+			first_child: true,
+		};
+		UndoTree {
+			nodes: vec![root],
+			current: 0,
+			saved: Some(0),
+			text: text.to_string(),
+			grouping: false,
+		}
+	}
+
+	/// The text of the document at the current edit.
+	pub fn text(&self) -> &str {
+		&self.text
+	}
+
+	/// The history to save, without the text of the document.
+	pub fn to_saved(&self) -> SavedHistory {
+		SavedHistory {
+			nodes: self.nodes.clone(),
+			current: self.current,
+			saved: self.saved,
+			text_hash: text_hash(&self.text),
+		}
+	}
+
+	/// Restores a saved history for the given text, `None` if it was saved for another text.
+	pub fn from_saved(saved: SavedHistory, text: &str) -> Option<UndoTree> {
+		if saved.current >= saved.nodes.len() || saved.text_hash != text_hash(text) {
+			return None;
+		}
+		Some(UndoTree {
+			nodes: saved.nodes,
+			current: saved.current,
+			saved: saved.saved,
+			text: text.to_string(),
+			grouping: false,
+		})
+	}
+
+	/// Records the edit turning the current text into the given one, `time` being in milliseconds.
+	pub fn record(&mut self, new_text: &str, time: i64) {
+		let Some(change) = Change::between(&self.text, new_text) else {
+			return;
+		};
+		self.text = new_text.to_string();
+
+		let current = &mut self.nodes[self.current];
+		let can_group = self.grouping
+			&& self.current != 0
+			&& self.saved != Some(self.current)
+			&& current.redo_child.is_none()
+			&& time - current.time < GROUP_TIMEOUT_MS;
+		if can_group && current.change.merge(&change) {
+			current.time = time;
+			return;
+		}
+
+		let index = self.nodes.len();
+		let first_child = !self.nodes.iter().skip(1).any(|node| node.parent == self.current);
+		self.nodes.push(Node {
+			parent: self.current,
+			change,
+			time,
+			redo_child: None,
+			first_child,
+		});
+		self.nodes[self.current].redo_child = Some(index);
+		self.current = index;
+		self.grouping = true;
+		self.forget_oldest_edits();
+	}
+
+	/// Undoes the current edit, returns the edit to apply to the document.
+	pub fn undo(&mut self) -> Option<TextEdit> {
+		if self.current == 0 {
+			return None;
+		}
+		let node = &self.nodes[self.current];
+		let edit = node.change.backward();
+		let parent = node.parent;
+		self.nodes[parent].redo_child = Some(self.current);
+		self.current = parent;
+		self.apply(&edit);
+		Some(edit)
+	}
+
+	/// Redoes the last undone edit, returns the edit to apply to the document.
+	pub fn redo(&mut self) -> Option<TextEdit> {
+		let child = self.nodes[self.current].redo_child?;
+		let edit = self.nodes[child].change.forward();
+		self.current = child;
+		self.apply(&edit);
+		Some(edit)
+	}
+
+	/// Moves to any edit of the tree, returns the edit to apply to the document.
+	pub fn jump_to(&mut self, target: usize) -> Option<TextEdit> {
+		if target >= self.nodes.len() || target == self.current {
+			return None;
+		}
+		let old_text = self.text.clone();
+		let target_path = self.path_from_root(target);
+		let mut cursor = None;
+
+		// Undo up to the common ancestor, then redo down to the target.
+		while !target_path.contains(&self.current) {
+			cursor = self.undo().map(|edit| edit.cursor);
+		}
+		let start = target_path.iter().position(|node| *node == self.current).unwrap_or_default();
+		for window in target_path[start..].windows(2) {
+			self.nodes[window[0]].redo_child = Some(window[1]);
+			cursor = self.redo().map(|edit| edit.cursor);
+		}
+
+		let edit = TextEdit::between(&old_text, &self.text);
+		Some(match cursor {
+			Some(cursor) => edit.with_cursor(cursor),
+			None => edit,
+		})
+	}
+
+	/// Goes back (or forward) to the text of the last save, returns the edit to apply to the document.
+	pub fn undo_to_last_save(&mut self) -> Option<TextEdit> {
+		self.jump_to(self.saved?)
+	}
+
+	/// Marks the given text as saved, recording it first if it is not the current text.
+	pub fn mark_saved(&mut self, text: &str, time: i64) {
+		self.record(text, time);
+		self.saved = Some(self.current);
+		self.grouping = false;
+	}
+
+	/// Lists the edits in the order they were made.
+	pub fn entries(&self) -> Vec<HistoryEntry> {
+		let mut branches = vec![0; self.nodes.len()];
+		(0..self.nodes.len())
+			.map(|index| {
+				let node = &self.nodes[index];
+				if index != 0 {
+					branches[index] = branches[node.parent] + usize::from(!node.first_child);
+				}
+				HistoryEntry {
+					index,
+					branch: branches[index],
+					summary: if index == 0 { "Opened".to_string() } else { node.change.summary() },
+					time: node.time,
+					current: index == self.current,
+					saved: self.saved == Some(index),
+				}
+			})
+			.collect()
+	}
+
+	fn apply(&mut self, edit: &TextEdit) {
+		let mut chars: Vec<char> = self.text.chars().collect();
+		chars.splice(edit.range.clone(), edit.text.chars());
+		self.text = chars.into_iter().collect();
+		self.grouping = false;
+	}
+
+	/// Keeps the history under its maximum length. The oldest edit is node 1, the first child of the root: if the
+	/// current edit comes from it, it becomes the root and the other branches of the root are dropped, otherwise
+	/// it is dropped with its branch.
+	fn forget_oldest_edits(&mut self) {
+		while self.nodes.len() > MAX_HISTORY_LENGTH {
+			let oldest = 1;
+			let mut keep = vec![true; self.nodes.len()];
+			if self.path_from_root(self.current).get(1) == Some(&oldest) {
+				keep[0] = false;
+				for (index, node) in self.nodes.iter().enumerate().skip(oldest + 1) {
+					keep[index] = node.parent != 0;
+				}
+				let root = &mut self.nodes[oldest];
+				root.parent = oldest;
+				root.change = Change {
+					start: 0,
+					removed: String::new(),
+					inserted: String::new(),
+				};
+			} else {
+				keep[oldest] = false;
+			}
+			// Edits are made after their parent, so the parent of a node always comes before it.
+			for index in oldest + 1..self.nodes.len() {
+				keep[index] = keep[index] && keep[self.nodes[index].parent];
+			}
+
+			let mut new_indices = vec![0; self.nodes.len()];
+			let mut kept = 0;
+			for (index, new_index) in new_indices.iter_mut().enumerate() {
+				if keep[index] {
+					*new_index = kept;
+					kept += 1;
+				}
+			}
+			let mut has_child = vec![false; kept];
+			self.nodes = std::mem::take(&mut self.nodes)
+				.into_iter()
+				.enumerate()
+				.filter(|(index, _)| keep[*index])
+				.map(|(index, mut node)| {
+					node.parent = new_indices[node.parent];
+					node.redo_child = node.redo_child.filter(|child| keep[*child]).map(|child| new_indices[child]);
+					node.first_child = new_indices[index] == 0 || !std::mem::replace(&mut has_child[node.parent], true);
+					node
+				})
+				.collect();
+			self.current = new_indices[self.current];
+			self.saved = self.saved.filter(|saved| keep[*saved]).map(|saved| new_indices[saved]);
+		}
+	}
+
+	/// Returns the nodes from the root to the given node.
+	fn path_from_root(&self, mut index: usize) -> Vec<usize> {
+		let mut path = vec![index];
+		while index != 0 {
+			index = self.nodes[index].parent;
+			path.push(index);
+		}
+		path.reverse();
+		path
+	}
+}
+
+/// FNV-1a hash of a text, which unlike the std hasher is stable across releases.
+pub fn text_hash(text: &str) -> String {
+	let hash = text.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+		(hash ^ byte as u64).wrapping_mul(0x100000001b3)
+	});
+	format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Records one edit per word, each made long after the previous one so they are not grouped.
+	fn type_words(tree: &mut UndoTree, count: usize) {
+		for _ in 0..count {
+			let text = format!("{}w ", tree.text());
+			let time = tree.nodes.iter().map(|node| node.time).max().unwrap_or_default() + GROUP_TIMEOUT_MS;
+			tree.record(&text, time);
+		}
+	}
+
+	#[test]
+	fn groups_typing_until_a_new_word() {
+		let mut tree = UndoTree::new("");
+		tree.record("a", 0);
+		tree.record("ab", 10);
+		tree.record("ab c", 20);
+		assert_eq!(tree.entries().len(), 3);
+		tree.undo();
+		assert_eq!(tree.text(), "ab");
+		tree.undo();
+		assert_eq!(tree.text(), "");
+		assert_eq!(tree.undo(), None);
+	}
+
+	#[test]
+	fn editing_after_undo_starts_a_branch() {
+		let mut tree = UndoTree::new("");
+		type_words(&mut tree, 2);
+		tree.undo();
+		tree.record("w x", i64::MAX / 2);
+		let entries = tree.entries();
+		assert_eq!(entries.iter().map(|entry| entry.branch).collect::<Vec<_>>(), vec![0, 0, 0, 1]);
+		tree.jump_to(2);
+		assert_eq!(tree.text(), "w w ");
+	}
+
+	#[test]
+	fn history_is_capped() {
+		let mut tree = UndoTree::new("");
+		type_words(&mut tree, MAX_HISTORY_LENGTH + 10);
+		assert_eq!(tree.nodes.len(), MAX_HISTORY_LENGTH);
+		assert_eq!(tree.saved, None);
+		while tree.undo().is_some() {}
+		assert_eq!(tree.text(), "w ".repeat(11));
+		while tree.redo().is_some() {}
+		assert_eq!(tree.text(), "w ".repeat(MAX_HISTORY_LENGTH + 10));
+	}
+
+	#[test]
+	fn capping_drops_the_oldest_branch_first() {
+		let mut tree = UndoTree::new("");
+		type_words(&mut tree, 5);
+		while tree.undo().is_some() {}
+		tree.mark_saved("saved", 0);
+		type_words(&mut tree, MAX_HISTORY_LENGTH - 6);
+		assert_eq!(tree.nodes.len(), MAX_HISTORY_LENGTH - 4);
+		assert_eq!(tree.nodes[1].change.inserted, "saved");
+
+		let entries = tree.entries();
+		assert!(entries.iter().all(|entry| entry.branch == 0));
+		assert_eq!(entries.iter().filter(|entry| entry.saved).count(), 1);
+		tree.undo_to_last_save();
+		assert_eq!(tree.text(), "saved");
+		while tree.undo().is_some() {}
+		assert_eq!(tree.text(), "");
+	}
+
+	#[test]
+	fn saved_history_is_restored_for_the_same_text() {
+		let mut tree = UndoTree::new("");
+		type_words(&mut tree, 3);
+		tree.undo();
+		let saved: SavedHistory = toml::from_str(&toml::to_string(&tree.to_saved()).unwrap()).unwrap();
+		assert!(!toml::to_string(&saved).unwrap().contains("w w "));
+		assert_eq!(UndoTree::from_saved(saved.clone(), "w w x"), None);
+
+		let mut restored = UndoTree::from_saved(saved, "w w ").expect("the same text");
+		assert_eq!(restored, UndoTree { grouping: false, ..tree });
+		restored.redo();
+		assert_eq!(restored.text(), "w w w ");
+	}
+}
@@ -0,0 +1,1203 @@
+//! # Vim Mode.
+//! Optional modal editing layer between the key handlers of the document view and the editor buffer.
+//!
+//! Supports the normal, insert, visual (charwise and linewise) and command-line modes, the common motions,
+//! the `d`, `c`, `y`, `>` and `<` operators with counts and text objects, pasting, `.` repeat and the `:w`
+//! and `:q` commands. Keys are applied to a copy of the text and the result is returned as a single
+//! `TextEdit`. In insert mode the keys go to the editor, so that the markdown editing features keep working.
+
+use crate::data::{
+	list_editing::{INDENT, outdent_line},
+	text_edit::TextEdit,
+};
+use std::ops::Range;
+
+/// Largest count accepted before a command, larger counts are clamped.
+const MAX_COUNT: usize = 9999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VimMode {
+	#[default]
+	Normal,
+	Insert,
+	Visual,
+	VisualLine,
+	Command,
+}
+
+impl VimMode {
+	pub fn label(self) -> &'static str {
+		match self {
+			VimMode::Normal => "NORMAL",
+			VimMode::Insert => "INSERT",
+			VimMode::Visual => "VISUAL",
+			VimMode::VisualLine => "VISUAL LINE",
+			VimMode::Command => "COMMAND",
+		}
+	}
+
+	fn is_visual(self) -> bool {
+		matches!(self, VimMode::Visual | VimMode::VisualLine)
+	}
+}
+
+/// A key as seen by the Vim layer, other keys go to the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimKey {
+	Char(char),
+	Escape,
+	Enter,
+	Backspace,
+	Tab,
+	Redo, // Ctrl+R
+}
+
+/// Actions of the app requested by a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimCommand {
+	Save,
+	Close,
+	SaveAndClose,
+	Undo,
+	Redo,
+}
+
+/// The result of a key.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VimOutput {
+	pub handled: bool,          // Whether the key was consumed, otherwise it goes to the editor
+	pub edit: Option<TextEdit>, // Change of the text, the cursor and the selection
+	pub yanked: Option<String>, // Text put in the register
+	pub command: Option<VimCommand>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+	Left,
+	Right,
+	Up,
+	Down,
+	WordForward(bool), // Whether the motion is on WORDs, separated by whitespace only
+	WordBackward(bool),
+	WordEnd(bool),
+	LineStart,
+	FirstNonBlank,
+	LineEnd,
+	DocumentStart,
+	DocumentEnd,
+	Find { target: char, till: bool, backwards: bool },
+}
+
+/// How an operator treats the text between the cursor and the target of a motion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Span {
+	Exclusive,
+	Inclusive,
+	Lines,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextObject {
+	Word(bool),
+	Quote(char),
+	Delimited(char, char),
+	Paragraph,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+	Delete,
+	Change,
+	Yank,
+	Indent,
+	Outdent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+	Motion(Motion, usize),
+	Object(TextObject, bool), // Whether the object includes its surroundings
+	Lines,
+	Selection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InsertAt {
+	Cursor,
+	AfterCursor,
+	LineStart,
+	LineEnd,
+	LineBelow,
+	LineAbove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+	Toggle,
+	Lower,
+	Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+	Move(Motion),
+	Operate(Operator, Target),
+	Paste(bool), // Whether the text goes before the cursor
+	Insert(InsertAt),
+	Visual(bool), // Whether the selection is linewise
+	SelectObject(TextObject, bool),
+	SwapSelectionEnds,
+	JoinLines,
+	ChangeCase(Case),
+	Replace(char),
+	Undo,
+	Repeat,
+	CommandLine,
+}
+
+impl Action {
+	/// Whether the action changes the text, and can be repeated with `.`.
+	fn is_change(self) -> bool {
+		match self {
+			Action::Operate(operator, _) => operator != Operator::Yank,
+			Action::Paste(_) | Action::Insert(_) | Action::JoinLines | Action::ChangeCase(_) | Action::Replace(_) => true,
+			_ => false,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Command {
+	count: Option<usize>,
+	action: Action,
+}
+
+enum Parsed<T> {
+	Incomplete,
+	Invalid,
+	Complete(T),
+}
+
+/// State of the Vim layer, kept across keys and documents.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Vim {
+	mode: VimMode,
+	pending: Vec<char>,     // Keys of the command being typed
+	command_line: String,   // Text typed after `:`
+	visual_anchor: usize,   // Fixed end of the visual selection
+	register: String,       // Last yanked or deleted text, linewise if it ends with a newline
+	recording: Vec<VimKey>, // Keys of the change being made
+	last_change: Vec<VimKey>,
+	replaying: bool,
+}
+
+impl Vim {
+	pub fn mode(&self) -> VimMode {
+		self.mode
+	}
+
+	/// Text shown in the bottom bar: the mode and the keys typed so far.
+	pub fn status(&self) -> String {
+		match self.mode {
+			VimMode::Command => format!(":{}", self.command_line),
+			mode if self.pending.is_empty() => mode.label().to_string(),
+			mode => format!("{} {}", mode.label(), self.pending.iter().collect::<String>()),
+		}
+	}
+
+	/// Goes back to normal mode, dropping the keys typed so far.
+	pub fn reset(&mut self) {
+		self.mode = VimMode::Normal;
+		self.pending.clear();
+		self.command_line.clear();
+	}
+
+	/// Replaces the register, with the content of the clipboard before pasting.
+	pub fn set_register(&mut self, text: String) {
+		self.register = text;
+	}
+
+	/// Handles a key with the given text and cursor of the document.
+	pub fn handle_key(&mut self, key: VimKey, text: &str, cursor: usize) -> VimOutput {
+		let mut output = VimOutput {
+			handled: true,
+			..VimOutput::default()
+		};
+		if self.mode == VimMode::Insert && key != VimKey::Escape {
+			self.recording.push(key);
+			output.handled = false;
+			return output;
+		}
+
+		let chars: Vec<char> = text.chars().collect();
+		let mut buffer = Buffer {
+			cursor: cursor.min(chars.len()),
+			chars,
+		};
+		self.key(key, &mut buffer, &mut output);
+		if self.mode == VimMode::Normal {
+			buffer.cursor = buffer.normal_cursor(buffer.cursor);
+		}
+
+		let new_text: String = buffer.chars.iter().collect();
+		let edit = if new_text == text {
+			TextEdit::replace(0..0, "")
+		} else {
+			TextEdit::between(text, &new_text)
+		}
+		.with_cursor(buffer.cursor);
+		output.edit = Some(match self.selection(&buffer) {
+			Some((start, end)) => edit.with_selection(start, end),
+			None => edit,
+		});
+		output
+	}
+
+	fn key(&mut self, key: VimKey, buffer: &mut Buffer, output: &mut VimOutput) {
+		match (self.mode, key) {
+			(VimMode::Insert, VimKey::Escape) => self.leave_insert(buffer),
+			// Only reached when replaying a change, typed keys go to the editor.
+			(VimMode::Insert, key) => match key {
+				VimKey::Char(ch) => buffer.insert(&ch.to_string()),
+				VimKey::Enter => buffer.insert("\n"),
+				VimKey::Tab => buffer.insert("\t"),
+				VimKey::Backspace if buffer.cursor > 0 => {
+					buffer.replace(buffer.cursor - 1..buffer.cursor, "");
+					buffer.cursor -= 1;
+				}
+				_ => (),
+			},
+			(VimMode::Command, VimKey::Char(ch)) => self.command_line.push(ch),
+			(VimMode::Command, VimKey::Backspace) => {
+				if self.command_line.pop().is_none() {
+					self.mode = VimMode::Normal;
+				}
+			}
+			(VimMode::Command, VimKey::Enter) => {
+				let line = std::mem::take(&mut self.command_line);
+				self.mode = VimMode::Normal;
+				run_command_line(line.trim(), buffer, output);
+			}
+			(VimMode::Command, _) => self.reset(),
+			(mode, VimKey::Escape) => {
+				self.pending.clear();
+				if mode.is_visual() {
+					self.mode = VimMode::Normal;
+				}
+			}
+			(_, VimKey::Redo) => {
+				self.pending.clear();
+				output.command = Some(VimCommand::Redo);
+			}
+			(_, VimKey::Char(ch)) => self.pending_key(ch, buffer, output),
+			(_, VimKey::Enter) => self.pending_key('j', buffer, output),
+			(_, VimKey::Backspace) => self.pending_key('h', buffer, output),
+			(_, VimKey::Tab) => (),
+		}
+	}
+
+	fn pending_key(&mut self, key: char, buffer: &mut Buffer, output: &mut VimOutput) {
+		self.pending.push(key);
+		match parse(&self.pending, self.mode.is_visual()) {
+			Parsed::Incomplete => (),
+			Parsed::Invalid => self.pending.clear(),
+			Parsed::Complete(command) => {
+				let keys = std::mem::take(&mut self.pending);
+				self.execute(command, keys, buffer, output);
+			}
+		}
+	}
+
+	fn execute(&mut self, command: Command, keys: Vec<char>, buffer: &mut Buffer, output: &mut VimOutput) {
+		let times = command.count.unwrap_or(1);
+		let records = self.mode == VimMode::Normal && !self.replaying && command.action.is_change();
+		if records {
+			self.recording = keys.into_iter().map(VimKey::Char).collect();
+		}
+
+		match command.action {
+			Action::Move(motion) => {
+				if let Some((target, _)) = buffer.motion_target(motion, times, command.count.is_some()) {
+					buffer.cursor = target;
+				}
+			}
+			Action::Operate(operator, target) => {
+				let range = match target {
+					Target::Selection => Some(self.selection_range(buffer)),
+					Target::Lines => Some((buffer.cursor..buffer.down(buffer.cursor, times - 1), true)),
+					Target::Object(object, around) => buffer.object_range(object, around),
+					Target::Motion(motion, count) => buffer.operator_range(operator, motion, times * count),
+				};
+				if self.mode.is_visual() {
+					self.mode = VimMode::Normal;
+				}
+				if let Some((range, linewise)) = range {
+					self.operate(operator, range, linewise, buffer, output);
+				}
+			}
+			Action::Paste(before) => {
+				// Pasting over a selection replaces it.
+				if self.mode.is_visual() {
+					let (range, linewise) = self.selection_range(buffer);
+					let range = if linewise { buffer.lines_range(range) } else { range };
+					buffer.replace(range.clone(), "");
+					buffer.cursor = range.start;
+					self.mode = VimMode::Normal;
+				}
+				self.paste(before, times, buffer);
+			}
+			Action::Insert(at) => {
+				buffer.open_insert(at);
+				self.mode = VimMode::Insert;
+			}
+			Action::Visual(linewise) => {
+				let mode = if linewise { VimMode::VisualLine } else { VimMode::Visual };
+				if self.mode == mode {
+					self.mode = VimMode::Normal;
+				} else {
+					if !self.mode.is_visual() {
+						self.visual_anchor = buffer.cursor;
+					}
+					self.mode = mode;
+				}
+			}
+			Action::SelectObject(object, around) => {
+				if let Some((range, linewise)) = buffer.object_range(object, around) {
+					let range = if linewise { buffer.lines_range(range) } else { range };
+					self.visual_anchor = range.start;
+					buffer.cursor = range.end.saturating_sub(1).max(range.start);
+					if linewise {
+						self.mode = VimMode::VisualLine;
+					}
+				}
+			}
+			Action::SwapSelectionEnds => std::mem::swap(&mut self.visual_anchor, &mut buffer.cursor),
+			Action::JoinLines => buffer.join_lines(times.max(2) - 1),
+			Action::ChangeCase(case) => {
+				// `~` moves past the changed chars, the case of a selection leaves the cursor at its start.
+				let visual = self.mode.is_visual();
+				let range = if visual {
+					let (range, linewise) = self.selection_range(buffer);
+					self.mode = VimMode::Normal;
+					if linewise { buffer.lines_range(range) } else { range }
+				} else {
+					buffer.cursor..(buffer.cursor + times).min(buffer.line_end(buffer.cursor))
+				};
+				buffer.change_case(range.clone(), case);
+				buffer.cursor = if visual { range.start } else { range.end };
+			}
+			Action::Replace(ch) => {
+				let end = buffer.cursor + times;
+				if end <= buffer.line_end(buffer.cursor) {
+					buffer.replace(buffer.cursor..end, &ch.to_string().repeat(times));
+					buffer.cursor = end - 1;
+				}
+			}
+			Action::Undo => output.command = Some(VimCommand::Undo),
+			Action::Repeat => {
+				if !self.replaying {
+					self.replaying = true;
+					for key in self.last_change.clone() {
+						self.key(key, buffer, output);
+					}
+					self.replaying = false;
+				}
+			}
+			Action::CommandLine => {
+				self.mode = VimMode::Command;
+				self.command_line.clear();
+			}
+		}
+
+		if records && self.mode != VimMode::Insert {
+			self.last_change = std::mem::take(&mut self.recording);
+		}
+	}
+
+	fn leave_insert(&mut self, buffer: &mut Buffer) {
+		self.mode = VimMode::Normal;
+		if buffer.cursor > buffer.line_start(buffer.cursor) {
+			buffer.cursor -= 1;
+		}
+		if !self.replaying {
+			self.recording.push(VimKey::Escape);
+			self.last_change = std::mem::take(&mut self.recording);
+		}
+	}
+
+	fn operate(&mut self, operator: Operator, range: Range<usize>, linewise: bool, buffer: &mut Buffer, output: &mut VimOutput) {
+		let mut range = if linewise { buffer.lines_range(range) } else { range };
+		match operator {
+			Operator::Yank => {
+				self.yank(buffer.slice(range.clone()), linewise, output);
+				if !linewise || self.mode.is_visual() {
+					buffer.cursor = range.start;
+				}
+			}
+			Operator::Delete => {
+				self.yank(buffer.slice(range.clone()), linewise, output);
+				// Deleting the last lines removes the newline before them.
+				if linewise && range.end == buffer.chars.len() && range.start > 0 && buffer.chars[range.end - 1] != '\n' {
+					range.start -= 1;
+				}
+				buffer.replace(range.clone(), "");
+				buffer.cursor = if linewise {
+					buffer.first_non_blank(range.start)
+				} else {
+					range.start
+				};
+			}
+			Operator::Change => {
+				self.yank(buffer.slice(range.clone()), linewise, output);
+				// Changing lines keeps an empty line, with the indentation of the first one. The range is empty on
+				// an empty last line, and in an empty document.
+				if linewise {
+					if range.end > range.start && buffer.chars[range.end - 1] == '\n' {
+						range.end -= 1;
+					}
+					range.start = buffer.first_non_blank(range.start);
+				}
+				buffer.replace(range.clone(), "");
+				buffer.cursor = range.start;
+				self.mode = VimMode::Insert;
+			}
+			Operator::Indent | Operator::Outdent => {
+				let range = buffer.lines_range(range);
+				let lines: Vec<String> = buffer
+					.slice(range.clone())
+					.split('\n')
+					.map(|line| match operator {
+						Operator::Outdent => outdent_line(line),
+						_ if line.is_empty() => String::new(),
+						_ => format!("{INDENT}{line}"),
+					})
+					.collect();
+				buffer.replace(range.clone(), &lines.join("\n"));
+				buffer.cursor = buffer.first_non_blank(range.start);
+			}
+		}
+	}
+
+	fn yank(&mut self, mut text: String, linewise: bool, output: &mut VimOutput) {
+		if linewise && !text.ends_with('\n') {
+			text.push('\n');
+		}
+		self.register = text.clone();
+		output.yanked = Some(text);
+	}
+
+	fn paste(&mut self, before: bool, times: usize, buffer: &mut Buffer) {
+		if self.register.is_empty() {
+			return;
+		}
+		let text = self.register.repeat(times);
+		if self.register.ends_with('\n') {
+			let line_end = buffer.line_end(buffer.cursor);
+			if before {
+				let start = buffer.line_start(buffer.cursor);
+				buffer.replace(start..start, &text);
+				buffer.cursor = start;
+			} else if line_end == buffer.chars.len() {
+				// After the last line, which has no newline.
+				buffer.replace(line_end..line_end, &format!("\n{}", &text[..text.len() - 1]));
+				buffer.cursor = line_end + 1;
+			} else {
+				buffer.replace(line_end + 1..line_end + 1, &text);
+				buffer.cursor = line_end + 1;
+			}
+			buffer.cursor = buffer.first_non_blank(buffer.cursor);
+		} else {
+			let at = if before {
+				buffer.cursor
+			} else {
+				(buffer.cursor + 1).min(buffer.line_end(buffer.cursor))
+			};
+			buffer.replace(at..at, &text);
+			buffer.cursor = at + text.chars().count() - 1;
+		}
+	}
+
+	/// The text selected in visual mode, and whether it is linewise.
+	fn selection_range(&self, buffer: &Buffer) -> (Range<usize>, bool) {
+		let anchor = self.visual_anchor.min(buffer.chars.len());
+		let (start, end) = (anchor.min(buffer.cursor), anchor.max(buffer.cursor));
+		match self.mode {
+			VimMode::VisualLine => (start..end, true),
+			_ => (start..(end + 1).min(buffer.chars.len()), false),
+		}
+	}
+
+	/// The selection shown in the editor.
+	fn selection(&self, buffer: &Buffer) -> Option<(usize, usize)> {
+		let (range, linewise) = self.selection_range(buffer);
+		match self.mode {
+			VimMode::Visual => Some((range.start, range.end)),
+			VimMode::VisualLine if linewise => Some((buffer.line_start(range.start), buffer.line_end(range.end))),
+			_ => None,
+		}
+	}
+}
+
+/// Runs a command typed after `:`.
+fn run_command_line(line: &str, buffer: &mut Buffer, output: &mut VimOutput) {
+	output.command = match line {
+		"w" | "w!" => Some(VimCommand::Save),
+		"q" | "q!" => Some(VimCommand::Close),
+		"wq" | "wq!" | "x" => Some(VimCommand::SaveAndClose),
+		_ => {
+			// `:<number>` goes to a line.
+			if let Ok(row) = line.parse::<usize>() {
+				buffer.cursor = buffer.first_non_blank(buffer.row_start(row.saturating_sub(1)));
+			}
+			None
+		}
+	};
+}
+
+/// Parses the keys typed so far as a command.
+fn parse(keys: &[char], visual: bool) -> Parsed<Command> {
+	let (count, keys) = parse_count(keys);
+	let Some(&first) = keys.first() else {
+		return Parsed::Incomplete;
+	};
+	let complete = |action| {
+		if keys.len() == 1 {
+			Parsed::Complete(Command { count, action })
+		} else {
+			Parsed::Invalid
+		}
+	};
+
+	if visual {
+		let action = match first {
+			'd' | 'x' => Some(Action::Operate(Operator::Delete, Target::Selection)),
+			'c' | 's' => Some(Action::Operate(Operator::Change, Target::Selection)),
+			'y' => Some(Action::Operate(Operator::Yank, Target::Selection)),
+			'>' => Some(Action::Operate(Operator::Indent, Target::Selection)),
+			'<' => Some(Action::Operate(Operator::Outdent, Target::Selection)),
+			'p' | 'P' => Some(Action::Paste(true)),
+			'o' => Some(Action::SwapSelectionEnds),
+			'~' => Some(Action::ChangeCase(Case::Toggle)),
+			'u' => Some(Action::ChangeCase(Case::Lower)),
+			'U' => Some(Action::ChangeCase(Case::Upper)),
+			_ => None,
+		};
+		if let Some(action) = action {
+			return complete(action);
+		}
+		match keys {
+			['i' | 'a'] => return Parsed::Incomplete,
+			[kind @ ('i' | 'a'), object] => {
+				return match parse_object(*object) {
+					Some(object) => Parsed::Complete(Command {
+						count,
+						action: Action::SelectObject(object, *kind == 'a'),
+					}),
+					None => Parsed::Invalid,
+				};
+			}
+			_ => (),
+		}
+	}
+
+	let operator = match first {
+		'd' => Some(Operator::Delete),
+		'c' => Some(Operator::Change),
+		'y' => Some(Operator::Yank),
+		'>' => Some(Operator::Indent),
+		'<' => Some(Operator::Outdent),
+		_ => None,
+	};
+	if let Some(operator) = operator {
+		return match parse_target(first, &keys[1..]) {
+			Parsed::Complete(target) => Parsed::Complete(Command {
+				count,
+				action: Action::Operate(operator, target),
+			}),
+			Parsed::Incomplete => Parsed::Incomplete,
+			Parsed::Invalid => Parsed::Invalid,
+		};
+	}
+
+	let action = match first {
+		'x' => Action::Operate(Operator::Delete, Target::Motion(Motion::Right, 1)),
+		'X' => Action::Operate(Operator::Delete, Target::Motion(Motion::Left, 1)),
+		'D' => Action::Operate(Operator::Delete, Target::Motion(Motion::LineEnd, 1)),
+		'C' => Action::Operate(Operator::Change, Target::Motion(Motion::LineEnd, 1)),
+		's' => Action::Operate(Operator::Change, Target::Motion(Motion::Right, 1)),
+		'S' => Action::Operate(Operator::Change, Target::Lines),
+		'Y' => Action::Operate(Operator::Yank, Target::Lines),
+		'p' => Action::Paste(false),
+		'P' => Action::Paste(true),
+		'i' => Action::Insert(InsertAt::Cursor),
+		'a' => Action::Insert(InsertAt::AfterCursor),
+		'I' => Action::Insert(InsertAt::LineStart),
+		'A' => Action::Insert(InsertAt::LineEnd),
+		'o' => Action::Insert(InsertAt::LineBelow),
+		'O' => Action::Insert(InsertAt::LineAbove),
+		'v' => Action::Visual(false),
+		'V' => Action::Visual(true),
+		'J' => Action::JoinLines,
+		'~' => Action::ChangeCase(Case::Toggle),
+		'u' => Action::Undo,
+		'.' => Action::Repeat,
+		':' => Action::CommandLine,
+		'r' => {
+			return match keys {
+				[_] => Parsed::Incomplete,
+				[_, ch] => Parsed::Complete(Command {
+					count,
+					action: Action::Replace(*ch),
+				}),
+				_ => Parsed::Invalid,
+			};
+		}
+		_ => {
+			return match parse_motion(keys) {
+				Parsed::Complete(motion) => Parsed::Complete(Command {
+					count,
+					action: Action::Move(motion),
+				}),
+				Parsed::Incomplete => Parsed::Incomplete,
+				Parsed::Invalid => Parsed::Invalid,
+			};
+		}
+	};
+	complete(action)
+}
+
+/// Splits the count typed before a command from the rest of the keys.
+fn parse_count(keys: &[char]) -> (Option<usize>, &[char]) {
+	let digits = keys
+		.iter()
+		.enumerate()
+		.take_while(|(index, key)| key.is_ascii_digit() && (*index > 0 || **key != '0'))
+		.count();
+	let count = keys[..digits]
+		.iter()
+		.collect::<String>()
+		.parse::<usize>()
+		.ok()
+		.map(|count| count.min(MAX_COUNT));
+	(count, &keys[digits..])
+}
+
+/// Parses what follows an operator: the same operator for whole lines, a text object or a motion.
+fn parse_target(operator: char, keys: &[char]) -> Parsed<Target> {
+	let (count, keys) = parse_count(keys);
+	match keys {
+		[] => Parsed::Incomplete,
+		[key] if *key == operator => Parsed::Complete(Target::Lines),
+		['i' | 'a'] => Parsed::Incomplete,
+		[kind @ ('i' | 'a'), object] => match parse_object(*object) {
+			Some(object) => Parsed::Complete(Target::Object(object, *kind == 'a')),
+			None => Parsed::Invalid,
+		},
+		_ => match parse_motion(keys) {
+			Parsed::Complete(motion) => Parsed::Complete(Target::Motion(motion, count.unwrap_or(1))),
+			Parsed::Incomplete => Parsed::Incomplete,
+			Parsed::Invalid => Parsed::Invalid,
+		},
+	}
+}
+
+/// Parses the key naming a text object, after `i` or `a`.
+fn parse_object(key: char) -> Option<TextObject> {
+	Some(match key {
+		'w' => TextObject::Word(false),
+		'W' => TextObject::Word(true),
+		'p' => TextObject::Paragraph,
+		'"' | '\'' | '`' => TextObject::Quote(key),
+		'(' | ')' | 'b' => TextObject::Delimited('(', ')'),
+		'[' | ']' => TextObject::Delimited('[', ']'),
+		'{' | '}' | 'B' => TextObject::Delimited('{', '}'),
+		'<' | '>' => TextObject::Delimited('<', '>'),
+		_ => return None,
+	})
+}
+
+fn parse_motion(keys: &[char]) -> Parsed<Motion> {
+	let motion = match keys {
+		['h'] => Motion::Left,
+		['l' | ' '] => Motion::Right,
+		['k'] => Motion::Up,
+		['j'] => Motion::Down,
+		['w'] => Motion::WordForward(false),
+		['W'] => Motion::WordForward(true),
+		['b'] => Motion::WordBackward(false),
+		['B'] => Motion::WordBackward(true),
+		['e'] => Motion::WordEnd(false),
+		['E'] => Motion::WordEnd(true),
+		['0'] => Motion::LineStart,
+		['^'] => Motion::FirstNonBlank,
+		['$'] => Motion::LineEnd,
+		['G'] => Motion::DocumentEnd,
+		['g'] | ['f' | 't' | 'F' | 'T'] => return Parsed::Incomplete,
+		['g', 'g'] => Motion::DocumentStart,
+		[kind @ ('f' | 't' | 'F' | 'T'), target] => Motion::Find {
+			target: *target,
+			till: matches!(kind, 't' | 'T'),
+			backwards: matches!(kind, 'F' | 'T'),
+		},
+		_ => return Parsed::Invalid,
+	};
+	Parsed::Complete(motion)
+}
+
+/// Kind of a char for word motions: whitespace, word chars or punctuation. WORDs only separate whitespace.
+fn char_class(ch: char, big: bool) -> u8 {
+	if ch.is_whitespace() {
+		0
+	} else if big || ch.is_alphanumeric() || ch == '_' {
+		1
+	} else {
+		2
+	}
+}
+
+fn is_blank(ch: char) -> bool {
+	ch == ' ' || ch == '\t'
+}
+
+/// The text of the document being edited, with the cursor, as char indices.
+struct Buffer {
+	chars: Vec<char>,
+	cursor: usize,
+}
+
+impl Buffer {
+	fn slice(&self, range: Range<usize>) -> String {
+		self.chars[range].iter().collect()
+	}
+
+	fn replace(&mut self, range: Range<usize>, text: &str) {
+		self.chars.splice(range, text.chars());
+	}
+
+	fn insert(&mut self, text: &str) {
+		self.replace(self.cursor..self.cursor, text);
+		self.cursor += text.chars().count();
+	}
+
+	fn line_start(&self, index: usize) -> usize {
+		self.chars[..index]
+			.iter()
+			.rposition(|ch| *ch == '\n')
+			.map_or(0, |newline| newline + 1)
+	}
+
+	/// Index of the newline ending the line, or the end of the text.
+	fn line_end(&self, index: usize) -> usize {
+		self.chars[index..]
+			.iter()
+			.position(|ch| *ch == '\n')
+			.map_or(self.chars.len(), |newline| index + newline)
+	}
+
+	fn first_non_blank(&self, index: usize) -> usize {
+		let end = self.line_end(index);
+		(self.line_start(index)..end)
+			.find(|index| !is_blank(self.chars[*index]))
+			.unwrap_or(end)
+	}
+
+	/// Start of the given line, or of the last line.
+	fn row_start(&self, row: usize) -> usize {
+		if row == 0 {
+			return 0;
+		}
+		self.chars
+			.iter()
+			.enumerate()
+			.filter(|(_, ch)| **ch == '\n')
+			.nth(row - 1)
+			.map_or_else(|| self.line_start(self.chars.len()), |(newline, _)| newline + 1)
+	}
+
+	/// In normal mode the cursor is on a char, not after the last one of the line.
+	fn normal_cursor(&self, index: usize) -> usize {
+		if index > self.line_start(index) && index == self.line_end(index) {
+			index - 1
+		} else {
+			index
+		}
+	}
+
+	/// Whole lines of the range, with the newline ending the last one.
+	fn lines_range(&self, range: Range<usize>) -> Range<usize> {
+		let end = self.line_end(range.end);
+		self.line_start(range.start)..(end + 1).min(self.chars.len())
+	}
+
+	/// Moves down the given number of lines, keeping the column.
+	fn down(&self, index: usize, rows: usize) -> usize {
+		let column = index - self.line_start(index);
+		let mut start = self.line_start(index);
+		for _ in 0..rows {
+			let end = self.line_end(start);
+			if end == self.chars.len() {
+				break;
+			}
+			start = end + 1;
+		}
+		(start + column).min(self.line_end(start))
+	}
+
+	fn up(&self, index: usize, rows: usize) -> usize {
+		let column = index - self.line_start(index);
+		let mut start = self.line_start(index);
+		for _ in 0..rows {
+			if start == 0 {
+				break;
+			}
+			start = self.line_start(start - 1);
+		}
+		(start + column).min(self.line_end(start))
+	}
+
+	fn word_forward(&self, mut index: usize, big: bool) -> usize {
+		let length = self.chars.len();
+		if index >= length {
+			return length;
+		}
+		let class = char_class(self.chars[index], big);
+		if class != 0 {
+			while index < length && char_class(self.chars[index], big) == class {
+				index += 1;
+			}
+		}
+		// Skipping whitespace, an empty line is a word.
+		while index < length && char_class(self.chars[index], big) == 0 {
+			if self.chars[index] == '\n' && self.chars.get(index + 1) == Some(&'\n') {
+				return index + 1;
+			}
+			index += 1;
+		}
+		index
+	}
+
+	fn word_end(&self, index: usize, big: bool) -> usize {
+		let length = self.chars.len();
+		let mut index = index + 1;
+		while index < length && char_class(self.chars[index], big) == 0 {
+			index += 1;
+		}
+		if index >= length {
+			return length.saturating_sub(1);
+		}
+		let class = char_class(self.chars[index], big);
+		while index + 1 < length && char_class(self.chars[index + 1], big) == class {
+			index += 1;
+		}
+		index
+	}
+
+	fn word_backward(&self, index: usize, big: bool) -> usize {
+		if index == 0 {
+			return 0;
+		}
+		let mut index = index - 1;
+		while index > 0 && char_class(self.chars[index], big) == 0 {
+			index -= 1;
+		}
+		let class = char_class(self.chars[index], big);
+		while index > 0 && char_class(self.chars[index - 1], big) == class {
+			index -= 1;
+		}
+		index
+	}
+
+	/// Returns where the motion moves the cursor, and how an operator treats the text up to there.
+	fn motion_target(&self, motion: Motion, times: usize, count_given: bool) -> Option<(usize, Span)> {
+		let cursor = self.cursor;
+		let repeat = |step: &dyn Fn(usize) -> usize| (0..times).fold(cursor, |index, _| step(index));
+		let target = match motion {
+			Motion::Left => (cursor.saturating_sub(times).max(self.line_start(cursor)), Span::Exclusive),
+			Motion::Right => ((cursor + times).min(self.line_end(cursor)), Span::Exclusive),
+			Motion::Up => (self.up(cursor, times), Span::Lines),
+			Motion::Down => (self.down(cursor, times), Span::Lines),
+			Motion::WordForward(big) => (repeat(&|index| self.word_forward(index, big)), Span::Exclusive),
+			Motion::WordBackward(big) => (repeat(&|index| self.word_backward(index, big)), Span::Exclusive),
+			Motion::WordEnd(big) => (repeat(&|index| self.word_end(index, big)), Span::Inclusive),
+			Motion::LineStart => (self.line_start(cursor), Span::Exclusive),
+			Motion::FirstNonBlank => (self.first_non_blank(cursor), Span::Exclusive),
+			Motion::LineEnd => (self.line_end(self.down(cursor, times - 1)), Span::Exclusive),
+			Motion::DocumentStart | Motion::DocumentEnd => {
+				let start = match (count_given, motion) {
+					(true, _) => self.row_start(times - 1),
+					(false, Motion::DocumentStart) => 0,
+					(false, _) => self.line_start(self.chars.len()),
+				};
+				(self.first_non_blank(start), Span::Lines)
+			}
+			Motion::Find { target, till, backwards } => {
+				let found = if backwards {
+					(self.line_start(cursor)..cursor)
+						.rev()
+						.filter(|index| self.chars[*index] == target)
+						.nth(times - 1)?
+				} else {
+					(cursor + 1..self.line_end(cursor))
+						.filter(|index| self.chars[*index] == target)
+						.nth(times - 1)?
+				};
+				match (till, backwards) {
+					(false, false) => (found, Span::Inclusive),
+					(true, false) => (found - 1, Span::Inclusive),
+					(false, true) => (found, Span::Exclusive),
+					(true, true) => (found + 1, Span::Exclusive),
+				}
+			}
+		};
+		Some(target)
+	}
+
+	/// The text an operator applies to with a motion, and whether it is linewise.
+	fn operator_range(&self, operator: Operator, motion: Motion, times: usize) -> Option<(Range<usize>, bool)> {
+		let on_word = self.chars.get(self.cursor).is_some_and(|ch| !ch.is_whitespace());
+		let (mut target, span) = match motion {
+			// `cw` changes up to the end of the word, like `ce`.
+			Motion::WordForward(big) if operator == Operator::Change && on_word => {
+				self.motion_target(Motion::WordEnd(big), times, false)?
+			}
+			_ => self.motion_target(motion, times, false)?,
+		};
+		// A word motion does not take the operator past the end of the line.
+		let line_end = self.line_end(self.cursor);
+		if matches!(motion, Motion::WordForward(_)) && target > line_end && line_end > self.cursor {
+			target = line_end;
+		}
+		let (start, end) = (self.cursor.min(target), self.cursor.max(target));
+		Some(match span {
+			Span::Exclusive => (start..end, false),
+			Span::Inclusive => (start..(end + 1).min(self.chars.len()), false),
+			Span::Lines => (start..end, true),
+		})
+	}
+
+	fn object_range(&self, object: TextObject, around: bool) -> Option<(Range<usize>, bool)> {
+		let length = self.chars.len();
+		let cursor = self.cursor;
+		let range = match object {
+			TextObject::Word(big) => {
+				let ch = *self.chars.get(cursor)?;
+				if ch == '\n' {
+					return None;
+				}
+				let class = char_class(ch, big);
+				let same = |index: usize| self.chars[index] != '\n' && char_class(self.chars[index], big) == class;
+				let mut start = cursor;
+				while start > 0 && same(start - 1) {
+					start -= 1;
+				}
+				let mut end = cursor + 1;
+				while end < length && same(end) {
+					end += 1;
+				}
+				if around {
+					// The whitespace after the word, or before it if there is none after.
+					let trailing = (end..length).take_while(|index| is_blank(self.chars[*index])).count();
+					if trailing > 0 {
+						end += trailing;
+					} else {
+						let line_start = self.line_start(cursor);
+						while start > line_start && is_blank(self.chars[start - 1]) {
+							start -= 1;
+						}
+					}
+				}
+				start..end
+			}
+			TextObject::Quote(quote) => {
+				let quotes: Vec<usize> = (self.line_start(cursor)..self.line_end(cursor))
+					.filter(|index| self.chars[*index] == quote)
+					.collect();
+				let pair = quotes.chunks_exact(2).find(|pair| cursor <= pair[1])?;
+				if around { pair[0]..pair[1] + 1 } else { pair[0] + 1..pair[1] }
+			}
+			TextObject::Delimited(open, close) => {
+				let open_index = if self.chars.get(cursor) == Some(&open) {
+					cursor
+				} else {
+					let mut depth = 0;
+					let mut found = None;
+					for index in (0..cursor).rev() {
+						if self.chars[index] == close {
+							depth += 1;
+						} else if self.chars[index] == open {
+							if depth == 0 {
+								found = Some(index);
+								break;
+							}
+							depth -= 1;
+						}
+					}
+					found?
+				};
+				let mut depth = 0;
+				let mut close_index = None;
+				for index in open_index + 1..length {
+					if self.chars[index] == open {
+						depth += 1;
+					} else if self.chars[index] == close {
+						if depth == 0 {
+							close_index = Some(index);
+							break;
+						}
+						depth -= 1;
+					}
+				}
+				let close_index = close_index?;
+				if around {
+					open_index..close_index + 1
+				} else {
+					open_index + 1..close_index
+				}
+			}
+			TextObject::Paragraph => {
+				let is_blank_line = |start: usize| (start..self.line_end(start)).all(|index| is_blank(self.chars[index]));
+				let blank = is_blank_line(self.line_start(cursor));
+				let mut start = self.line_start(cursor);
+				while start > 0 && is_blank_line(self.line_start(start - 1)) == blank {
+					start = self.line_start(start - 1);
+				}
+				let mut end = self.line_end(cursor);
+				let extend = |end: usize, blank: bool| {
+					let mut end = end;
+					while end < length && is_blank_line(end + 1) == blank {
+						end = self.line_end(end + 1);
+					}
+					end
+				};
+				end = extend(end, blank);
+				if around && !blank {
+					end = extend(end, true);
+				}
+				return Some((start..end, true));
+			}
+		};
+		Some((range, false))
+	}
+
+	/// Places the cursor for insert mode, opening a new line if needed.
+	fn open_insert(&mut self, at: InsertAt) {
+		let cursor = self.cursor;
+		let indent: String = self.chars[self.line_start(cursor)..self.first_non_blank(cursor)].iter().collect();
+		self.cursor = match at {
+			InsertAt::Cursor => cursor,
+			InsertAt::AfterCursor => (cursor + 1).min(self.line_end(cursor)),
+			InsertAt::LineStart => self.first_non_blank(cursor),
+			InsertAt::LineEnd => self.line_end(cursor),
+			InsertAt::LineBelow => {
+				let end = self.line_end(cursor);
+				self.replace(end..end, &format!("\n{indent}"));
+				end + 1 + indent.chars().count()
+			}
+			InsertAt::LineAbove => {
+				let start = self.line_start(cursor);
+				self.replace(start..start, &format!("{indent}\n"));
+				start + indent.chars().count()
+			}
+		};
+	}
+
+	/// Joins the line of the cursor with the given number of following lines, separated by a space.
+	fn join_lines(&mut self, joins: usize) {
+		for _ in 0..joins {
+			let end = self.line_end(self.cursor);
+			if end == self.chars.len() {
+				break;
+			}
+			let blanks = (end + 1..self.chars.len()).take_while(|index| is_blank(self.chars[*index])).count();
+			let next_empty = self.chars.get(end + 1 + blanks).is_none_or(|ch| *ch == '\n');
+			let separator = if next_empty || end == self.line_start(end) || is_blank(self.chars[end - 1]) {
+				""
+			} else {
+				" "
+			};
+			self.replace(end..end + 1 + blanks, separator);
+			self.cursor = end;
+		}
+	}
+
+	fn change_case(&mut self, range: Range<usize>, case: Case) {
+		let changed: String = self.chars[range.clone()]
+			.iter()
+			.flat_map(|ch| match case {
+				Case::Upper => ch.to_uppercase().collect::<Vec<char>>(),
+				Case::Lower => ch.to_lowercase().collect(),
+				Case::Toggle if ch.is_lowercase() => ch.to_uppercase().collect(),
+				Case::Toggle => ch.to_lowercase().collect(),
+			})
+			.collect();
+		self.replace(range, &changed);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Types the keys in normal mode, `\x1b` being Escape, and returns the text, the cursor and the mode.
+	fn type_keys(text: &str, cursor: usize, keys: &str) -> (String, usize, VimMode) {
+		let mut vim = Vim::default();
+		let mut text = text.to_string();
+		let mut cursor = cursor;
+		for ch in keys.chars() {
+			let key = if ch == '\x1b' { VimKey::Escape } else { VimKey::Char(ch) };
+			let output = vim.handle_key(key, &text, cursor);
+			if !output.handled {
+				let mut chars: Vec<char> = text.chars().collect();
+				chars.insert(cursor, ch);
+				text = chars.into_iter().collect();
+				cursor += 1;
+				continue;
+			}
+			if let Some(edit) = output.edit {
+				let mut chars: Vec<char> = text.chars().collect();
+				chars.splice(edit.range, edit.text.chars());
+				text = chars.into_iter().collect();
+				cursor = edit.cursor;
+			}
+		}
+		(text, cursor, vim.mode())
+	}
+
+	#[test]
+	fn motions_move_the_cursor() {
+		assert_eq!(type_keys("foo bar baz", 0, "w").1, 4);
+		assert_eq!(type_keys("foo bar baz", 0, "2w").1, 8);
+		assert_eq!(type_keys("foo bar baz", 0, "e").1, 2);
+		assert_eq!(type_keys("foo bar baz", 8, "b").1, 4);
+		assert_eq!(type_keys("foo bar baz", 0, "$").1, 10);
+		assert_eq!(type_keys("one\ntwo\nthree", 1, "j").1, 5);
+		assert_eq!(type_keys("one\ntwo\nthree", 0, "G").1, 8);
+	}
+
+	#[test]
+	fn operators_apply_to_motions() {
+		assert_eq!(type_keys("foo bar", 0, "dw").0, "bar");
+		assert_eq!(type_keys("foo bar", 0, "cwbaz\x1b").0, "baz bar");
+		assert_eq!(type_keys("foo bar", 4, "D").0, "foo ");
+		assert_eq!(type_keys("a b c", 0, "x.").0, "b c");
+	}
+
+	#[test]
+	fn deleting_the_last_line_removes_the_newline_before_it() {
+		assert_eq!(type_keys("one\ntwo", 4, "dd").0, "one");
+		assert_eq!(type_keys("one\ntwo\nthree", 0, "2dd").0, "three");
+	}
+
+	#[test]
+	fn changing_lines_keeps_the_indentation() {
+		let (text, cursor, mode) = type_keys("  foo\nbar", 3, "cc");
+		assert_eq!((text.as_str(), cursor, mode), ("  \nbar", 2, VimMode::Insert));
+	}
+
+	#[test]
+	fn changing_lines_of_an_empty_document() {
+		assert_eq!(type_keys("", 0, "cc"), (String::new(), 0, VimMode::Insert));
+		assert_eq!(type_keys("", 0, "S"), (String::new(), 0, VimMode::Insert));
+		assert_eq!(type_keys("one\n", 4, "S"), ("one\n".to_string(), 4, VimMode::Insert));
+	}
+}
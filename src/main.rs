@@ -35,7 +35,10 @@ mod view;
 //-------------------------------------------------------------------------
 // - Imports
 //-------------------------------------------------------------------------
-use data::{io_utils::logger_init, stores::JET_BRAINS_MONO};
+use data::{
+	io_utils::{load_user_fonts, logger_init},
+	stores::JET_BRAINS_MONO,
+};
 use freya::prelude::*;
 use view::app_view::app;
 
@@ -52,19 +55,22 @@ fn main() {
 
 	log::info!("Rhyolite App started, initialising GUI.");
 
+	let mut launch_config = LaunchConfig::new().with_font("JetBrains Mono", JET_BRAINS_MONO);
+	for (family, font) in load_user_fonts() {
+		// The fonts are used until the app exits.
+		launch_config = launch_config.with_font(family.leak(), font.leak());
+	}
+
 	launch_cfg(
-		LaunchConfig::new()
-			.with_font("JetBrains Mono", JET_BRAINS_MONO)
-			.with_default_font("JetBrains Mono")
-			.with_window(
-				WindowConfig::new(app)
-					.with_size(1284.0, 724.0)
-					.with_title("Rhyolite")
-					.with_min_size(400.0, 300.0)
-					.with_decorations(false)
-					.with_transparency(true)
-					.with_background("transparent")
-					.with_icon(LaunchConfig::load_icon(APP_ICON)),
-			),
+		launch_config.with_default_font("JetBrains Mono").with_window(
+			WindowConfig::new(app)
+				.with_size(1284.0, 724.0)
+				.with_title("Rhyolite")
+				.with_min_size(400.0, 300.0)
+				.with_decorations(false)
+				.with_transparency(true)
+				.with_background("transparent")
+				.with_icon(LaunchConfig::load_icon(APP_ICON)),
+		),
 	);
 }
@@ -31,16 +31,26 @@ File Index
 #[allow(unused_imports)]
 use crate::{
 	data::{
-		fn_utils::handle_global_keyboard_input,
+		fn_utils::{handle_global_key_release, handle_global_keyboard_input},
 		io_utils::{deinitialise_app, initialise_app},
 		stores::{
-			CURRENT_EDITOR_BUFFER, CURRENT_TAB, FILES_ARENA, SHOW_COMMAND_PALETTE, SHOW_RECENT_FILES, SHOW_SETTINGS_DROPUP, TABS,
-			THEME_STORE, WORD_CHAR_COUNT, close_settings_dropup, toggle_command_palette, toggle_recent_files,
+			CURRENT_EDITOR_BUFFER, CURRENT_TAB, FILES_ARENA, SETTINGS, SHOW_COMMAND_PALETTE, SHOW_RECENT_FILES, SHOW_SETTINGS_DROPUP,
+			SHOW_SHORTCUTS, TABS, THEME_STORE, WORD_CHAR_COUNT, apply_theme_mode, close_settings_dropup, record_system_appearance,
+			reload_keymap_if_changed, reload_settings_if_changed, reload_themes_if_changed, toggle_command_palette, toggle_recent_files,
+			toggle_shortcuts,
 		},
 	},
-	view::{docview::work_space, palette::palette_box, sidebar::side_bar, top_bar::top_nav_bar},
+	view::{
+		docview::work_space,
+		notification_toast::toast_stack,
+		palette::{command_palette, palette_box},
+		shortcuts::shortcuts_overlay,
+		sidebar::side_bar,
+		top_bar::top_nav_bar,
+	},
 };
 use freya::prelude::*;
+use tokio::time::{Duration, sleep};
 use winit::window::ResizeDirection;
 
 //-------------------------------------------------------------------------
@@ -55,6 +65,22 @@ pub fn app() -> Element {
 		initialise_app();
 	});
 
+	// Reload the key bindings, the settings and the themes when their files are edited, the settings window
+	// saves the settings as they are changed. The appearance of the system is polled too, since the theme events
+	// of the window do not reach the app. It is asked to the window before waiting, so the theme mode knows it
+	// once the wait is over.
+	let platform = use_platform();
+	use_future(move || async move {
+		loop {
+			platform.with_window(|window| record_system_appearance(window.theme()));
+			sleep(Duration::from_secs(1)).await;
+			reload_keymap_if_changed();
+			reload_settings_if_changed();
+			reload_themes_if_changed();
+			apply_theme_mode();
+		}
+	});
+
 	// Update the word and char counts on tab change/keyboard input.
 	use_effect(move || {
 		let editor_content = CURRENT_EDITOR_BUFFER().editor().to_string();
@@ -80,6 +106,7 @@ pub fn app() -> Element {
 			background: theme.crust,
 			direction: "vertical",
 			onglobalkeydown: handle_global_keyboard_input,
+			onglobalkeyup: handle_global_key_release,
 			drag_resize_area {border_size: BORDER_SIZE}
 
 			// Tabs Navigation Bar
@@ -94,9 +121,12 @@ pub fn app() -> Element {
 				work_space{}
 			}
 
-			if SHOW_COMMAND_PALETTE() ^ SHOW_RECENT_FILES() ^ SHOW_SETTINGS_DROPUP() {
+			if SHOW_COMMAND_PALETTE() ^ SHOW_RECENT_FILES() ^ SHOW_SETTINGS_DROPUP() || SHOW_SHORTCUTS() {
 				overlay_view{}
 			}
+
+			// Notifications, over everything else
+			toast_stack{}
 		}
 	)
 }
@@ -111,6 +141,8 @@ pub fn app() -> Element {
 /// Other ways of doing this are by using `use_focus()` to close the floating windows when unfocussed or by using the `onglobalclick` handler.
 #[component]
 pub fn overlay_view() -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
 	let _focus = use_focus();
 	let backdrop_blur_value: u8 = if SHOW_SETTINGS_DROPUP() { 0 } else { 1 };
 	let background_color = if SHOW_SETTINGS_DROPUP() {
@@ -137,19 +169,34 @@ pub fn overlay_view() -> Element {
 				toggle_recent_files();
 			} else if SHOW_COMMAND_PALETTE() {
 				toggle_command_palette();
+			} else if SHOW_SHORTCUTS() {
+				toggle_shortcuts();
 			} else if SHOW_SETTINGS_DROPUP() {
 				close_settings_dropup();
 			}
 		},
 
+		if SHOW_SHORTCUTS() {
+			palette_box{
+				shortcuts_overlay{}
+			}
+		}
+
 		if SHOW_COMMAND_PALETTE() ^ SHOW_RECENT_FILES() {
-			// TODO: Implement the floating palettes.
 			palette_box{
-				// if SHOW_RECENT_FILES() {
-				// 	recent_files_palette{}
-				// } else {
-				// 	command_palette{}
-				// }
+				if SHOW_RECENT_FILES() {
+					// TODO: Implement the recent files palette.
+					paragraph {
+						text {
+							color: "{theme.text}",
+							font_size: "{appearance.ui_size(28.0)}",
+							font_family: "{appearance.font_family}",
+							"To Be Implemented."
+						}
+					}
+				} else {
+					command_palette{}
+				}
 			}
 		}
 	})
@@ -1,9 +1,10 @@
-use crate::data::stores::{THEME_STORE, WORD_CHAR_COUNT};
+use crate::data::stores::{KEYMAP, SETTINGS, THEME_STORE, VIM_MODE, VIM_STATE, WORD_CHAR_COUNT};
 use freya::prelude::*;
 
 #[component]
 pub fn bottom_floating_bar() -> Element {
 	let theme = THEME_STORE().current_theme.colors;
+	let appearance = SETTINGS().appearance;
 
 	// let bar_width = 260;
 	// let bar_height = 30;
@@ -13,7 +14,7 @@ pub fn bottom_floating_bar() -> Element {
 			position_bottom: "10",
 			position_right: "10",
 			width: "fill",
-			height: "30",
+			height: "{appearance.ui_size(30.0)}",
 			// main_align: "end",
 			cross_align: "end",
 			rect {
@@ -31,6 +32,12 @@ pub fn bottom_floating_bar() -> Element {
 				main_align: "space-between",
 				cross_align: "center",
 				padding: "2 10",
+				if let Some(pending) = KEYMAP.read().pending() {
+					pending_chords { pending }
+				}
+				if VIM_MODE() {
+					vim_status {}
+				}
 				word_count {},
 				char_count {}
 			}
@@ -38,15 +45,51 @@ pub fn bottom_floating_bar() -> Element {
 	})
 }
 
+/// The chords typed of an unfinished key sequence.
+#[component]
+fn pending_chords(pending: String) -> Element {
+	let theme = THEME_STORE().current_theme.colors;
+	let appearance = SETTINGS().appearance;
+	rsx!(rect {
+		width: "auto",
+		main_align: "center",
+		margin: "0 12 0 0",
+		label {
+			color: theme.subtext0,
+			font_size: "{appearance.ui_size(15.0)}",
+			font_family: "{appearance.font_family}",
+			"{pending} …"
+		}
+	})
+}
+
+/// The Vim mode, or the command being typed.
+fn vim_status() -> Element {
+	let theme = THEME_STORE().current_theme.colors;
+	let appearance = SETTINGS().appearance;
+	rsx!(rect {
+		width: "auto",
+		main_align: "center",
+		margin: "0 12 0 0",
+		label {
+			color: theme.accent,
+			font_size: "{appearance.ui_size(15.0)}",
+			font_family: "{appearance.font_family}",
+			"{ VIM_STATE.read().status() }"
+		}
+	})
+}
+
 fn word_count() -> Element {
 	let theme = THEME_STORE().current_theme.colors;
+	let appearance = SETTINGS().appearance;
 	rsx!(rect {
 		width: "auto",
 		main_align: "center",
 		label {
 			color: theme.text,
-			font_size: "15",
-			font_family: "JetBrains Mono",
+			font_size: "{appearance.ui_size(15.0)}",
+			font_family: "{appearance.font_family}",
 			"{ WORD_CHAR_COUNT().0 } Words "
 		}
 	})
@@ -54,14 +97,15 @@ fn word_count() -> Element {
 
 fn char_count() -> Element {
 	let theme = THEME_STORE().current_theme.colors;
+	let appearance = SETTINGS().appearance;
 	rsx!(rect {
 		width: "auto",
 		main_align: "center",
 
 		label {
 			color: theme.text,
-			font_size: "15",
-			font_family: "JetBrains Mono",
+			font_size: "{appearance.ui_size(15.0)}",
+			font_family: "{appearance.font_family}",
 			" { WORD_CHAR_COUNT().1 } Characters"
 		}
 	})
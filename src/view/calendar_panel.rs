@@ -0,0 +1,246 @@
+use crate::{
+	data::{
+		daily_notes::{month_weeks, offset_month, parse_daily_note_date},
+		io_utils::{get_default_trove_dir, list_markdown_files},
+		stores::{DAILY_NOTES_CONFIG, SETTINGS, TABS, THEME_STORE, current_file, open_adjacent_daily_note, open_daily_note},
+	},
+	view::side_panel::panel_header,
+};
+use chrono::{Local, NaiveDate};
+use freya::prelude::*;
+use std::collections::BTreeSet;
+
+const WEEKDAYS: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+
+/// Calendar of the daily notes, days with a note are marked and clicking a day opens its note.
+#[component]
+pub fn calendar_panel() -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let today = Local::now().date_naive();
+	let mut shown_month = use_signal(|| today);
+
+	// Dates of the existing daily notes, refreshed when the tabs change as notes are created with a tab.
+	let note_dates = use_memo(move || {
+		let _ = TABS.read();
+		let folder = DAILY_NOTES_CONFIG().folder_path(&get_default_trove_dir());
+		list_markdown_files(&folder)
+			.iter()
+			.filter(|path| path.parent() == Some(folder.as_path()))
+			.filter_map(|path| parse_daily_note_date(path))
+			.collect::<BTreeSet<NaiveDate>>()
+	});
+
+	let open_date = current_file().and_then(|file| DAILY_NOTES_CONFIG().note_date(&get_default_trove_dir(), &file.path));
+	let month_title = shown_month().format("%B %Y").to_string();
+
+	rsx!(
+		panel_header { title: "Daily Notes" }
+		rect {
+			width: "fill",
+			direction: "horizontal",
+			cross_align: "center",
+			padding: "4",
+			month_button { label: "‹", onclick: move |_| shown_month.set(offset_month(shown_month(), -1)) }
+			label {
+				width: "fill",
+				text_align: "center",
+				color: "{theme.text}",
+				font_size: "{appearance.ui_size(14.0)}",
+				font_family: "{appearance.font_family}",
+				"{month_title}"
+			}
+			month_button { label: "›", onclick: move |_| shown_month.set(offset_month(shown_month(), 1)) }
+		}
+		rect {
+			width: "fill",
+			direction: "horizontal",
+			for weekday in WEEKDAYS {
+				label {
+					width: "flex(1)",
+					text_align: "center",
+					color: "{theme.overlay0}",
+					font_size: "{appearance.ui_size(12.0)}",
+					font_family: "{appearance.font_family}",
+					"{weekday}"
+				}
+			}
+		}
+		for week in month_weeks(shown_month()) {
+			rect {
+				width: "fill",
+				direction: "horizontal",
+				content: "flex",
+				for day in week {
+					{match day {
+						Some(date) => rsx!(day_cell {
+							date,
+							has_note: note_dates.read().contains(&date),
+							is_today: date == today,
+							is_open: open_date == Some(date),
+						}),
+						None => rsx!(rect { width: "flex(1)", height: "30" }),
+					}}
+				}
+			}
+		}
+		rect {
+			width: "fill",
+			direction: "horizontal",
+			main_align: "space-between",
+			padding: "8 4",
+			text_button { label: "Previous", onclick: move |_| { spawn(open_adjacent_daily_note(-1)); } }
+			text_button { label: "Today", onclick: move |_| {
+				shown_month.set(today);
+				spawn(open_daily_note(today));
+			} }
+			text_button { label: "Next", onclick: move |_| { spawn(open_adjacent_daily_note(1)); } }
+		}
+		daily_notes_settings {}
+	)
+}
+
+#[component]
+fn day_cell(date: NaiveDate, has_note: bool, is_today: bool, is_open: bool) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let mut hovered = use_signal(|| false);
+
+	let background = if is_open {
+		theme.surface1.clone()
+	} else if hovered() {
+		theme.surface0.clone()
+	} else {
+		"transparent".to_string()
+	};
+	let color = if is_today { theme.accent.clone() } else { theme.text.clone() };
+	let dot_color = if has_note {
+		theme.subtext0.clone()
+	} else {
+		"transparent".to_string()
+	};
+	let day = date.format("%-d").to_string();
+
+	rsx!(CursorArea {
+		icon: CursorIcon::Pointer,
+		rect {
+			width: "flex(1)",
+			height: "30",
+			main_align: "center",
+			cross_align: "center",
+			corner_radius: "8",
+			background: "{background}",
+			onclick: move |_| {
+				spawn(open_daily_note(date));
+			},
+			onmouseenter: move |_| hovered.set(true),
+			onmouseleave: move |_| hovered.set(false),
+			label {
+				color: "{color}",
+				font_size: "{appearance.ui_size(13.0)}",
+				font_family: "{appearance.font_family}",
+				"{day}"
+			}
+			// Marks the days which have a note.
+			rect {
+				width: "4",
+				height: "4",
+				corner_radius: "2",
+				background: "{dot_color}",
+			}
+		}
+	})
+}
+
+#[component]
+fn month_button(label: String, onclick: EventHandler<()>) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+
+	rsx!(CursorArea {
+		icon: CursorIcon::Pointer,
+		rect {
+			padding: "0 8",
+			onclick: move |_| onclick.call(()),
+			label {
+				color: "{theme.subtext0}",
+				font_size: "{appearance.ui_size(18.0)}",
+				font_family: "{appearance.font_family}",
+				"{label}"
+			}
+		}
+	})
+}
+
+#[component]
+fn text_button(label: String, onclick: EventHandler<()>) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+
+	rsx!(CursorArea {
+		icon: CursorIcon::Pointer,
+		label {
+			color: "{theme.accent}",
+			font_size: "{appearance.ui_size(13.0)}",
+			font_family: "{appearance.font_family}",
+			onclick: move |_| onclick.call(()),
+			"{label}"
+		}
+	})
+}
+
+/// Inputs for the folder of the daily notes and the template they are created from.
+#[component]
+fn daily_notes_settings() -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let mut folder = use_signal(|| DAILY_NOTES_CONFIG().folder);
+	let mut template = use_signal(|| DAILY_NOTES_CONFIG().template.unwrap_or_default());
+
+	let input_theme = theme_with!(InputTheme {
+		font_color: Cow::from(theme.text.clone()),
+		background: Cow::from(theme.crust.clone()),
+		hover_background: Cow::from(theme.crust.clone()),
+		border_fill: Cow::from(theme.surface0.clone()),
+		focus_border_fill: Cow::from(theme.surface2.clone()),
+		width: cow_borrowed!("100%"),
+	});
+
+	rsx!(rect {
+		width: "fill",
+		direction: "vertical",
+		spacing: "4",
+		padding: "8 4 0 4",
+		border: "1 0 0 0 inner { theme.surface0 }",
+		label {
+			color: "{theme.subtext0}",
+			font_size: "{appearance.ui_size(12.0)}",
+			font_family: "{appearance.font_family}",
+			"Folder"
+		}
+		Input {
+			value: folder,
+			placeholder: "Daily",
+			onchange: move |text: String| {
+				DAILY_NOTES_CONFIG.write().folder = text.clone();
+				folder.set(text);
+			},
+			theme: input_theme.clone(),
+		}
+		label {
+			color: "{theme.subtext0}",
+			font_size: "{appearance.ui_size(12.0)}",
+			font_family: "{appearance.font_family}",
+			"Template"
+		}
+		Input {
+			value: template,
+			placeholder: "No template",
+			onchange: move |text: String| {
+				DAILY_NOTES_CONFIG.write().template = Some(text.clone()).filter(|name| !name.trim().is_empty());
+				template.set(text);
+			},
+			theme: input_theme,
+		}
+	})
+}
@@ -1,24 +1,60 @@
+use std::collections::{HashMap, HashSet};
 use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+use std::rc::Rc;
 
 use crate::{
 	data::{
-		fn_utils::handle_editor_key_input,
-		io_utils::{deinitialise_app, update_document_title},
-		stores::{ACTIVE_DOCUMENT_TITLE, CURRENT_EDITOR_BUFFER, THEME_STORE},
+		fn_utils::{
+			apply_text_edit, handle_editor_key_input, handle_markdown_editing, handle_multi_cursor_key, handle_vim_key, main_caret,
+			realign_table_after_key, renumber_lists_after_key, set_carets, starts_own_edit,
+		},
+		folding::{foldable_ranges, folded_ranges, shift_folds, visible_lines},
+		io_utils::{autosave_file, deinitialise_app, update_document_title},
+		markdown_style::{SpanStyle, line_styles, styled_spans},
+		multi_cursor::{column_carets, line_caret_columns, line_highlights},
+		stores::{
+			ACTIVE_DOCUMENT_TITLE, CURRENT_EDITOR_BUFFER, DICTIONARY, DOCUMENT_OUTLINE, EDITOR_SCROLL_REQUEST, EXTRA_CURSORS,
+			FOLDABLE_RANGES, FOLDED_LINES, SETTINGS, TAG_INDEX, THEME_STORE, UNDO_TREES, current_file, record_current_edit, record_edit,
+			redo_edit, toggle_fold, toggle_task_at_cursor, undo_edit,
+		},
+		tags::{parse_tags, tag_prefix_at},
+		text_edit::TextEdit,
 	},
-	view::bottom_bar::bottom_floating_bar,
+	view::{bottom_bar::bottom_floating_bar, properties_panel::properties_panel, side_panel::side_panel},
 };
 use freya::prelude::*;
 use tokio::time::Duration;
 use tokio::time::sleep;
 
+/// Approximate height of an editor line, used to scroll to lines that have not been measured yet.
+const ESTIMATED_LINE_HEIGHT: f32 = 24.0;
+
+/// Width of the gutter on the left of the editor lines showing the fold toggles.
+const FOLD_GUTTER_WIDTH: usize = 20;
+
+/// Pause in typing after which the outline is updated, in milliseconds.
+const OUTLINE_DELAY_MS: u64 = 150;
+
+/// Pause in typing after which the edits are recorded in the undo history, in milliseconds.
+const HISTORY_DELAY_MS: u64 = 300;
+
+/// Pause in typing after which the tags of the document are updated in the tag index, in milliseconds.
+const TAGS_DELAY_MS: u64 = 1000;
+
 #[component]
 pub fn work_space() -> Element {
 	rsx!(rect {
 		width: "fill",
 		height: "fill",
-		editor_area{}
-		bottom_floating_bar {}
+		direction: "horizontal",
+		rect {
+			width: "fill",
+			height: "fill",
+			editor_area{}
+			bottom_floating_bar {}
+		}
+		side_panel {}
 	})
 }
 
@@ -29,6 +65,7 @@ fn editor_area() -> Element {
 		height: "fill",
 		direction: "vertical",
 		title_box{}
+		properties_panel{}
 		editor_box_dynamic{}
 	})
 }
@@ -36,6 +73,7 @@ fn editor_area() -> Element {
 #[component]
 fn title_box() -> Element {
 	let theme = THEME_STORE().current_theme.colors;
+	let tokens = THEME_STORE().current_theme.tokens();
 
 	let mut focus = use_focus();
 
@@ -54,6 +92,8 @@ fn title_box() -> Element {
 	let editor = editable.editor().read();
 	let cursor_char = editor.cursor_pos();
 	let mut is_cursor_blinking = use_signal(|| false);
+	let appearance = SETTINGS().appearance;
+	let title_font_size = 40.0 * f32::from(appearance.editor_zoom) / 100.0;
 
 	let onmousedown = move |e: MouseEvent| {
 		focus.request_focus();
@@ -90,15 +130,12 @@ fn title_box() -> Element {
 	// A future that runs a timer to toggle the blink signal
 	use_future(move || async move {
 		loop {
-			sleep(Duration::from_millis(550)).await;
-			if focus.is_focused() {
-				is_cursor_blinking.toggle();
-			}
+			blink_cursor(focus, is_cursor_blinking).await;
 		}
 	});
 
 	let cursor_color = if focus.is_focused() && *is_cursor_blinking.read() {
-		theme.text.as_str()
+		tokens.ui.cursor.as_str()
 	} else {
 		"transparent"
 	};
@@ -131,7 +168,7 @@ fn title_box() -> Element {
 					cursor_mode: "editable",
 					cursor_color: "{cursor_color}",
 					highlights,
-					highlight_color: "{theme.subtext1}",
+					highlight_color: "{tokens.ui.selection}",
 					a11y_id: focus.attribute(),
 					cursor_reference,
 					onclick,
@@ -140,8 +177,8 @@ fn title_box() -> Element {
 					onkeydown,
 					onkeyup,
 					color: "{theme.text}",
-					font_size: "40",
-					font_family: "JetBrains Mono",
+					font_size: "{title_font_size}",
+					font_family: "{appearance.font_family}",
 					text_overflow: "ellipsis",
 					max_lines: "1",
 					text {
@@ -161,6 +198,7 @@ fn editor_box() -> Element {
 	let editor = editable.editor().read();
 	let mut is_cursor_blinking = use_signal(|| false);
 	let theme = THEME_STORE().current_theme.colors;
+	let editor_width = SETTINGS().appearance.editor_width;
 
 	let onclick = move |_: MouseEvent| {
 		*is_cursor_blinking.write() = true;
@@ -188,11 +226,7 @@ fn editor_box() -> Element {
 
 	use_future(move || async move {
 		loop {
-			sleep(Duration::from_millis(500)).await;
-
-			if focus.is_focused() {
-				is_cursor_blinking.toggle();
-			}
+			blink_cursor(focus, is_cursor_blinking).await;
 		}
 	});
 
@@ -211,7 +245,7 @@ fn editor_box() -> Element {
 		CursorArea {
 			icon: CursorIcon::Text,
 			rect {
-				width: "80%",
+				width: "{editor_width}%",
 				height: "fill",
 				background: "transparent",
 				padding: "4 0",
@@ -230,6 +264,7 @@ fn editor_box() -> Element {
 					cache_elements: true,
 					builder: move |line_index, _: &Option<()>| {
 						let theme = THEME_STORE().current_theme.colors;
+						let appearance = SETTINGS().appearance;
 						let editor = editable.editor().read();
 						let line = editor.line(line_index).unwrap();
 						let is_line_selected = editor.cursor_row() == line_index;
@@ -299,8 +334,8 @@ fn editor_box() -> Element {
 									highlights,
 									text {
 										color: "{theme.text}",
-										font_size: "16",
-										font_family: "JetBrains Mono",
+										font_size: "{appearance.editor_font_size()}",
+										font_family: "{appearance.font_family}",
 										"{line}"
 									}
 								}
@@ -319,24 +354,114 @@ fn editor_box_dynamic() -> Element {
 	let mut focus = use_focus();
 	let mut editable = CURRENT_EDITOR_BUFFER();
 	let mut is_cursor_blinking = use_signal(|| false);
-	let theme = THEME_STORE().current_theme.colors;
+	let tokens = THEME_STORE().current_theme.tokens();
+	let editor_width = SETTINGS().appearance.editor_width;
+
+	// Alt + click adds a cursor and Alt + drag selects a column, the anchor of the column is kept while dragging.
+	let mut is_alt_down = use_signal(|| false);
+	let mut column_anchor = use_signal(|| None::<usize>);
 
 	let onclick = move |_: MouseEvent| {
 		focus.request_focus();
 		*is_cursor_blinking.write() = true;
+		column_anchor.set(None);
 		editable.process_event(&EditableEvent::Click);
 	};
 
-	let onkeydown = {
+	// Tags matching the `#tag` being typed at the cursor, along with the char range of the typed prefix.
+	let tag_suggestions = use_memo(use_reactive(&editable, move |editable| {
+		let editor = editable.editor().read();
+		let row = editor.cursor_row();
+		let Some(line) = editor.line(row) else {
+			return None;
+		};
+		// Tags are not parsed in code blocks, so none are suggested there.
+		if DOCUMENT_OUTLINE.read().is_code_line(row) {
+			return None;
+		}
+		let (start_col, prefix) = tag_prefix_at(&line.text, editor.cursor_col())?;
+		let suggestions = TAG_INDEX.read().complete(&prefix);
+		if suggestions.is_empty() {
+			return None;
+		}
+		let line_start = editor.line_to_char(row);
+		Some((line_start + start_col..editor.cursor_pos(), suggestions))
+	}));
+
+	let mut handle_key_down = {
 		move |e: KeyboardEvent| {
-			if handle_editor_key_input(&e) {
-				editable.process_event(&EditableEvent::KeyDown(e.data));
+			if e.data.key == Key::Alt {
+				is_alt_down.set(true);
+			}
+			// Keys of the keymap are handled by the global handler.
+			if !handle_editor_key_input(&e) {
+				return;
+			}
+			// Undo and redo go through the undo tree of the file instead of the history of the editor.
+			let mods = e.data.modifiers;
+			if let Key::Character(c) = &e.data.key
+				&& mods.contains(Modifiers::CONTROL)
+			{
+				let shift = mods.contains(Modifiers::SHIFT);
+				if c == "z" && !shift {
+					undo_edit();
+					return;
+				}
+				if c == "y" || (c.eq_ignore_ascii_case("z") && shift) {
+					redo_edit();
+					return;
+				}
+			}
+			if handle_vim_key(&mut editable, &e) {
+				return;
+			}
+			if handle_multi_cursor_key(&mut editable, &e) {
+				return;
+			}
+			// Tab accepts the first tag suggestion.
+			if e.data.key == Key::Tab
+				&& e.data.modifiers.is_empty()
+				&& let Some((range, suggestions)) = tag_suggestions()
+			{
+				apply_text_edit(&mut editable, TextEdit::replace(range, suggestions[0].clone()));
+				return;
+			}
+			// Ctrl + Enter toggles the task on the current line.
+			if e.data.key == Key::Enter && e.data.modifiers.contains(Modifiers::CONTROL) {
+				toggle_task_at_cursor();
+				return;
 			}
+			if handle_markdown_editing(&mut editable, &e) {
+				return;
+			}
+			let (previous_cursor, previous_row) = {
+				let editor = editable.editor().read();
+				(editor.cursor_pos(), editor.cursor_row())
+			};
+			editable.process_event(&EditableEvent::KeyDown(e.data.clone()));
+			renumber_lists_after_key(&mut editable, &e);
+			realign_table_after_key(&mut editable, &e, previous_cursor, previous_row);
+		}
+	};
+
+	// Keys starting an edit of their own record the edits before and after them in the undo history at once,
+	// typing is recorded once it pauses.
+	let onkeydown = move |e: KeyboardEvent| {
+		let starts_edit = starts_own_edit(&e);
+		if starts_edit {
+			record_current_edit();
+		}
+		handle_key_down(e);
+		if starts_edit {
+			record_current_edit();
 		}
 	};
 
 	let onkeyup = {
 		move |e: KeyboardEvent| {
+			if e.data.key == Key::Alt {
+				is_alt_down.set(false);
+			}
 			if handle_editor_key_input(&e) {
 				editable.process_event(&EditableEvent::KeyUp(e.data));
 			}
@@ -345,9 +470,9 @@ fn editor_box_dynamic() -> Element {
 
 	let scrollbar_theme = theme_with!(ScrollBarTheme {
 		background: cow_borrowed!("transparent"), //
-		thumb_background: Cow::from(theme.surface0.clone()),
-		hover_thumb_background: Cow::from(theme.surface1.clone()),
-		active_thumb_background: Cow::from(theme.surface2.clone()),
+		thumb_background: Cow::from(tokens.ui.scrollbar_thumb.clone()),
+		hover_thumb_background: Cow::from(tokens.ui.scrollbar_thumb_hover.clone()),
+		active_thumb_background: Cow::from(tokens.ui.scrollbar_thumb_active.clone()),
 	});
 
 	use_effect(move || {
@@ -355,10 +480,7 @@ fn editor_box_dynamic() -> Element {
 			*is_cursor_blinking.write() = true;
 			spawn(async move {
 				while focus.is_focused() {
-					sleep(Duration::from_millis(500)).await;
-					if focus.is_focused() {
-						is_cursor_blinking.toggle();
-					}
+					blink_cursor(focus, is_cursor_blinking).await;
 				}
 				*is_cursor_blinking.write() = false;
 			});
@@ -374,12 +496,137 @@ fn editor_box_dynamic() -> Element {
 	// 	deinitialise_app();
 	// });
 
+	// Text of the buffer, built once per edit and shared by the effects below.
+	let buffer_text = use_memo(move || Rc::<str>::from(CURRENT_EDITOR_BUFFER().editor().read().to_string()));
+
+	// Keep the outline and the foldable ranges of the document up to date while typing. Edits adding or
+	// removing lines, or another document, update them at once so that the folds stay on their lines, the
+	// others once typing pauses.
+	let mut outline_task = use_signal(|| None::<Task>);
+	let mut outline_source = use_signal(|| None::<(Option<PathBuf>, usize)>);
+	use_effect(move || {
+		let text = buffer_text();
+		if let Some(task) = outline_task.write().take() {
+			task.cancel();
+		}
+		let source = Some((current_file().map(|file| file.path), text.split('\n').count()));
+		let update_outline = move || {
+			let lines: Vec<&str> = text.split('\n').collect();
+			DOCUMENT_OUTLINE.write().update(&lines);
+			*FOLDABLE_RANGES.write() = foldable_ranges(&lines, &DOCUMENT_OUTLINE.peek());
+		};
+		if outline_source.replace(source.clone()) != source {
+			update_outline();
+			return;
+		}
+		outline_task.set(Some(spawn(async move {
+			sleep(Duration::from_millis(OUTLINE_DELAY_MS)).await;
+			update_outline();
+		})));
+	});
+
+	// Keep the folds on their lines when lines are added or removed above them.
+	let mut previous_text = use_signal(|| None::<(PathBuf, Rc<str>)>);
+	use_effect(move || {
+		let text = buffer_text();
+		let Some(file) = current_file() else {
+			return;
+		};
+		let previous = previous_text.replace(Some((file.path.clone(), text.clone())));
+		if let Some((path, old)) = previous
+			&& path == file.path
+			&& let Some(folds) = FOLDED_LINES.write().get_mut(&path)
+			&& !folds.is_empty()
+			&& old != text
+		{
+			*folds = shift_folds(folds, &old, &text);
+		}
+	});
+
+	// Record the edits of the document in its undo tree, whatever made them, once typing pauses. The keys
+	// starting an edit of their own are recorded at once by `onkeydown`.
+	let mut history_task = use_signal(|| None::<Task>);
+	use_effect(move || {
+		let text = buffer_text();
+		if let Some(task) = history_task.write().take() {
+			task.cancel();
+		}
+		let Some(file) = current_file() else {
+			return;
+		};
+		// The history of a document opened for the first time starts from its text before any edit.
+		if !UNDO_TREES.peek().contains_key(&file.path) {
+			record_edit(&file.path, &text);
+			return;
+		}
+		history_task.set(Some(spawn(async move {
+			sleep(Duration::from_millis(HISTORY_DELAY_MS)).await;
+			record_edit(&file.path, &text);
+		})));
+	});
+
+	// Keep the tags of the document in the tag index up to date once typing pauses, without waiting for a
+	// save. The index is only written when the tags changed, as the tag browser and completion read it.
+	let mut tags_task = use_signal(|| None::<Task>);
+	use_effect(move || {
+		let text = buffer_text();
+		if let Some(task) = tags_task.write().take() {
+			task.cancel();
+		}
+		let Some(file) = current_file() else {
+			return;
+		};
+		tags_task.set(Some(spawn(async move {
+			sleep(Duration::from_millis(TAGS_DELAY_MS)).await;
+			let tags = parse_tags(&text);
+			if !TAG_INDEX.peek().has_tags(&file.path, &tags) {
+				TAG_INDEX.write().set_tags(&file.path, tags);
+			}
+		})));
+	});
+
+	// Save the document once nothing was typed for the autosave delay.
+	let mut autosave_task = use_signal(|| None::<Task>);
+	use_effect(move || {
+		// Runs again on every edit of the document.
+		let _ = CURRENT_EDITOR_BUFFER().editor().read().len_chars();
+		let delay = SETTINGS().files.autosave_delay;
+		if let Some(task) = autosave_task.write().take() {
+			task.cancel();
+		}
+		let Some(file) = current_file().filter(|_| delay > 0) else {
+			return;
+		};
+		autosave_task.set(Some(spawn(async move {
+			sleep(Duration::from_secs(delay)).await;
+			autosave_file(file).await;
+		})));
+	});
+
+	// Ranges of lines folded in the current document.
+	let folded_ranges = use_memo(move || {
+		let folds = current_file()
+			.and_then(|file| FOLDED_LINES.read().get(&file.path).cloned())
+			.unwrap_or_default();
+		folded_ranges(&FOLDABLE_RANGES.read(), &folds)
+	});
+
+	// Lines of the document shown in the editor, folded lines are skipped.
+	let visible_lines = use_memo(move || {
+		let total_lines = CURRENT_EDITOR_BUFFER().editor().read().len_lines();
+		visible_lines(total_lines, &folded_ranges.read())
+	});
+
+	let mut scroll_controller = use_scroll_controller(ScrollConfig::default);
+
 	// Generate a unique and stable key for each line by hashing its content.
 	// Required by dynamic scroll view.
 	let item_keys = use_memo(use_reactive(&editable, move |editable| {
 		let editor = editable.editor().read();
-		(0..editor.len_lines())
-			.map(|i| {
+		visible_lines
+			.read()
+			.iter()
+			.map(|&i| {
 				let mut hasher = DefaultHasher::new();
 				if let Some(line) = editor.line(i) {
 					line.text.hash(&mut hasher);
@@ -390,6 +637,29 @@ fn editor_box_dynamic() -> Element {
 			.collect::<Vec<u64>>()
 	}));
 
+	// Heights of the lines laid out so far, by their keys. Lines wrap and headings are taller, so they are
+	// measured to find the offset of a line.
+	let mut line_heights = use_signal(HashMap::<u64, f32>::new);
+	use_effect(move || {
+		let keys: HashSet<u64> = item_keys().into_iter().collect();
+		line_heights.write().retain(|key, _| keys.contains(key));
+	});
+
+	// Scroll to the line requested by other components (like the outline).
+	use_effect(move || {
+		let Some(line) = EDITOR_SCROLL_REQUEST() else {
+			return;
+		};
+		let item_index = visible_lines.peek().iter().position(|visible| *visible >= line).unwrap_or_default();
+		let heights = line_heights.peek();
+		let offset: f32 = item_keys.peek()[..item_index]
+			.iter()
+			.map(|key| heights.get(key).copied().unwrap_or(ESTIMATED_LINE_HEIGHT))
+			.sum();
+		scroll_controller.scroll_to_y(offset as i32);
+		*EDITOR_SCROLL_REQUEST.write() = None;
+	});
+
 	rsx!(rect{
 		width: "fill",
 		height: "fill",
@@ -399,7 +669,7 @@ fn editor_box_dynamic() -> Element {
 		CursorArea {
 			icon: CursorIcon::Text,
 			rect {
-				width: "80%",
+				width: "{editor_width}%",
 				height: "fill",
 				background: "transparent",
 				padding: "4 0",
@@ -418,14 +688,35 @@ fn editor_box_dynamic() -> Element {
 					min_scrollthumb_height: Some(25.0),
 					item_keys: item_keys(),
 					scrollbar_theme,
-					builder: move |line_index| {
+					scroll_controller,
+					builder: move |item_index: usize| {
 						let theme = THEME_STORE().current_theme.colors;
+						let tokens = THEME_STORE().current_theme.tokens();
+						let settings = SETTINGS();
+						let appearance = settings.appearance;
+						let font_size = appearance.editor_font_size();
+						let line_box_height = font_size * appearance.line_height;
 						let editor = editable.editor().read();
 
+						let Some(line_index) = visible_lines.read().get(item_index).copied() else {
+							return rsx! { rect {} };
+						};
+						let item_key = item_keys.read().get(item_index).copied().unwrap_or_default();
+						let is_folded = folded_ranges.read().iter().any(|range| range.start == line_index);
+						let is_foldable = FOLDABLE_RANGES.read().contains_key(&line_index);
+						let chevron_rotation = if is_folded { "0deg" } else { "90deg" };
+
 						let line = match editor.line(line_index) {
 							Some(line) => line,
 							None => return rsx! { rect {} }
 						};
+						// Code blocks use the code font, the rest of the note the prose one.
+						let in_code_block = DOCUMENT_OUTLINE.read().is_code_line(line_index);
+						let font_family = if in_code_block {
+							appearance.code_font_family.clone()
+						} else {
+							appearance.font_family.clone()
+						};
 
 						let is_line_selected = editor.cursor_row() == line_index;
 						let character_index = if is_line_selected {
@@ -434,53 +725,280 @@ fn editor_box_dynamic() -> Element {
 							"none".to_string()
 						};
 						let cursor_color = if focus.is_focused() && *is_cursor_blinking.read() {
-							theme.text.as_str()
+							tokens.ui.cursor.as_str()
 						} else {
 							"transparent"
 						};
-						let line_background = "none";
+						let line_background = if is_line_selected { tokens.ui.line_highlight.as_str() } else { "none" };
 
 						let onmousedown = move |e: MouseEvent| {
-							editable.process_event(&EditableEvent::MouseDown(e.data, line_index));
+							if is_alt_down() {
+								// The previous main cursor stays as an extra cursor.
+								let previous = main_caret(&editable.editor().read());
+								editable.process_event(&EditableEvent::MouseDown(e.data, line_index));
+								EXTRA_CURSORS.write().push(previous);
+								column_anchor.set(Some(editable.editor().read().cursor_pos()));
+							} else {
+								EXTRA_CURSORS.write().clear();
+								editable.process_event(&EditableEvent::MouseDown(e.data, line_index));
+							}
 						};
 
 						let onmousemove = move |e: MouseEvent| {
-							editable.process_event(&EditableEvent::MouseMove(e.data, line_index));
+							let Some(anchor) = column_anchor() else {
+								editable.process_event(&EditableEvent::MouseMove(e.data, line_index));
+								return;
+							};
+							// The cursor is moved under the mouse to find the position of the head of the column.
+							editable.process_event(&EditableEvent::MouseDown(e.data, line_index));
+							let (text, head) = {
+								let editor = editable.editor().read();
+								(editor.to_string(), editor.cursor_pos())
+							};
+							if head != anchor {
+								set_carets(&mut editable, column_carets(&text, anchor, head));
+							}
 						};
 
-						let highlights = editable.highlights_attr(line_index);
+						// The selections of the extra cursors are highlighted along with the main one, and their
+						// cursors are drawn over the line.
+						let extra_cursors = EXTRA_CURSORS.read();
+						let line_text = line.text.trim_end_matches(['\n', '\r']).to_string();
+						let line_length = line_text.chars().count();
+						let mut caret_columns = Vec::new();
+						let highlights = if extra_cursors.is_empty() {
+							editable.highlights_attr(line_index)
+						} else {
+							let line_start = editor.line_to_char(line_index);
+							caret_columns = line_caret_columns(&extra_cursors, line_start, line_length);
+							let mut carets = extra_cursors.clone();
+							let main = main_caret(&editor);
+							if !main.is_empty() {
+								carets.push(main);
+							}
+							let ranges = line_highlights(&carets, line_start, line_length)
+								.into_iter()
+								.map(|(start, end)| (utf16_offset(&line_text, start), utf16_offset(&line_text, end)))
+								.collect();
+							AttributeValue::any_value(CustomAttributeValues::TextHighlights(ranges))
+						};
+						let line_content = line.to_string();
+						let misspelled = if settings.editor.spell_check {
+							DICTIONARY.read().misspelled(&line_content)
+						} else {
+							Vec::new()
+						};
+						let spans = styled_spans(&line_content, &line_styles(&line_content, in_code_block), &misspelled);
 
 						rsx! {
-							rect {
-								width: "100%",
-								height: "auto",
-								content: "fit",
-								direction: "horizontal",
-								background: "{line_background}",
-								paragraph {
-									cursor_reference: editable.cursor_attr(),
-									main_align: "center",
+							measured_line {
+								item_key,
+								line_heights,
+								rect {
+									width: "100%",
 									height: "auto",
-									width: "98.5%",
-									cursor_index: "{character_index}",
-									cursor_color: "{cursor_color}",
-									highlight_color: "{theme.subtext1}",
-									cursor_mode: "editable",
-									cursor_id: "{line_index}",
-									onmousedown,
-									onmousemove,
-									highlights,
-									text {
-										color: "{theme.text}",
-										font_size: "16",
-										font_family: "JetBrains Mono",
-										"{line}"
+									content: "fit",
+									direction: "horizontal",
+									background: "{line_background}",
+									// Fold gutter
+									rect {
+										width: "{FOLD_GUTTER_WIDTH}",
+										height: "{line_box_height}",
+										main_align: "center",
+										cross_align: "center",
+										if is_foldable {
+											CursorArea {
+												icon: CursorIcon::Pointer,
+												rect {
+													width: "14",
+													height: "14",
+													rotate: "{chevron_rotation}",
+													onclick: move |_| toggle_fold(line_index),
+													svg {
+														width: "100%",
+														height: "100%",
+														stroke: "{theme.surface2}",
+														svg_content: include_str!("../static/svgs/chevron-right.svg")
+													}
+												}
+											}
+										}
+									}
+									rect {
+										width: "calc(98.5% - {FOLD_GUTTER_WIDTH})",
+										height: "auto",
+										paragraph {
+											cursor_reference: editable.cursor_attr(),
+											main_align: "center",
+											height: "auto",
+											width: "100%",
+											cursor_index: "{character_index}",
+											cursor_color: "{cursor_color}",
+											highlight_color: "{tokens.ui.selection}",
+											cursor_mode: "editable",
+											cursor_id: "{line_index}",
+											line_height: "{appearance.line_height}",
+											onmousedown,
+											onmousemove,
+											highlights,
+											for span in spans.iter() {
+												text {
+													color: "{tokens.color(span.style)}",
+													font_size: "{font_size}",
+													font_family: "{font_family}",
+													font_weight: if matches!(span.style, SpanStyle::Strong | SpanStyle::Heading(_)) { "bold" } else { "normal" },
+													font_style: if span.style == SpanStyle::Emphasis { "italic" } else { "normal" },
+													decoration: if span.misspelled { "underline" } else { "none" },
+													decoration_style: "wavy",
+													decoration_color: "{theme.accent}",
+													"{span.text}"
+												}
+											}
+										}
+										// Each extra cursor is the caret of a copy of the line with transparent text laid
+										// over it, so it sits where the main cursor would.
+										for column in caret_columns {
+											paragraph {
+												position: "absolute",
+												position_top: "0",
+												position_left: "0",
+												main_align: "center",
+												height: "auto",
+												width: "100%",
+												cursor_index: "{column}",
+												cursor_color: "{cursor_color}",
+												cursor_mode: "editable",
+												line_height: "{appearance.line_height}",
+												for span in spans.iter() {
+													text {
+														color: "transparent",
+														font_size: "{font_size}",
+														font_family: "{font_family}",
+														font_weight: if matches!(span.style, SpanStyle::Strong | SpanStyle::Heading(_)) { "bold" } else { "normal" },
+														font_style: if span.style == SpanStyle::Emphasis { "italic" } else { "normal" },
+														"{span.text}"
+													}
+												}
+											}
+										}
+									}
+									if is_folded {
+										CursorArea {
+											icon: CursorIcon::Pointer,
+											label {
+												color: "{theme.subtext0}",
+												font_size: "{font_size}",
+												font_family: "{font_family}",
+												onclick: move |e: MouseEvent| {
+													e.stop_propagation();
+													toggle_fold(line_index);
+												},
+												" ⋯"
+											}
+										}
 									}
 								}
 							}
 						}
 					}
 				}
+				if let Some((range, suggestions)) = tag_suggestions() {
+					tag_autocomplete { range, suggestions }
+				}
+			}
+		}
+	})
+}
+
+/// A line of the editor, reporting its height once it is laid out.
+#[component]
+fn measured_line(item_key: u64, mut line_heights: Signal<HashMap<u64, f32>>, children: Element) -> Element {
+	let (reference, layout) = use_node_signal();
+	use_effect(use_reactive(&item_key, move |item_key| {
+		let height = layout.read().area.height();
+		if height > 0.0 && line_heights.peek().get(&item_key) != Some(&height) {
+			line_heights.write().insert(item_key, height);
+		}
+	}));
+
+	rsx!(rect {
+		reference,
+		width: "100%",
+		{children}
+	})
+}
+
+/// Waits for the next blink of the cursor and toggles it, the blink rate of the settings being the time
+/// between two blinks. A blink rate of 0 keeps the cursor shown.
+async fn blink_cursor(focus: UseFocus, mut is_cursor_blinking: Signal<bool>) {
+	let blink_rate = SETTINGS.peek().editor.cursor_blink_rate;
+	if blink_rate == 0 {
+		sleep(Duration::from_millis(500)).await;
+		is_cursor_blinking.set(focus.is_focused());
+		return;
+	}
+	sleep(Duration::from_millis(blink_rate)).await;
+	if focus.is_focused() {
+		is_cursor_blinking.toggle();
+	}
+}
+
+/// Converts a column in chars to an offset in UTF-16 code units, the unit of the paragraph highlights.
+fn utf16_offset(line: &str, column: usize) -> usize {
+	line.chars().take(column).map(char::len_utf16).sum::<usize>() + column.saturating_sub(line.chars().count())
+}
+
+/// Popup listing the tags matching the one being typed, clicking a tag completes it.
+#[component]
+fn tag_autocomplete(range: std::ops::Range<usize>, suggestions: Vec<String>) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+
+	// The first suggestion is the one accepted with Tab, so it is highlighted.
+	let items = suggestions.into_iter().enumerate().map(|(index, tag)| {
+		let background = if index == 0 {
+			theme.surface0.clone()
+		} else {
+			"transparent".to_string()
+		};
+		(tag, background)
+	});
+
+	rsx!(rect {
+		position: "absolute",
+		position_bottom: "10",
+		position_left: "10",
+		width: "240",
+		padding: "6 4",
+		direction: "vertical",
+		spacing: "2",
+		background: "{theme.base}",
+		shadow: "0 4 12 2 rgb(0, 0, 0, 60)",
+		corner_radius: "10",
+		layer: "overlay",
+		for (tag, background) in items {
+			CursorArea {
+				icon: CursorIcon::Pointer,
+				rect {
+					width: "fill",
+					padding: "3 8",
+					corner_radius: "6",
+					background: "{background}",
+					onclick: {
+						let range = range.clone();
+						let tag = tag.clone();
+						move |e: MouseEvent| {
+							e.stop_propagation();
+							apply_text_edit(&mut CURRENT_EDITOR_BUFFER(), TextEdit::replace(range.clone(), tag.clone()));
+						}
+					},
+					label {
+						color: "{theme.text}",
+						font_size: "{appearance.ui_size(14.0)}",
+						font_family: "{appearance.font_family}",
+						"#{tag}"
+					}
+				}
 			}
 		}
 	})
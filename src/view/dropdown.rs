@@ -28,7 +28,7 @@ pub fn menu(children: Element) -> Element {
 }
 
 #[component]
-pub fn submenu(children: Element) -> Element {
+pub fn submenu(children: Element, #[props(default = EventHandler::new(|_| {}))] onmouseleave: EventHandler<()>) -> Element {
 	let theme = THEME_STORE().current_theme.colors;
 
 	let scrollbar_theme = theme_with!(ScrollBarTheme {
@@ -49,6 +49,7 @@ pub fn submenu(children: Element) -> Element {
 			background: "{theme.base}",
 			layer: "overlay",
 			corner_radius: "12",
+			onmouseleave: move |_| onmouseleave.call(()),
 			ScrollView {
 				width: "100%",
 				direction: "vertical",
@@ -0,0 +1,149 @@
+use crate::{
+	data::{
+		stores::{
+			PERSIST_UNDO_HISTORY, SETTINGS, THEME_STORE, UNDO_TREES, current_file, jump_to_history_entry, redo_edit, undo_edit,
+			undo_to_last_save,
+		},
+		undo_tree::HistoryEntry,
+	},
+	view::side_panel::{panel_header, panel_row},
+};
+use chrono::{Local, TimeZone};
+use freya::prelude::*;
+
+/// Indentation of the edits of an alternative branch of the history.
+const BRANCH_INDENT: usize = 12;
+
+/// Lists the edits of the undo history of the current note, latest first. Clicking an edit restores the
+/// note to it, edits made after an undo are shown as a new branch.
+#[component]
+pub fn history_panel() -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let entries: Vec<HistoryEntry> = current_file()
+		.and_then(|file| UNDO_TREES.read().get(&file.path).map(|tree| tree.entries()))
+		.unwrap_or_default();
+	let persist_label = if PERSIST_UNDO_HISTORY() {
+		"Kept across restarts"
+	} else {
+		"Cleared on close"
+	};
+
+	let scrollbar_theme = theme_with!(ScrollBarTheme {
+		background: cow_borrowed!("transparent"), //
+		thumb_background: Cow::from(theme.surface0.clone()),
+		hover_thumb_background: Cow::from(theme.surface1.clone()),
+		active_thumb_background: Cow::from(theme.surface2.clone()),
+	});
+
+	rsx!(
+		panel_header { title: "History" }
+		rect {
+			width: "fill",
+			direction: "horizontal",
+			spacing: "4",
+			history_button { label: "Undo", onclick: move |_| undo_edit() }
+			history_button { label: "Redo", onclick: move |_| redo_edit() }
+			history_button { label: "Last Save", onclick: move |_| undo_to_last_save() }
+		}
+		label {
+			color: "{theme.overlay0}",
+			font_size: "{appearance.ui_size(12.0)}",
+			font_family: "{appearance.font_family}",
+			"{persist_label}"
+		}
+		ScrollView {
+			width: "100%",
+			height: "fill",
+			spacing: "2",
+			scrollbar_theme,
+			for entry in entries.into_iter().rev() {
+				history_row { entry }
+			}
+		}
+	)
+}
+
+#[component]
+fn history_row(entry: HistoryEntry) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let index = entry.index;
+	let time = match Local.timestamp_millis_opt(entry.time).single() {
+		Some(time) if entry.time > 0 => time.format("%H:%M:%S").to_string(),
+		_ => String::new(),
+	};
+	let text_color = if entry.current {
+		theme.text.clone()
+	} else {
+		theme.subtext0.clone()
+	};
+
+	rsx!(panel_row {
+		selected: entry.current,
+		onclick: move |_| jump_to_history_entry(index),
+		rect {
+			width: "fill",
+			margin: "0 0 0 {entry.branch * BRANCH_INDENT}",
+			direction: "vertical",
+			label {
+				width: "fill",
+				color: "{text_color}",
+				font_size: "{appearance.ui_size(14.0)}",
+				font_family: "{appearance.font_family}",
+				max_lines: "1",
+				text_overflow: "ellipsis",
+				"{entry.summary}"
+			}
+			rect {
+				width: "fill",
+				direction: "horizontal",
+				main_align: "space-between",
+				label {
+					color: "{theme.overlay0}",
+					font_size: "{appearance.ui_size(12.0)}",
+					font_family: "{appearance.font_family}",
+					"{time}"
+				}
+				if entry.saved {
+					label {
+						color: "{theme.accent}",
+						font_size: "{appearance.ui_size(12.0)}",
+						font_family: "{appearance.font_family}",
+						"saved"
+					}
+				}
+			}
+		}
+	})
+}
+
+#[component]
+fn history_button(label: String, onclick: EventHandler<()>) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let mut hovered = use_signal(|| false);
+	let background = if hovered() {
+		theme.surface1.clone()
+	} else {
+		theme.surface0.clone()
+	};
+
+	rsx!(CursorArea {
+		icon: CursorIcon::Pointer,
+		rect {
+			padding: "2 6",
+			corner_radius: "6",
+			background: "{background}",
+			onclick: move |_| onclick.call(()),
+			onmouseenter: move |_| hovered.set(true),
+			onmouseleave: move |_| hovered.set(false),
+			label {
+				color: "{theme.text}",
+				font_size: "{appearance.ui_size(12.0)}",
+				font_family: "{appearance.font_family}",
+				"{label}"
+			}
+		}
+	})
+}
@@ -1,8 +1,20 @@
 pub mod app_view;
 pub mod bottom_bar;
+pub mod calendar_panel;
 pub mod docview;
 pub mod dropdown;
+pub mod history_panel;
+pub mod notification_toast;
+pub mod outline_panel;
 pub mod palette;
+pub mod properties_panel;
+pub mod settings_window;
+pub mod shortcuts;
+pub mod side_panel;
 pub mod sidebar;
+pub mod tag_browser;
+pub mod tasks_panel;
+pub mod theme_editor;
+pub mod theme_import;
 pub mod top_bar;
 mod widgets;
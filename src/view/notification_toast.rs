@@ -0,0 +1,161 @@
+use crate::data::{
+	stores::{SETTINGS, THEME_STORE, TOASTS, dismiss_toast, run_toast_action},
+	types::{Toast, ToastLevel},
+};
+use freya::prelude::*;
+use tokio::time::{Duration, sleep};
+
+/// The toasts stacked in the bottom right corner of the window, over the rest of the app.
+#[component]
+pub fn toast_stack() -> Element {
+	let toasts = TOASTS();
+	if toasts.is_empty() {
+		return rsx!();
+	}
+
+	rsx!(rect {
+		position: "global",
+		position_bottom: "56",
+		position_right: "16",
+		width: "360",
+		direction: "vertical",
+		spacing: "8",
+		layer: "overlay",
+		for toast in toasts {
+			toast_card { key: "{toast.id}", toast }
+		}
+	})
+}
+
+/// A toast with its level, its message and its buttons. It leaves once its time is up, unless it is hovered.
+#[component]
+fn toast_card(toast: Toast) -> Element {
+	let theme = THEME_STORE().current_theme.colors;
+	let appearance = SETTINGS().appearance;
+	let mut hovered = use_signal(|| false);
+	let id = toast.id;
+	let level = toast.level;
+
+	use_future(move || async move {
+		let mut wait = level.duration();
+		loop {
+			sleep(wait).await;
+			if !*hovered.peek() {
+				dismiss_toast(id);
+				break;
+			}
+			wait = Duration::from_secs(2);
+		}
+	});
+
+	let level_color = match level {
+		ToastLevel::Info => theme.overlay1.clone(),
+		ToastLevel::Warning => theme.highlight.clone(),
+		ToastLevel::Error => theme.accent.clone(),
+	};
+
+	rsx!(rect {
+		width: "100%",
+		direction: "horizontal",
+		corner_radius: "10",
+		overflow: "clip",
+		background: "{theme.base}",
+		border: "1 outer {theme.surface0}",
+		shadow: "4 4 8 1 rgb(0, 0, 0, 20)",
+		onmouseenter: move |_| hovered.set(true),
+		onmouseleave: move |_| hovered.set(false),
+		rect {
+			width: "4",
+			height: "fill",
+			background: "{level_color}",
+		}
+		rect {
+			width: "fill",
+			padding: "8 10",
+			spacing: "6",
+			rect {
+				width: "100%",
+				direction: "horizontal",
+				cross_align: "center",
+				label {
+					width: "fill",
+					color: "{level_color}",
+					font_size: "{appearance.ui_size(12.0)}",
+					font_family: "{appearance.font_family}",
+					font_weight: "bold",
+					"{level.title()}"
+				}
+				CursorArea {
+					icon: CursorIcon::Pointer,
+					rect {
+						width: "16",
+						height: "16",
+						onclick: move |_| dismiss_toast(id),
+						svg {
+							width: "100%",
+							height: "100%",
+							stroke: "{theme.subtext0}",
+							svg_content: include_str!("../static/svgs/close.svg")
+						}
+					}
+				}
+			}
+			paragraph {
+				width: "100%",
+				text {
+					color: "{theme.text}",
+					font_size: "{appearance.ui_size(13.0)}",
+					font_family: "{appearance.font_family}",
+					"{toast.message}"
+				}
+			}
+			if !toast.actions.is_empty() {
+				rect {
+					width: "100%",
+					direction: "horizontal",
+					main_align: "end",
+					spacing: "6",
+					for (index, action) in toast.actions.into_iter().enumerate() {
+						toast_button {
+							key: "{index}",
+							label: action.label(),
+							onclick: move |_| {
+								spawn(run_toast_action(id, action.clone()));
+							},
+						}
+					}
+				}
+			}
+		}
+	})
+}
+
+#[component]
+fn toast_button(label: &'static str, onclick: EventHandler<()>) -> Element {
+	let theme = THEME_STORE().current_theme.colors;
+	let appearance = SETTINGS().appearance;
+	let mut hovered = use_signal(|| false);
+	let background = if hovered() {
+		theme.surface1.clone()
+	} else {
+		theme.surface0.clone()
+	};
+
+	rsx!(CursorArea {
+		icon: CursorIcon::Pointer,
+		rect {
+			padding: "3 10",
+			corner_radius: "6",
+			background: "{background}",
+			onclick: move |_| onclick.call(()),
+			onmouseenter: move |_| hovered.set(true),
+			onmouseleave: move |_| hovered.set(false),
+			label {
+				color: "{theme.text}",
+				font_size: "{appearance.ui_size(12.0)}",
+				font_family: "{appearance.font_family}",
+				"{label}"
+			}
+		}
+	})
+}
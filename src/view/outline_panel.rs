@@ -0,0 +1,129 @@
+use crate::{
+	data::{
+		fn_utils::apply_text_edit,
+		stores::{CURRENT_EDITOR_BUFFER, DOCUMENT_OUTLINE, FOLDED_LINES, SETTINGS, THEME_STORE, current_file, jump_to_line, toggle_fold},
+	},
+	view::side_panel::{panel_header, panel_row},
+};
+use freya::prelude::*;
+
+/// Lists the headings of the current note as a tree. Clicking a heading moves the editor to it, and
+/// headings can be dragged onto other headings to reorder their sections.
+#[component]
+pub fn outline_panel() -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let outline = DOCUMENT_OUTLINE();
+	let depths = outline.depths();
+	let folds = current_file()
+		.and_then(|file| FOLDED_LINES.read().get(&file.path).cloned())
+		.unwrap_or_default();
+
+	let headings: Vec<(usize, String, usize, bool)> = outline
+		.headings()
+		.iter()
+		.zip(depths)
+		.map(|(heading, depth)| (heading.line, heading.title.clone(), depth, folds.contains(&heading.line)))
+		.collect();
+
+	let scrollbar_theme = theme_with!(ScrollBarTheme {
+		background: cow_borrowed!("transparent"), //
+		thumb_background: Cow::from(theme.surface0.clone()),
+		hover_thumb_background: Cow::from(theme.surface1.clone()),
+		active_thumb_background: Cow::from(theme.surface2.clone()),
+	});
+
+	rsx!(
+		panel_header { title: "Outline" }
+		if headings.is_empty() {
+			label {
+				color: "{theme.subtext0}",
+				font_size: "{appearance.ui_size(14.0)}",
+				font_family: "{appearance.font_family}",
+				"No headings in this note."
+			}
+		}
+		ScrollView {
+			width: "100%",
+			height: "fill",
+			spacing: "2",
+			scrollbar_theme,
+			for (index, (line, title, depth, folded)) in headings.into_iter().enumerate() {
+				heading_row { index, line, title, depth, folded }
+			}
+			// Dropping a heading here moves its section to the end of the note.
+			DropZone {
+				ondrop: move |from: usize| move_section(from, None),
+				rect {
+					width: "fill",
+					height: "30",
+				}
+			}
+		}
+	)
+}
+
+#[component]
+fn heading_row(index: usize, line: usize, title: String, depth: usize, folded: bool) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let chevron_rotation = if folded { "0deg" } else { "90deg" };
+
+	rsx!(DropZone {
+		ondrop: move |from: usize| move_section(from, Some(index)),
+		DragZone {
+			data: index,
+			hide_while_dragging: false,
+			drag_element: rsx!(label {
+				color: "{theme.text}",
+				font_size: "{appearance.ui_size(14.0)}",
+				font_family: "{appearance.font_family}",
+				"{title}"
+			}),
+			panel_row {
+				selected: false,
+				onclick: move |_| jump_to_line(line),
+				rect {
+					width: "16",
+					height: "16",
+					margin: "0 4 0 {depth * 12}",
+					rotate: "{chevron_rotation}",
+					onclick: move |e: MouseEvent| {
+						e.stop_propagation();
+						toggle_fold(line);
+					},
+					svg {
+						width: "100%",
+						height: "100%",
+						stroke: "{theme.subtext0}",
+						svg_content: include_str!("../static/svgs/chevron-right.svg")
+					}
+				}
+				label {
+					width: "fill",
+					color: "{theme.text}",
+					font_size: "{appearance.ui_size(14.0)}",
+					font_family: "{appearance.font_family}",
+					max_lines: "1",
+					text_overflow: "ellipsis",
+					"{title}"
+				}
+			}
+		}
+	})
+}
+
+/// Moves the section of the heading at `from` before the heading at `to`, or to the end of the note.
+fn move_section(from: usize, to: Option<usize>) {
+	let mut editable = CURRENT_EDITOR_BUFFER();
+	let text = editable.editor().read().to_string();
+	let Some(edit) = DOCUMENT_OUTLINE.read().move_section(&text, from, to) else {
+		return;
+	};
+	apply_text_edit(&mut editable, edit);
+
+	// The folds are identified by their lines, which have moved.
+	if let Some(file) = current_file() {
+		FOLDED_LINES.write().remove(&file.path);
+	}
+}
@@ -1,6 +1,12 @@
 use freya::prelude::*;
+use std::path::PathBuf;
 
-use crate::data::stores::THEME_STORE;
+use crate::data::{
+	commands::Command,
+	io_utils::{get_templates_dir, list_markdown_files},
+	stores::{PALETTE_MODE, SETTINGS, THEME_STORE, insert_template_at_cursor, new_tab_from_template, toggle_command_palette},
+	types::{PaletteMode, TemplateTarget},
+};
 
 #[component]
 pub fn palette_box(children: Element) -> Element {
@@ -20,14 +26,167 @@ pub fn palette_box(children: Element) -> Element {
 		onclick: move |e| {
 			e.stop_propagation();
 		} ,
-		paragraph {
-			text {
+		{children}
+	})
+}
+
+/// An entry of the command palette.
+#[derive(Debug, Clone, PartialEq)]
+enum PaletteItem {
+	Command(Command),
+	Template(PathBuf, TemplateTarget),
+}
+
+impl PaletteItem {
+	fn title(&self) -> String {
+		match self {
+			PaletteItem::Command(command) => command.title().to_string(),
+			PaletteItem::Template(path, _) => path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default(),
+		}
+	}
+
+	/// Closes the palette and runs the action of the item.
+	fn select(self) {
+		toggle_command_palette();
+		match self {
+			PaletteItem::Command(command) => {
+				spawn(command.run());
+			}
+			PaletteItem::Template(path, TemplateTarget::NewNote) => {
+				spawn(new_tab_from_template(path));
+			}
+			PaletteItem::Template(path, TemplateTarget::CurrentNote) => insert_template_at_cursor(path),
+		}
+	}
+}
+
+/// Searchable list of the commands, or of the templates when picking one.
+#[component]
+pub fn command_palette() -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let mut query = use_signal(String::new);
+	let mut selected = use_signal(|| 0usize);
+
+	let items = use_memo(move || {
+		let all_items: Vec<PaletteItem> = match PALETTE_MODE() {
+			PaletteMode::Commands => Command::ALL.into_iter().map(PaletteItem::Command).collect(),
+			PaletteMode::Templates(target) => list_markdown_files(&get_templates_dir())
+				.into_iter()
+				.map(|path| PaletteItem::Template(path, target))
+				.collect(),
+		};
+		let query = query().to_lowercase();
+		all_items
+			.into_iter()
+			.filter(|item| item.title().to_lowercase().contains(&query))
+			.collect::<Vec<PaletteItem>>()
+	});
+
+	let placeholder = match PALETTE_MODE() {
+		PaletteMode::Commands => "Type a command...",
+		PaletteMode::Templates(_) => "Pick a template...",
+	};
+	let empty_message = match PALETTE_MODE() {
+		PaletteMode::Commands => "No matching command.".to_string(),
+		PaletteMode::Templates(_) => format!("No template found in {}.", get_templates_dir().display()),
+	};
+
+	let onkeydown = move |e: KeyboardEvent| {
+		let count = items.read().len();
+		match e.data.key {
+			Key::ArrowDown if count > 0 => selected.set((selected() + 1) % count),
+			Key::ArrowUp if count > 0 => selected.set((selected() + count - 1) % count),
+			Key::Enter => {
+				if let Some(item) = items.read().get(selected()).cloned() {
+					item.select();
+				}
+			}
+			Key::Escape => toggle_command_palette(),
+			_ => (),
+		}
+	};
+
+	let scrollbar_theme = theme_with!(ScrollBarTheme {
+		background: cow_borrowed!("transparent"), //
+		thumb_background: Cow::from(theme.surface0.clone()),
+		hover_thumb_background: Cow::from(theme.surface1.clone()),
+		active_thumb_background: Cow::from(theme.surface2.clone()),
+	});
+
+	rsx!(rect {
+		width: "fill",
+		height: "fill",
+		direction: "vertical",
+		spacing: "8",
+		onkeydown,
+		Input {
+			value: query,
+			placeholder,
+			onchange: move |text| {
+				query.set(text);
+				selected.set(0);
+			},
+			theme: theme_with!(InputTheme {
+				font_color: Cow::from(theme.text.clone()),
+				background: Cow::from(theme.base.clone()),
+				hover_background: Cow::from(theme.base.clone()),
+				border_fill: Cow::from(theme.surface0.clone()),
+				focus_border_fill: Cow::from(theme.surface2.clone()),
+				width: cow_borrowed!("100%"),
+			}),
+		}
+		if items.read().is_empty() {
+			label {
+				color: "{theme.subtext0}",
+				font_size: "{appearance.ui_size(14.0)}",
+				font_family: "{appearance.font_family}",
+				"{empty_message}"
+			}
+		}
+		ScrollView {
+			width: "100%",
+			height: "fill",
+			spacing: "2",
+			scrollbar_theme,
+			for (index, item) in items().into_iter().enumerate() {
+				palette_row {
+					title: item.title(),
+					selected: index == selected(),
+					onselect: move |_| item.clone().select(),
+				}
+			}
+		}
+	})
+}
+
+#[component]
+fn palette_row(title: String, selected: bool, onselect: EventHandler<()>) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let mut hovered = use_signal(|| false);
+	let background = if selected || hovered() {
+		theme.surface0.clone()
+	} else {
+		"transparent".to_string()
+	};
+
+	rsx!(CursorArea {
+		icon: CursorIcon::Pointer,
+		rect {
+			width: "fill",
+			padding: "6 8",
+			corner_radius: "6",
+			background: "{background}",
+			onmouseenter: move |_| hovered.set(true),
+			onmouseleave: move |_| hovered.set(false),
+			onclick: move |_| onselect.call(()),
+			label {
 				color: "{theme.text}",
-				font_size: "28",
-				font_family: "JetBrains Mono",
-				"To Be Implemented."
+				font_size: "{appearance.ui_size(14.0)}",
+				font_family: "{appearance.font_family}",
+				"{title}"
 			}
 		}
-		{children}
 	})
 }
@@ -0,0 +1,230 @@
+use crate::data::{
+	fn_utils::apply_text_edit,
+	frontmatter::{FrontMatter, PropertyValue},
+	stores::{CURRENT_EDITOR_BUFFER, SETTINGS, SHOW_PROPERTIES, THEME_STORE, current_file, toggle_properties},
+};
+use freya::prelude::*;
+
+/// Collapsible panel above the editor to view and edit the front matter properties of the current note.
+#[component]
+pub fn properties_panel() -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let front_matter = use_memo(move || current_file().and_then(|file| file.front_matter()));
+
+	let properties: Vec<(String, PropertyValue)> = front_matter()
+		.map(|front_matter| {
+			front_matter
+				.properties()
+				.map(|(name, value)| (name.to_string(), value.clone()))
+				.collect()
+		})
+		.unwrap_or_default();
+	let expanded = SHOW_PROPERTIES();
+	let editor_width = SETTINGS().appearance.editor_width;
+	let chevron_rotation = if expanded { "90deg" } else { "0deg" };
+
+	rsx!(rect {
+		width: "fill",
+		cross_align: "center",
+		margin: "8 0 0 0",
+		rect {
+			width: "{editor_width}%",
+			direction: "vertical",
+			spacing: "4",
+			CursorArea {
+				icon: CursorIcon::Pointer,
+				rect {
+					direction: "horizontal",
+					cross_align: "center",
+					spacing: "4",
+					onclick: move |_| toggle_properties(),
+					rect {
+						width: "16",
+						height: "16",
+						rotate: "{chevron_rotation}",
+						svg {
+							width: "100%",
+							height: "100%",
+							stroke: "{theme.subtext0}",
+							svg_content: include_str!("../static/svgs/chevron-right.svg")
+						}
+					}
+					label {
+						color: "{theme.subtext0}",
+						font_size: "{appearance.ui_size(14.0)}",
+						font_family: "{appearance.font_family}",
+						"Properties ({properties.len()})"
+					}
+				}
+			}
+			if expanded {
+				for (name, value) in properties {
+					property_row { name, value }
+				}
+				new_property_row {}
+			}
+		}
+	})
+}
+
+#[component]
+fn property_row(name: String, value: PropertyValue) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let mut draft = use_signal(|| value.to_string());
+
+	// Reset the draft when the property is changed from the editor.
+	use_effect(use_reactive(&value, move |value| draft.set(value.to_string())));
+
+	let parsed_draft = value.parse_as(&draft());
+	let draft_color = if parsed_draft.is_some() {
+		theme.text.clone()
+	} else {
+		theme.accent.clone()
+	};
+
+	let onkeydown = {
+		let name = name.clone();
+		move |e: KeyboardEvent| {
+			if e.data.key == Key::Enter
+				&& let Some(new_value) = parsed_draft.clone()
+			{
+				edit_front_matter(|front_matter| front_matter.set(&name, new_value));
+			}
+		}
+	};
+
+	let onremove = {
+		let name = name.clone();
+		move |_| edit_front_matter(|front_matter| front_matter.remove(&name))
+	};
+
+	rsx!(rect {
+		width: "fill",
+		direction: "horizontal",
+		cross_align: "center",
+		spacing: "8",
+		padding: "2 4",
+		corner_radius: "8",
+		background: "{theme.base}",
+		onkeydown,
+		label {
+			width: "25%",
+			color: "{theme.subtext1}",
+			font_size: "{appearance.ui_size(14.0)}",
+			font_family: "{appearance.font_family}",
+			max_lines: "1",
+			text_overflow: "ellipsis",
+			"{name}"
+		}
+		rect {
+			width: "fill",
+			{match &value {
+				PropertyValue::Bool(checked) => {
+					let checked = *checked;
+					let name = name.clone();
+					rsx!(Switch {
+						enabled: checked,
+						ontoggled: move |_| edit_front_matter(|front_matter| front_matter.set(&name, PropertyValue::Bool(!checked))),
+					})
+				}
+				_ => rsx!(Input {
+					value: draft,
+					onchange: move |text| draft.set(text),
+					theme: theme_with!(InputTheme {
+						font_color: Cow::from(draft_color.clone()),
+						background: Cow::from(theme.crust.clone()),
+						hover_background: Cow::from(theme.crust.clone()),
+						border_fill: Cow::from(theme.surface0.clone()),
+						focus_border_fill: Cow::from(theme.surface2.clone()),
+						width: cow_borrowed!("100%"),
+					}),
+				}),
+			}}
+		}
+		label {
+			color: "{theme.overlay0}",
+			font_size: "{appearance.ui_size(12.0)}",
+			font_family: "{appearance.font_family}",
+			"{value.kind_name()}"
+		}
+		CursorArea {
+			icon: CursorIcon::Pointer,
+			label {
+				color: "{theme.subtext0}",
+				font_size: "{appearance.ui_size(16.0)}",
+				font_family: "{appearance.font_family}",
+				onclick: onremove,
+				"×"
+			}
+		}
+	})
+}
+
+/// Inputs for adding a new property, the type of the value is inferred from what is typed.
+#[component]
+fn new_property_row() -> Element {
+	let theme = THEME_STORE().current_theme.colors;
+	let mut name = use_signal(String::new);
+	let mut value = use_signal(String::new);
+
+	let onkeydown = move |e: KeyboardEvent| {
+		let new_name = name().trim().to_string();
+		if e.data.key != Key::Enter || new_name.is_empty() {
+			return;
+		}
+		let new_value = PropertyValue::parse(&value());
+		edit_front_matter(|front_matter| front_matter.set(&new_name, new_value));
+		name.set(String::new());
+		value.set(String::new());
+	};
+
+	let input_theme = theme_with!(InputTheme {
+		font_color: Cow::from(theme.text.clone()),
+		background: Cow::from(theme.crust.clone()),
+		hover_background: Cow::from(theme.crust.clone()),
+		border_fill: Cow::from(theme.surface0.clone()),
+		focus_border_fill: Cow::from(theme.surface2.clone()),
+		width: cow_borrowed!("100%"),
+	});
+
+	rsx!(rect {
+		width: "fill",
+		direction: "horizontal",
+		cross_align: "center",
+		spacing: "8",
+		padding: "2 4",
+		onkeydown,
+		rect {
+			width: "25%",
+			Input {
+				value: name,
+				placeholder: "New property",
+				onchange: move |text| name.set(text),
+				theme: input_theme.clone(),
+			}
+		}
+		rect {
+			width: "fill",
+			Input {
+				value: value,
+				placeholder: "Value",
+				onchange: move |text| value.set(text),
+				theme: input_theme,
+			}
+		}
+	})
+}
+
+/// Applies a change to the front matter of the current note, creating the front matter if the note has none.
+fn edit_front_matter(change: impl FnOnce(&mut FrontMatter)) {
+	let Some(file) = current_file() else {
+		return;
+	};
+	let mut front_matter = file.front_matter().unwrap_or_else(FrontMatter::empty);
+	change(&mut front_matter);
+
+	let cursor = CURRENT_EDITOR_BUFFER().editor().read().cursor_pos();
+	apply_text_edit(&mut CURRENT_EDITOR_BUFFER(), front_matter.text_edit(cursor));
+}
@@ -0,0 +1,209 @@
+use crate::{
+	data::{
+		io_utils::{get_config_dir, get_dictionary_path, load_settings, save_settings},
+		settings::{Setting, Settings, SettingsPage},
+		stores::THEME_STORE,
+		types::DICTIONARY_FILE,
+	},
+	view::side_panel::{panel_header, panel_row},
+};
+use freya::prelude::*;
+
+/// Window editing the settings, one page per category. Valid values are saved as they are typed, and the
+/// main window applies them once it sees the settings file change.
+#[component]
+pub fn settings_window() -> Element {
+	let platform = use_platform();
+	let theme = THEME_STORE().current_theme.colors;
+	let mut settings = use_signal(load_settings);
+	let mut page = use_signal(|| SettingsPage::Appearance);
+	// The window shows the fonts and zoom being edited.
+	let appearance = settings.read().appearance.clone();
+
+	let scrollbar_theme = theme_with!(ScrollBarTheme {
+		background: cow_borrowed!("transparent"), //
+		thumb_background: Cow::from(theme.surface0.clone()),
+		hover_thumb_background: Cow::from(theme.surface1.clone()),
+		active_thumb_background: Cow::from(theme.surface2.clone()),
+	});
+
+	let restore_defaults = move |_| {
+		let defaults = Settings::default();
+		save_change(settings, |saved| {
+			for setting in Setting::ALL.into_iter().filter(|setting| setting.page() == page()) {
+				let _ = saved.set(setting, &defaults.value(setting));
+			}
+		});
+	};
+
+	rsx!(rect {
+		width: "100%",
+		height: "100%",
+		direction: "horizontal",
+		background: "{theme.crust}",
+		rect {
+			width: "180",
+			height: "fill",
+			padding: "12 8",
+			spacing: "2",
+			background: "{theme.mantle}",
+			for settings_page in SettingsPage::ALL {
+				panel_row {
+					selected: page() == settings_page,
+					onclick: move |_| page.set(settings_page),
+					label {
+						color: "{theme.text}",
+						font_size: "{appearance.ui_size(14.0)}",
+						font_family: "{appearance.font_family}",
+						"{settings_page.title()}"
+					}
+				}
+			}
+		}
+		rect {
+			width: "fill",
+			height: "fill",
+			padding: "12 16",
+			spacing: "8",
+			panel_header { title: page().title() }
+			ScrollView {
+				width: "100%",
+				height: "fill",
+				spacing: "12",
+				scrollbar_theme,
+				for setting in Setting::ALL.into_iter().filter(|setting| setting.page() == page()) {
+					setting_row { key: "{setting.title()}", setting, settings }
+				}
+			}
+			rect {
+				width: "100%",
+				direction: "horizontal",
+				main_align: "end",
+				spacing: "8",
+				Button {
+					onpress: restore_defaults,
+					label { "Restore Defaults" }
+				}
+				Button {
+					onpress: move |_| platform.close_window(),
+					label { "Close" }
+				}
+			}
+		}
+	})
+}
+
+/// Applies the change to the settings of the file rather than to the copy of the window, which keeps the
+/// settings changed by the main window since, like the zoom, and saves them.
+fn save_change(mut settings: Signal<Settings>, change: impl FnOnce(&mut Settings)) {
+	let mut saved = load_settings();
+	let before = saved.clone();
+	change(&mut saved);
+	if saved != before {
+		save_settings(&saved);
+	}
+	settings.set(saved);
+}
+
+/// A setting with its description, edited with a switch or a text field showing why the typed value is
+/// invalid.
+#[component]
+fn setting_row(setting: Setting, settings: Signal<Settings>) -> Element {
+	let theme = THEME_STORE().current_theme.colors;
+	let appearance = settings.read().appearance.clone();
+	let mut draft = use_signal(|| settings.peek().value(setting));
+	// The spell check can not be turned on without a word list, which Windows does not have.
+	let missing_dictionary = use_hook(move || setting == Setting::SpellCheck && get_dictionary_path().is_none());
+
+	// Reset the draft when the setting is changed by something else than the draft, like the defaults.
+	use_effect(move || {
+		let settings = settings.read();
+		let mut from_draft = settings.clone();
+		if from_draft.set(setting, &draft.peek()).is_err() || from_draft != *settings {
+			draft.set(settings.value(setting));
+		}
+	});
+
+	let error = settings.read().clone().set(setting, &draft()).err();
+	let draft_color = if error.is_none() {
+		theme.text.clone()
+	} else {
+		theme.accent.clone()
+	};
+
+	let apply = move |text: String| {
+		save_change(settings, |saved| {
+			let _ = saved.set(setting, &text);
+		});
+	};
+
+	rsx!(rect {
+		width: "fill",
+		direction: "vertical",
+		spacing: "4",
+		padding: "8",
+		corner_radius: "8",
+		background: "{theme.base}",
+		rect {
+			width: "fill",
+			direction: "horizontal",
+			main_align: "space-between",
+			cross_align: "center",
+			label {
+				color: "{theme.text}",
+				font_size: "{appearance.ui_size(15.0)}",
+				font_family: "{appearance.font_family}",
+				"{setting.title()}"
+			}
+			if setting.is_toggle() && !missing_dictionary {
+				Switch {
+					enabled: draft() == "true",
+					ontoggled: move |_| {
+						let toggled = (draft() != "true").to_string();
+						draft.set(toggled.clone());
+						apply(toggled);
+					},
+				}
+			}
+		}
+		label {
+			color: "{theme.subtext0}",
+			font_size: "{appearance.ui_size(12.0)}",
+			font_family: "{appearance.font_family}",
+			"{setting.description()}"
+		}
+		if missing_dictionary {
+			label {
+				color: "{theme.accent}",
+				font_size: "{appearance.ui_size(12.0)}",
+				font_family: "{appearance.font_family}",
+				"No dictionary was found. Add a word list, one word per line, at {get_config_dir().join(DICTIONARY_FILE).display()} and open the settings again."
+			}
+		}
+		if !setting.is_toggle() {
+			Input {
+				value: draft,
+				onchange: move |text: String| {
+					draft.set(text.clone());
+					apply(text);
+				},
+				theme: theme_with!(InputTheme {
+					font_color: Cow::from(draft_color.clone()),
+					background: Cow::from(theme.crust.clone()),
+					hover_background: Cow::from(theme.crust.clone()),
+					border_fill: Cow::from(theme.surface0.clone()),
+					focus_border_fill: Cow::from(theme.surface2.clone()),
+					width: cow_borrowed!("100%"),
+				}),
+			}
+		}
+		if let Some(error) = error {
+			label {
+				color: "{theme.accent}",
+				font_size: "{appearance.ui_size(12.0)}",
+				font_family: "{appearance.font_family}",
+				"{error}"
+			}
+		}
+	})
+}
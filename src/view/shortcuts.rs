@@ -0,0 +1,168 @@
+use freya::prelude::*;
+
+use crate::{
+	data::{
+		commands::Command,
+		fn_utils::chord_from_event,
+		keymap::format_sequence,
+		stores::{
+			KEYMAP, RECORDING_BINDING, SETTINGS, THEME_STORE, cancel_binding_recording, record_binding, record_chord,
+			save_recorded_binding, toggle_shortcuts,
+		},
+	},
+	view::side_panel::panel_row,
+};
+
+/// Searchable list of the commands with their key bindings, grouped by category. Clicking a command
+/// records a new binding for it.
+#[component]
+pub fn shortcuts_overlay() -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let mut query = use_signal(String::new);
+
+	let groups = use_memo(move || {
+		let keymap = KEYMAP.read();
+		let query = query().to_lowercase();
+		let mut groups: Vec<(&'static str, Vec<(Command, Vec<String>)>)> = Vec::new();
+		for command in Command::ALL {
+			let sequences = keymap.sequences_of(&command.id());
+			let matches = command.title().to_lowercase().contains(&query)
+				|| sequences.iter().any(|sequence| sequence.to_lowercase().contains(&query));
+			if !matches {
+				continue;
+			}
+			match groups.iter_mut().find(|(category, _)| *category == command.category()) {
+				Some((_, rows)) => rows.push((command, sequences)),
+				None => groups.push((command.category(), vec![(command, sequences)])),
+			}
+		}
+		groups
+	});
+
+	// While recording, every key goes to the new binding: Enter saves it and Escape cancels.
+	let onglobalkeydown = move |e: KeyboardEvent| {
+		if RECORDING_BINDING.peek().is_none() {
+			if e.data.key == Key::Escape {
+				toggle_shortcuts();
+			}
+			return;
+		}
+		e.stop_propagation();
+		match e.data.key {
+			Key::Enter if e.data.modifiers.is_empty() => save_recorded_binding(),
+			Key::Escape => cancel_binding_recording(),
+			_ => {
+				if let Some(chord) = chord_from_event(&e) {
+					record_chord(chord);
+				}
+			}
+		}
+	};
+
+	let scrollbar_theme = theme_with!(ScrollBarTheme {
+		background: cow_borrowed!("transparent"), //
+		thumb_background: Cow::from(theme.surface0.clone()),
+		hover_thumb_background: Cow::from(theme.surface1.clone()),
+		active_thumb_background: Cow::from(theme.surface2.clone()),
+	});
+
+	rsx!(rect {
+		width: "fill",
+		height: "fill",
+		direction: "vertical",
+		spacing: "8",
+		onglobalkeydown,
+		Input {
+			value: query,
+			placeholder: "Search shortcuts...",
+			onchange: move |text| query.set(text),
+			theme: theme_with!(InputTheme {
+				font_color: Cow::from(theme.text.clone()),
+				background: Cow::from(theme.base.clone()),
+				hover_background: Cow::from(theme.base.clone()),
+				border_fill: Cow::from(theme.surface0.clone()),
+				focus_border_fill: Cow::from(theme.surface2.clone()),
+				width: cow_borrowed!("100%"),
+			}),
+		}
+		if groups.read().is_empty() {
+			label {
+				color: "{theme.subtext0}",
+				font_size: "{appearance.ui_size(14.0)}",
+				font_family: "{appearance.font_family}",
+				"No matching shortcut."
+			}
+		}
+		ScrollView {
+			width: "100%",
+			height: "fill",
+			spacing: "2",
+			scrollbar_theme,
+			for (category, rows) in groups() {
+				label {
+					margin: "8 6 2 6",
+					color: "{theme.subtext0}",
+					font_size: "{appearance.ui_size(13.0)}",
+					font_family: "{appearance.font_family}",
+					font_weight: "bold",
+					"{category}"
+				}
+				for (command, sequences) in rows {
+					shortcut_row { command, sequences }
+				}
+			}
+		}
+	})
+}
+
+#[component]
+fn shortcut_row(command: Command, sequences: Vec<String>) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let recorded = RECORDING_BINDING
+		.read()
+		.as_ref()
+		.filter(|(recording, _)| *recording == command)
+		.map(|(_, chords)| chords.clone());
+
+	let bindings = match &recorded {
+		Some(chords) if chords.is_empty() => vec!["Press keys, then Enter".to_string()],
+		Some(chords) => vec![format_sequence(chords)],
+		None if sequences.is_empty() => vec!["Unbound".to_string()],
+		None => sequences,
+	};
+
+	rsx!(panel_row {
+		selected: recorded.is_some(),
+		onclick: move |_| record_binding(command),
+		label {
+			width: "50%",
+			color: "{theme.text}",
+			font_size: "{appearance.ui_size(14.0)}",
+			font_family: "{appearance.font_family}",
+			text_overflow: "ellipsis",
+			max_lines: "1",
+			"{command.title()}"
+		}
+		rect {
+			width: "50%",
+			direction: "horizontal",
+			main_align: "end",
+			spacing: "6",
+			for binding in bindings {
+				rect {
+					padding: "2 6",
+					corner_radius: "4",
+					background: "{theme.surface0}",
+					label {
+						color: "{theme.subtext0}",
+						font_size: "{appearance.ui_size(13.0)}",
+						font_family: "{appearance.font_family}",
+						"{binding}"
+					}
+				}
+			}
+		}
+	})
+}
@@ -0,0 +1,91 @@
+use crate::{
+	data::{
+		stores::{ACTIVE_SIDE_PANEL, SETTINGS, THEME_STORE},
+		types::SidePanel,
+	},
+	view::{
+		calendar_panel::calendar_panel, history_panel::history_panel, outline_panel::outline_panel, tag_browser::tag_browser,
+		tasks_panel::tasks_panel,
+	},
+};
+use freya::prelude::*;
+
+/// Panel shown on the right of the editor, the content depends on the active side panel.
+#[component]
+pub fn side_panel() -> Element {
+	let theme = THEME_STORE().current_theme.colors;
+
+	let Some(panel) = ACTIVE_SIDE_PANEL() else {
+		return rsx! {};
+	};
+
+	rsx!(rect {
+		width: "260",
+		height: "fill",
+		padding: "10",
+		margin: "16 10 50 0",
+		direction: "vertical",
+		spacing: "6",
+		background: "{theme.base}",
+		shadow: "5 8 8 2 rgb(0, 0, 0, 10)",
+		corner_radius: "12",
+		{match panel {
+			SidePanel::Tags => rsx!(tag_browser {}),
+			SidePanel::Outline => rsx!(outline_panel {}),
+			SidePanel::Calendar => rsx!(calendar_panel {}),
+			SidePanel::Tasks => rsx!(tasks_panel {}),
+			SidePanel::History => rsx!(history_panel {}),
+		}}
+	})
+}
+
+/// Title shown at the top of a side panel.
+#[component]
+pub fn panel_header(title: String) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+
+	rsx!(rect {
+		width: "fill",
+		padding: "2 4 6 4",
+		border: "0 0 1 0 inner { theme.surface0 }",
+		label {
+			color: "{theme.text}",
+			font_size: "{appearance.ui_size(16.0)}",
+			font_family: "{appearance.font_family}",
+			font_weight: "bold",
+			"{title}"
+		}
+	})
+}
+
+/// A clickable row of a side panel list.
+#[component]
+pub fn panel_row(onclick: EventHandler<()>, selected: bool, children: Element) -> Element {
+	let theme = THEME_STORE().current_theme.colors;
+	let mut hovered = use_signal(|| false);
+
+	let background = if selected {
+		theme.surface1.clone()
+	} else if hovered() {
+		theme.surface0.clone()
+	} else {
+		"transparent".to_string()
+	};
+
+	rsx!(CursorArea {
+		icon: CursorIcon::Pointer,
+		rect {
+			width: "fill",
+			padding: "4 6",
+			direction: "horizontal",
+			cross_align: "center",
+			corner_radius: "8",
+			background: "{background}",
+			onclick: move |_| onclick.call(()),
+			onmouseenter: move |_| hovered.set(true),
+			onmouseleave: move |_| hovered.set(false),
+			{children}
+		}
+	})
+}
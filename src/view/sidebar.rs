@@ -1,15 +1,22 @@
-use crate::data::stores::{
-	SHOW_SETTINGS_DROPUP, SHOW_THEMES_DROPUP, THEME_STORE, toggle_command_palette, toggle_recent_files, toggle_settings_dropup,
-	toggle_themes_dropup,
+use crate::data::{
+	stores::{
+		SHOW_SETTINGS_DROPUP, SHOW_THEMES_DROPUP, THEME_STORE, close_settings_dropup, commit_theme, end_theme_preview,
+		save_high_contrast_variant, toggle_command_palette, toggle_recent_files, toggle_settings_dropup, toggle_shortcuts,
+		toggle_side_panel, toggle_themes_dropup,
+	},
+	theme_contrast::check_contrast,
+	types::SidePanel,
 };
-use crate::view::widgets::buttons;
 use crate::view::dropdown;
+use crate::view::settings_window::settings_window;
+use crate::view::theme_editor::{theme_editor_window, theme_editor_windowProps};
+use crate::view::theme_import::{theme_import_window, theme_import_windowProps};
+use crate::view::widgets::buttons;
 use freya::prelude::*;
 
 #[component]
 pub fn side_bar() -> Element {
 	let theme = THEME_STORE().current_theme.colors;
-	let themes_list = THEME_STORE().themes_list;
 	let platform = use_platform();
 
 	let settings_list: [buttons::DropDownButtonProps; 4] = [
@@ -17,7 +24,9 @@ pub fn side_bar() -> Element {
 			label: "General Settings".to_string(),
 			onclick: EventHandler::new(move |_| {
 				platform.new_window(
-					WindowConfig::new_with_props(settings_window, settings_windowProps { value: 404 }).with_title("Rhyolite Settings"),
+					WindowConfig::new(settings_window)
+						.with_title("Rhyolite Settings")
+						.with_size(760.0, 540.0),
 				)
 			}),
 			icon: Some(include_str!("../static/svgs/sliders-horizontal.svg")),
@@ -33,7 +42,7 @@ pub fn side_bar() -> Element {
 		},
 		buttons::DropDownButtonProps {
 			label: "Keyboard Shortcuts".to_string(),
-			onclick: EventHandler::new(|_| {}),
+			onclick: EventHandler::new(|_| toggle_shortcuts()),
 			icon: Some(include_str!("../static/svgs/keyboard.svg")),
 			..Default::default()
 		},
@@ -60,21 +69,114 @@ pub fn side_bar() -> Element {
 				}
 			}
 			if SHOW_THEMES_DROPUP() {
-				dropdown::submenu {
-					for theme in themes_list {
-						buttons::DropDownButton {
-							label: &theme.0,
-							onclick: EventHandler::new({
-								let theme = theme.clone();
-								move |_| {
-									let theme_path = theme.1.clone();
-									spawn(async move {
-										THEME_STORE.write().change_current_theme(theme_path).await;
-									});
-								}
-							}),
+				themes_menu {}
+			}
+		}
+	})
+}
+
+/// The themes of the themes menu are previewed while hovered or picked with the arrow keys, leaving the
+/// menu restores the current theme and clicking or pressing enter keeps the previewed one.
+#[component]
+fn themes_menu() -> Element {
+	let themes_list = THEME_STORE().themes_list;
+	let low_contrast = THEME_STORE().low_contrast;
+	// The theme in use rather than the previewed one, so the entries do not move while previewing.
+	let contrast_failures = check_contrast(&THEME_STORE().committed_theme().colors);
+	let platform = use_platform();
+	let mut highlighted = use_signal(|| None::<usize>);
+
+	let onglobalkeydown = move |e: KeyboardEvent| {
+		let themes_list = THEME_STORE.peek().themes_list.clone();
+		let count = themes_list.len();
+		let index = match e.data.key {
+			Key::ArrowDown if count > 0 => highlighted().map_or(0, |index| (index + 1) % count),
+			Key::ArrowUp if count > 0 => highlighted().map_or(count - 1, |index| (index + count - 1) % count),
+			Key::Enter => {
+				if let Some((_, source)) = highlighted().and_then(|index| themes_list.get(index).cloned()) {
+					spawn(commit_theme(source));
+				}
+				return;
+			}
+			Key::Escape => {
+				close_settings_dropup();
+				return;
+			}
+			_ => return,
+		};
+		e.stop_propagation();
+		highlighted.set(Some(index));
+		THEME_STORE.write().preview_theme(themes_list[index].1.clone());
+	};
+
+	rsx!(dropdown::submenu {
+		onmouseleave: move |_| {
+			highlighted.set(None);
+			end_theme_preview();
+		},
+		rect {
+			width: "100%",
+			direction: "vertical",
+			spacing: "6",
+			onglobalkeydown,
+			buttons::DropDownButton {
+				label: "Theme Editor",
+				onclick: EventHandler::new(move |_| {
+					end_theme_preview();
+					let props = theme_editor_windowProps {
+						theme: THEME_STORE().current_theme,
+						themes_dir: THEME_STORE().themes_dir,
+					};
+					platform.new_window(
+						WindowConfig::new_with_props(theme_editor_window, props)
+							.with_title("Rhyolite Theme Editor")
+							.with_size(900.0, 640.0),
+					)
+				}),
+				icon: Some(include_str!("../static/svgs/palette.svg")),
+			}
+			buttons::DropDownButton {
+				label: "Import Theme",
+				onclick: EventHandler::new(move |_| {
+					end_theme_preview();
+					let props = theme_import_windowProps {
+						theme: THEME_STORE().current_theme,
+						themes_dir: THEME_STORE().themes_dir,
+					};
+					platform.new_window(
+						WindowConfig::new_with_props(theme_import_window, props)
+							.with_title("Rhyolite Theme Import")
+							.with_size(900.0, 640.0),
+					)
+				}),
+				icon: Some(include_str!("../static/svgs/palette.svg")),
+			}
+			if !contrast_failures.is_empty() {
+				buttons::DropDownButton {
+					label: "High Contrast Variant",
+					note: Some(format!("{} pairs below WCAG AA", contrast_failures.len())),
+					onclick: EventHandler::new(move |_| {
+						spawn(save_high_contrast_variant());
+					}),
+					icon: Some(include_str!("../static/svgs/info.svg")),
+				}
+			}
+			for (index, (name, source)) in themes_list.into_iter().enumerate() {
+				buttons::DropDownButton {
+					key: "{name}",
+					note: low_contrast.contains(&name).then(|| "low contrast".to_string()),
+					label: name,
+					selected: highlighted() == Some(index),
+					onclick: EventHandler::new({
+						let source = source.clone();
+						move |_| {
+							spawn(commit_theme(source.clone()));
 						}
-					}
+					}),
+					onmouseenter: EventHandler::new(move |_| {
+						highlighted.set(Some(index));
+						THEME_STORE.write().preview_theme(source.clone());
+					}),
 				}
 			}
 		}
@@ -124,6 +226,61 @@ fn top_buttons() -> Element {
 				stroke: "{ theme.surface2 }",
 				svg_content: include_str!("../static/svgs/recent_files.svg")
 			}
+		},
+
+		// Tag Browser Toggle Button
+		sidebar_button {
+			on_click: move |_| toggle_side_panel(SidePanel::Tags),
+			svg {
+				width: "100%",
+				height: "100%",
+				stroke: "{ theme.surface2 }",
+				svg_content: include_str!("../static/svgs/tag.svg")
+			}
+		},
+
+		// Outline Toggle Button
+		sidebar_button {
+			on_click: move |_| toggle_side_panel(SidePanel::Outline),
+			svg {
+				width: "100%",
+				height: "100%",
+				stroke: "{ theme.surface2 }",
+				svg_content: include_str!("../static/svgs/outline.svg")
+			}
+		},
+
+		// Calendar Toggle Button
+		sidebar_button {
+			on_click: move |_| toggle_side_panel(SidePanel::Calendar),
+			svg {
+				width: "100%",
+				height: "100%",
+				stroke: "{ theme.surface2 }",
+				svg_content: include_str!("../static/svgs/calendar.svg")
+			}
+		},
+
+		// Tasks Toggle Button
+		sidebar_button {
+			on_click: move |_| toggle_side_panel(SidePanel::Tasks),
+			svg {
+				width: "100%",
+				height: "100%",
+				stroke: "{ theme.surface2 }",
+				svg_content: include_str!("../static/svgs/tasks.svg")
+			}
+		}
+
+		// History Toggle Button
+		sidebar_button {
+			on_click: move |_| toggle_side_panel(SidePanel::History),
+			svg {
+				width: "100%",
+				height: "100%",
+				stroke: "{ theme.surface2 }",
+				svg_content: include_str!("../static/svgs/history.svg")
+			}
 		}
 	})
 }
@@ -195,31 +352,3 @@ fn sidebar_button(on_click: EventHandler<()>, children: Element) -> Element {
 
 	)
 }
-
-// TODO: Move this into its own component.
-#[component]
-fn settings_window(value: i32) -> Element {
-	let platform = use_platform();
-	let theme = THEME_STORE().current_theme.colors;
-
-	let onpress = move |_| platform.close_window();
-
-	rsx!(
-		rect {
-			height: "100%",
-			width: "100%",
-			main_align: "center",
-			cross_align: "center",
-			background: theme.crust,
-			font_size: "30",
-			label {
-				color: theme.text,
-				"{value}: Yet to be Implemented."
-			}
-			Button {
-				onpress,
-				label { "Close" }
-			}
-		}
-	)
-}
@@ -0,0 +1,146 @@
+use crate::{
+	data::stores::{SELECTED_TAGS, SETTINGS, TAG_INDEX, THEME_STORE, open_file_tab, toggle_selected_tag},
+	view::side_panel::{panel_header, panel_row},
+};
+use freya::prelude::*;
+use std::path::PathBuf;
+
+/// Lists all the tags in the trove with their counts, selecting tags filters the notes that have all of them.
+#[component]
+pub fn tag_browser() -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let tag_counts = TAG_INDEX.read().tag_counts();
+	let selected_tags = SELECTED_TAGS();
+	let filtered_files = TAG_INDEX.read().files_with_tags(&selected_tags);
+
+	let scrollbar_theme = theme_with!(ScrollBarTheme {
+		background: cow_borrowed!("transparent"), //
+		thumb_background: Cow::from(theme.surface0.clone()),
+		hover_thumb_background: Cow::from(theme.surface1.clone()),
+		active_thumb_background: Cow::from(theme.surface2.clone()),
+	});
+
+	let tags_height = if selected_tags.is_empty() { "fill" } else { "50%" };
+	let filter_label = selected_tags
+		.iter()
+		.map(|tag| format!("#{tag}"))
+		.collect::<Vec<String>>()
+		.join(" + ");
+
+	rsx!(
+		panel_header { title: "Tags" }
+		if tag_counts.is_empty() {
+			label {
+				color: "{theme.subtext0}",
+				font_size: "{appearance.ui_size(14.0)}",
+				font_family: "{appearance.font_family}",
+				"No tags in this trove yet."
+			}
+		}
+		ScrollView {
+			width: "100%",
+			height: "{tags_height}",
+			spacing: "2",
+			scrollbar_theme: scrollbar_theme.clone(),
+			for (tag, count) in tag_counts {
+				tag_row {
+					selected: selected_tags.contains(&tag),
+					tag,
+					count,
+				}
+			}
+		}
+		if !selected_tags.is_empty() {
+			rect {
+				width: "fill",
+				direction: "horizontal",
+				main_align: "space-between",
+				padding: "6 4 2 4",
+				label {
+					width: "fill",
+					color: "{theme.subtext0}",
+					font_size: "{appearance.ui_size(13.0)}",
+					font_family: "{appearance.font_family}",
+					max_lines: "1",
+					text_overflow: "ellipsis",
+					"{filter_label}"
+				}
+				CursorArea {
+					icon: CursorIcon::Pointer,
+					label {
+						color: "{theme.accent}",
+						font_size: "{appearance.ui_size(13.0)}",
+						font_family: "{appearance.font_family}",
+						onclick: move |_| SELECTED_TAGS.write().clear(),
+						"Clear"
+					}
+				}
+			}
+			ScrollView {
+				width: "100%",
+				height: "fill",
+				spacing: "2",
+				scrollbar_theme,
+				for path in filtered_files {
+					note_row { path }
+				}
+			}
+		}
+	)
+}
+
+#[component]
+fn tag_row(tag: String, count: usize, selected: bool) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	// Nested tags are indented under their parents and only show their last segment.
+	let depth = tag.matches('/').count();
+	let name = tag.rsplit('/').next().unwrap_or_default().to_string();
+
+	rsx!(panel_row {
+		selected,
+		onclick: move |_| toggle_selected_tag(tag.clone()),
+		label {
+			width: "fill",
+			margin: "0 0 0 {depth * 12}",
+			color: "{theme.text}",
+			font_size: "{appearance.ui_size(14.0)}",
+			font_family: "{appearance.font_family}",
+			max_lines: "1",
+			text_overflow: "ellipsis",
+			"#{name}"
+		}
+		label {
+			color: "{theme.subtext0}",
+			font_size: "{appearance.ui_size(13.0)}",
+			font_family: "{appearance.font_family}",
+			"{count}"
+		}
+	})
+}
+
+#[component]
+fn note_row(path: PathBuf) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let title = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+
+	rsx!(panel_row {
+		selected: false,
+		onclick: move |_| {
+			let path = path.clone();
+			spawn(async move {
+				open_file_tab(path).await;
+			});
+		},
+		label {
+			color: "{theme.text}",
+			font_size: "{appearance.ui_size(14.0)}",
+			font_family: "{appearance.font_family}",
+			max_lines: "1",
+			text_overflow: "ellipsis",
+			"{title}"
+		}
+	})
+}
@@ -0,0 +1,192 @@
+use crate::{
+	data::{
+		stores::{SETTINGS, TASK_INDEX, THEME_STORE, open_task, toggle_task_in_file},
+		tasks::{DueFilter, StatusFilter, Task, TaskFilter},
+	},
+	view::side_panel::{panel_header, panel_row},
+};
+use chrono::Local;
+use freya::prelude::*;
+
+const STATUS_FILTERS: [(StatusFilter, &str); 3] = [
+	(StatusFilter::Open, "Open"),
+	(StatusFilter::Done, "Done"),
+	(StatusFilter::All, "All"),
+];
+
+const DUE_FILTERS: [(DueFilter, &str); 5] = [
+	(DueFilter::Any, "Any"),
+	(DueFilter::Overdue, "Overdue"),
+	(DueFilter::Today, "Today"),
+	(DueFilter::Upcoming, "Upcoming"),
+	(DueFilter::NoDate, "None"),
+];
+
+/// Lists the tasks of every note in the trove, clicking a task opens its note at the task line.
+#[component]
+pub fn tasks_panel() -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let mut filter = use_signal(|| TaskFilter {
+		status: StatusFilter::Open,
+		due: DueFilter::Any,
+	});
+	let today = Local::now().date_naive();
+	let tasks = TASK_INDEX.read().tasks(&filter(), today);
+
+	let scrollbar_theme = theme_with!(ScrollBarTheme {
+		background: cow_borrowed!("transparent"), //
+		thumb_background: Cow::from(theme.surface0.clone()),
+		hover_thumb_background: Cow::from(theme.surface1.clone()),
+		active_thumb_background: Cow::from(theme.surface2.clone()),
+	});
+
+	rsx!(
+		panel_header { title: "Tasks" }
+		rect {
+			width: "fill",
+			direction: "horizontal",
+			spacing: "4",
+			for (status, label) in STATUS_FILTERS {
+				filter_chip {
+					label,
+					selected: filter().status == status,
+					onclick: move |_| filter.write().status = status,
+				}
+			}
+		}
+		rect {
+			width: "fill",
+			direction: "horizontal",
+			spacing: "4",
+			for (due, label) in DUE_FILTERS {
+				filter_chip {
+					label,
+					selected: filter().due == due,
+					onclick: move |_| filter.write().due = due,
+				}
+			}
+		}
+		if tasks.is_empty() {
+			label {
+				color: "{theme.subtext0}",
+				font_size: "{appearance.ui_size(14.0)}",
+				font_family: "{appearance.font_family}",
+				"No tasks."
+			}
+		}
+		ScrollView {
+			width: "100%",
+			height: "fill",
+			spacing: "2",
+			scrollbar_theme,
+			for task in tasks {
+				task_row { overdue: !task.done && task.due.is_some_and(|due| due < today), task }
+			}
+		}
+	)
+}
+
+#[component]
+fn task_row(task: Task, overdue: bool) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+
+	let file_name = task
+		.path
+		.file_stem()
+		.map(|stem| stem.to_string_lossy().into_owned())
+		.unwrap_or_default();
+	let source = format!("{file_name}:{}", task.line + 1);
+	let due = task.due.map(|due| due.format("%Y-%m-%d").to_string());
+	let due_color = if overdue { theme.accent.clone() } else { theme.subtext0.clone() };
+	let (text_color, checkbox_fill) = if task.done {
+		(theme.overlay0.clone(), theme.surface2.clone())
+	} else {
+		(theme.text.clone(), "transparent".to_string())
+	};
+
+	let path = task.path.clone();
+	let line = task.line;
+	let toggle_path = task.path.clone();
+
+	rsx!(panel_row {
+		selected: false,
+		onclick: move |_| {
+			spawn(open_task(path.clone(), line));
+		},
+		rect {
+			width: "14",
+			height: "14",
+			margin: "0 8 0 0",
+			corner_radius: "4",
+			border: "1.5 inner { theme.surface2 }",
+			background: "{checkbox_fill}",
+			onclick: move |e: MouseEvent| {
+				e.stop_propagation();
+				spawn(toggle_task_in_file(toggle_path.clone(), line));
+			},
+		}
+		rect {
+			width: "fill",
+			direction: "vertical",
+			label {
+				width: "fill",
+				color: "{text_color}",
+				font_size: "{appearance.ui_size(14.0)}",
+				font_family: "{appearance.font_family}",
+				max_lines: "2",
+				text_overflow: "ellipsis",
+				"{task.text}"
+			}
+			rect {
+				width: "fill",
+				direction: "horizontal",
+				main_align: "space-between",
+				label {
+					color: "{theme.overlay0}",
+					font_size: "{appearance.ui_size(12.0)}",
+					font_family: "{appearance.font_family}",
+					max_lines: "1",
+					text_overflow: "ellipsis",
+					"{source}"
+				}
+				if let Some(due) = due {
+					label {
+						color: "{due_color}",
+						font_size: "{appearance.ui_size(12.0)}",
+						font_family: "{appearance.font_family}",
+						"{due}"
+					}
+				}
+			}
+		}
+	})
+}
+
+#[component]
+fn filter_chip(label: String, selected: bool, onclick: EventHandler<()>) -> Element {
+	let appearance = SETTINGS().appearance;
+	let theme = THEME_STORE().current_theme.colors;
+	let (background, color) = if selected {
+		(theme.surface1.clone(), theme.text.clone())
+	} else {
+		("transparent".to_string(), theme.subtext0.clone())
+	};
+
+	rsx!(CursorArea {
+		icon: CursorIcon::Pointer,
+		rect {
+			padding: "2 6",
+			corner_radius: "6",
+			background: "{background}",
+			onclick: move |_| onclick.call(()),
+			label {
+				color: "{color}",
+				font_size: "{appearance.ui_size(12.0)}",
+				font_family: "{appearance.font_family}",
+				"{label}"
+			}
+		}
+	})
+}
@@ -0,0 +1,367 @@
+use crate::{
+	data::{
+		stores::{SETTINGS, THEME_STORE},
+		themes::{ColorScheme, Colors, Theme, hex_color, parse_hex_color, save_new_theme},
+	},
+	view::side_panel::{panel_header, panel_row},
+};
+use freya::prelude::*;
+use std::path::PathBuf;
+
+/// Window editing a copy of a theme, with a picker for each color and a preview of the app in the edited
+/// colors. The theme is saved as a new file of the themes directory, which the main window then lists.
+#[component]
+pub fn theme_editor_window(theme: Theme, themes_dir: PathBuf) -> Element {
+	let appearance = SETTINGS().appearance;
+	let platform = use_platform();
+	let mut edited = use_signal(|| {
+		let mut theme = theme.clone();
+		theme.info.name = format!("{} Copy", theme.info.name);
+		theme
+	});
+	let mut selected_field = use_signal(|| Colors::FIELDS[0]);
+	let mut status = use_signal(String::new);
+	let colors = edited().colors;
+
+	// The window has its own stores, showing the edited theme in them makes the whole window a preview.
+	use_effect(move || THEME_STORE.write().current_theme = edited());
+
+	let scrollbar_theme = theme_with!(ScrollBarTheme {
+		background: cow_borrowed!("transparent"), //
+		thumb_background: Cow::from(colors.surface0.clone()),
+		hover_thumb_background: Cow::from(colors.surface1.clone()),
+		active_thumb_background: Cow::from(colors.surface2.clone()),
+	});
+
+	let save = move |_| {
+		let theme = edited.peek().clone();
+		if theme.info.name.trim().is_empty() {
+			status.set("The theme needs a name.".to_string());
+			return;
+		}
+		match save_new_theme(&themes_dir, &theme) {
+			Ok(path) => status.set(format!("Saved to {}", path.display())),
+			Err(e) => {
+				log::error!("Failed to save the theme {}: {e}", theme.info.name);
+				status.set(format!("Could not save the theme: {e}"));
+			}
+		}
+	};
+
+	rsx!(rect {
+		width: "100%",
+		height: "100%",
+		direction: "horizontal",
+		background: "{colors.crust}",
+		rect {
+			width: "200",
+			height: "fill",
+			padding: "12 8",
+			background: "{colors.mantle}",
+			ScrollView {
+				width: "100%",
+				height: "fill",
+				spacing: "2",
+				scrollbar_theme,
+				for field in Colors::FIELDS {
+					panel_row {
+						key: "{field}",
+						selected: selected_field() == field,
+						onclick: move |_| selected_field.set(field),
+						rect {
+							width: "16",
+							height: "16",
+							corner_radius: "4",
+							border: "1 outer {colors.border}",
+							background: "{colors.get(field).unwrap_or_default()}",
+						}
+						label {
+							color: "{colors.text}",
+							font_size: "{appearance.ui_size(14.0)}",
+							font_family: "{appearance.font_family}",
+							"{field}"
+						}
+					}
+				}
+			}
+		}
+		rect {
+			width: "fill",
+			height: "fill",
+			padding: "12 16",
+			spacing: "8",
+			panel_header { title: "Theme" }
+			theme_info_fields { edited }
+			panel_header { title: "{selected_field()}" }
+			color_picker { key: "{selected_field()}", field: selected_field(), edited }
+			panel_header { title: "Preview" }
+			theme_preview { colors: colors.clone() }
+			rect {
+				width: "100%",
+				direction: "horizontal",
+				main_align: "end",
+				cross_align: "center",
+				spacing: "8",
+				label {
+					width: "fill",
+					color: "{colors.subtext0}",
+					font_size: "{appearance.ui_size(12.0)}",
+					font_family: "{appearance.font_family}",
+					"{status}"
+				}
+				Button {
+					onpress: save,
+					label { "Save as New Theme" }
+				}
+				Button {
+					onpress: move |_| platform.close_window(),
+					label { "Close" }
+				}
+			}
+		}
+	})
+}
+
+/// Name, author and color scheme of the edited theme.
+#[component]
+fn theme_info_fields(edited: Signal<Theme>) -> Element {
+	let appearance = SETTINGS().appearance;
+	let colors = edited().colors;
+	let input_theme = theme_with!(InputTheme {
+		font_color: Cow::from(colors.text.clone()),
+		background: Cow::from(colors.base.clone()),
+		hover_background: Cow::from(colors.base.clone()),
+		border_fill: Cow::from(colors.surface0.clone()),
+		focus_border_fill: Cow::from(colors.surface2.clone()),
+		width: cow_borrowed!("100%"),
+	});
+	let is_dark = edited().info.colorscheme == ColorScheme::Dark;
+
+	rsx!(rect {
+		width: "100%",
+		direction: "horizontal",
+		cross_align: "center",
+		spacing: "8",
+		rect {
+			width: "40%",
+			Input {
+				value: edited().info.name,
+				placeholder: "Name",
+				onchange: move |name: String| edited.write().info.name = name,
+				theme: input_theme.clone(),
+			}
+		}
+		rect {
+			width: "fill",
+			Input {
+				value: edited().info.author,
+				placeholder: "Author",
+				onchange: move |author: String| edited.write().info.author = author,
+				theme: input_theme,
+			}
+		}
+		label {
+			color: "{colors.text}",
+			font_size: "{appearance.ui_size(14.0)}",
+			font_family: "{appearance.font_family}",
+			"Dark"
+		}
+		Switch {
+			enabled: is_dark,
+			ontoggled: move |_| {
+				edited.write().info.colorscheme = if is_dark { ColorScheme::Light } else { ColorScheme::Dark };
+			},
+		}
+	})
+}
+
+/// Picker of one color: a swatch, its hex code and a slider per channel.
+#[component]
+fn color_picker(field: &'static str, edited: Signal<Theme>) -> Element {
+	let appearance = SETTINGS().appearance;
+	let colors = edited().colors;
+	let color = colors.get(field).unwrap_or_default().to_string();
+	let mut draft = use_signal(|| color.clone());
+	let rgb = parse_hex_color(&color).unwrap_or_default();
+	let draft_color = if parse_hex_color(&draft()).is_some() {
+		colors.text.clone()
+	} else {
+		colors.accent.clone()
+	};
+
+	let mut set_channel = move |channel: usize, percent: f64| {
+		let mut rgb = parse_hex_color(edited.peek().colors.get(field).unwrap_or_default()).unwrap_or_default();
+		rgb[channel] = (percent.clamp(0.0, 100.0) * 2.55).round() as u8;
+		let hex = hex_color(rgb);
+		draft.set(hex.clone());
+		edited.write().colors.set(field, hex);
+	};
+
+	rsx!(rect {
+		width: "100%",
+		direction: "horizontal",
+		spacing: "12",
+		padding: "8",
+		corner_radius: "8",
+		background: "{colors.base}",
+		rect {
+			width: "72",
+			height: "72",
+			corner_radius: "8",
+			border: "1 outer {colors.border}",
+			background: "{color}",
+		}
+		rect {
+			width: "fill",
+			spacing: "4",
+			Input {
+				value: draft,
+				onchange: move |text: String| {
+					draft.set(text.clone());
+					if parse_hex_color(&text).is_some() {
+						edited.write().colors.set(field, text.trim().to_string());
+					}
+				},
+				theme: theme_with!(InputTheme {
+					font_color: Cow::from(draft_color),
+					background: Cow::from(colors.crust.clone()),
+					hover_background: Cow::from(colors.crust.clone()),
+					border_fill: Cow::from(colors.surface0.clone()),
+					focus_border_fill: Cow::from(colors.surface2.clone()),
+					width: cow_borrowed!("100%"),
+				}),
+			}
+			for (channel, name) in ["R", "G", "B"].into_iter().enumerate() {
+				rect {
+					key: "{name}",
+					direction: "horizontal",
+					cross_align: "center",
+					spacing: "8",
+					label {
+						width: "16",
+						color: "{colors.subtext1}",
+						font_size: "{appearance.ui_size(12.0)}",
+						font_family: "{appearance.font_family}",
+						"{name}"
+					}
+					Slider {
+						width: "fill",
+						value: f64::from(rgb[channel]) / 2.55,
+						onmoved: move |percent: f64| set_channel(channel, percent),
+					}
+				}
+			}
+		}
+	})
+}
+
+/// A small mock of the app in the edited colors: the tab bar, a note and the palette.
+#[component]
+pub(crate) fn theme_preview(colors: Colors) -> Element {
+	let appearance = SETTINGS().appearance;
+	rsx!(rect {
+		width: "100%",
+		height: "fill",
+		corner_radius: "8",
+		overflow: "clip",
+		border: "1 outer {colors.border}",
+		background: "{colors.base}",
+		rect {
+			width: "100%",
+			height: "32",
+			direction: "horizontal",
+			cross_align: "center",
+			padding: "0 8",
+			spacing: "4",
+			background: "{colors.crust}",
+			for (tab, selected) in [("Welcome", true), ("Notes", false)] {
+				rect {
+					key: "{tab}",
+					padding: "4 12",
+					corner_radius: "6",
+					background: if selected { "{colors.surface0}" } else { "transparent" },
+					label {
+						color: if selected { "{colors.text}" } else { "{colors.subtext0}" },
+						font_size: "{appearance.ui_size(12.0)}",
+						font_family: "{appearance.font_family}",
+						"{tab}"
+					}
+				}
+			}
+		}
+		rect {
+			width: "100%",
+			height: "fill",
+			direction: "horizontal",
+			rect {
+				width: "32",
+				height: "100%",
+				background: "{colors.mantle}",
+				border: "0 1 0 0 outer {colors.surface0}",
+			}
+			rect {
+				width: "fill",
+				padding: "12 16",
+				spacing: "6",
+				label {
+					color: "{colors.text}",
+					font_size: "{appearance.ui_size(18.0)}",
+					font_family: "{appearance.font_family}",
+					"A note in this theme"
+				}
+				paragraph {
+					width: "100%",
+					font_size: "{appearance.ui_size(13.0)}",
+					font_family: "{appearance.font_family}",
+					text { color: "{colors.subtext2}", "Text with a " }
+					text { color: "{colors.accent}", "link" }
+					text { color: "{colors.subtext2}", " and a " }
+					text { background: "{colors.highlight}", color: "{colors.text}", "highlighted word" }
+					text { color: "{colors.subtext2}", "." }
+				}
+				rect {
+					width: "100%",
+					padding: "6 8",
+					corner_radius: "6",
+					background: "{colors.surface1}",
+					label {
+						color: "{colors.subtext1}",
+						font_size: "{appearance.ui_size(12.0)}",
+						font_family: "{appearance.font_family}",
+						"let code = \"block\";"
+					}
+				}
+				label {
+					color: "{colors.overlay1}",
+					font_size: "{appearance.ui_size(12.0)}",
+					font_family: "{appearance.font_family}",
+					"#tag · 42 words"
+				}
+			}
+			rect {
+				width: "160",
+				margin: "12",
+				padding: "6",
+				spacing: "2",
+				corner_radius: "8",
+				background: "{colors.mantle}",
+				border: "1 outer {colors.surface2}",
+				for (command, selected) in [("Open File", true), ("New Note", false), ("Toggle Sidebar", false)] {
+					rect {
+						key: "{command}",
+						width: "100%",
+						padding: "4 6",
+						corner_radius: "4",
+						background: if selected { "{colors.overlay0}" } else { "transparent" },
+						label {
+							color: "{colors.text}",
+							font_size: "{appearance.ui_size(11.0)}",
+							font_family: "{appearance.font_family}",
+							"{command}"
+						}
+					}
+				}
+			}
+		}
+	})
+}
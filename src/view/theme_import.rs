@@ -0,0 +1,228 @@
+use crate::{
+	data::{
+		stores::{SETTINGS, THEME_STORE},
+		theme_import::{ThemeFormat, export_base16, import_themes},
+		themes::{Theme, save_new_theme, theme_file_name},
+	},
+	view::{
+		side_panel::{panel_header, panel_row},
+		theme_editor::theme_preview,
+	},
+};
+use freya::prelude::*;
+use std::{fs, path::PathBuf};
+
+/// Window importing the schemes of other editors as themes, showing a preview of each imported theme
+/// before it is saved to the themes directory. The current theme can also be exported to Base16 from it.
+#[component]
+pub fn theme_import_window(theme: Theme, themes_dir: PathBuf) -> Element {
+	let appearance = SETTINGS().appearance;
+	let platform = use_platform();
+	let mut path = use_signal(String::new);
+	let mut file = use_signal(|| None::<(String, String)>);
+	let mut selected_format = use_signal(|| ThemeFormat::Base16);
+	let mut selected = use_signal(|| 0_usize);
+	let mut status = use_signal(String::new);
+	let imported = use_memo(move || file().map(|(content, name)| import_themes(selected_format(), &content, &name)));
+	let colors = theme.colors.clone();
+
+	// The window has its own stores, they get the theme of the main window for the shared widgets.
+	use_hook({
+		let theme = theme.clone();
+		move || THEME_STORE.write().current_theme = theme
+	});
+
+	let input_theme = theme_with!(InputTheme {
+		font_color: Cow::from(colors.text.clone()),
+		background: Cow::from(colors.base.clone()),
+		hover_background: Cow::from(colors.base.clone()),
+		border_fill: Cow::from(colors.surface0.clone()),
+		focus_border_fill: Cow::from(colors.surface2.clone()),
+		width: cow_borrowed!("100%"),
+	});
+
+	let load = move |_| {
+		let file_path = PathBuf::from(path.peek().trim());
+		match fs::read_to_string(&file_path) {
+			Ok(content) => {
+				if let Some(detected) = ThemeFormat::detect(&file_path) {
+					selected_format.set(detected);
+				}
+				let name = file_path
+					.file_stem()
+					.map(|stem| stem.to_string_lossy().to_string())
+					.unwrap_or_default();
+				selected.set(0);
+				status.set(String::new());
+				file.set(Some((content, name)));
+			}
+			Err(e) => {
+				log::warn!("Failed to read the theme file {}: {e}", file_path.display());
+				status.set(format!("Could not read the file: {e}"));
+				file.set(None);
+			}
+		}
+	};
+
+	let save = {
+		let themes_dir = themes_dir.clone();
+		move |_| {
+			let Some(Ok(themes)) = imported() else {
+				status.set("Load a scheme to import first.".to_string());
+				return;
+			};
+			let mut saved = Vec::new();
+			for theme in themes {
+				match save_new_theme(&themes_dir, &theme) {
+					Ok(path) => saved.push(path.display().to_string()),
+					Err(e) => {
+						log::error!("Failed to save the imported theme {}: {e}", theme.info.name);
+						status.set(format!("Could not save the theme: {e}"));
+						return;
+					}
+				}
+			}
+			status.set(format!("Saved to {}", saved.join(", ")));
+		}
+	};
+
+	let export = {
+		let theme = theme.clone();
+		move |_| {
+			let path = themes_dir.join(theme_file_name(&theme.info.name).replace(".toml", ".yaml"));
+			match fs::write(&path, export_base16(&theme)) {
+				Ok(()) => status.set(format!("Exported to {}", path.display())),
+				Err(e) => {
+					log::error!("Failed to export the theme {}: {e}", theme.info.name);
+					status.set(format!("Could not export the theme: {e}"));
+				}
+			}
+		}
+	};
+
+	let (preview, message) = match imported() {
+		Some(Ok(themes)) => (themes.get(selected()).or(themes.first()).cloned(), None),
+		Some(Err(e)) => (None, Some(format!("Could not import the scheme: {e}"))),
+		None => (None, Some("Enter the path of a scheme file and load it.".to_string())),
+	};
+	let imported_names: Vec<String> = match imported() {
+		Some(Ok(themes)) => themes.into_iter().map(|theme| theme.info.name).collect(),
+		_ => Vec::new(),
+	};
+
+	rsx!(rect {
+		width: "100%",
+		height: "100%",
+		direction: "horizontal",
+		background: "{colors.crust}",
+		rect {
+			width: "200",
+			height: "fill",
+			padding: "12 8",
+			spacing: "2",
+			background: "{colors.mantle}",
+			panel_header { title: "Format" }
+			for scheme_format in ThemeFormat::ALL {
+				panel_row {
+					key: "{scheme_format.title()}",
+					selected: selected_format() == scheme_format,
+					onclick: move |_| selected_format.set(scheme_format),
+					label {
+						color: "{colors.text}",
+						font_size: "{appearance.ui_size(14.0)}",
+						font_family: "{appearance.font_family}",
+						"{scheme_format.title()}"
+					}
+				}
+			}
+			if !imported_names.is_empty() {
+				panel_header { title: "Themes" }
+			}
+			for (index, name) in imported_names.into_iter().enumerate() {
+				panel_row {
+					key: "{index}",
+					selected: selected() == index,
+					onclick: move |_| selected.set(index),
+					label {
+						color: "{colors.text}",
+						font_size: "{appearance.ui_size(14.0)}",
+						font_family: "{appearance.font_family}",
+						"{name}"
+					}
+				}
+			}
+		}
+		rect {
+			width: "fill",
+			height: "fill",
+			padding: "12 16",
+			spacing: "8",
+			panel_header { title: "Scheme File" }
+			rect {
+				width: "100%",
+				direction: "horizontal",
+				cross_align: "center",
+				spacing: "8",
+				rect {
+					width: "fill",
+					Input {
+						value: path,
+						placeholder: "Path of a .yaml, .json or .css file",
+						onchange: move |text: String| path.set(text),
+						theme: input_theme,
+					}
+				}
+				Button {
+					onpress: load,
+					label { "Load" }
+				}
+			}
+			panel_header { title: "Preview" }
+			if let Some(preview) = preview {
+				theme_preview { colors: preview.colors }
+			} else {
+				rect {
+					width: "100%",
+					height: "fill",
+					main_align: "center",
+					cross_align: "center",
+					corner_radius: "8",
+					border: "1 outer {colors.border}",
+					background: "{colors.base}",
+					label {
+						color: "{colors.subtext0}",
+						font_size: "{appearance.ui_size(13.0)}",
+						font_family: "{appearance.font_family}",
+						"{message.unwrap_or_default()}"
+					}
+				}
+			}
+			rect {
+				width: "100%",
+				direction: "horizontal",
+				main_align: "end",
+				cross_align: "center",
+				spacing: "8",
+				label {
+					width: "fill",
+					color: "{colors.subtext0}",
+					font_size: "{appearance.ui_size(12.0)}",
+					font_family: "{appearance.font_family}",
+					"{status}"
+				}
+				Button {
+					onpress: export,
+					label { "Export Current to Base16" }
+				}
+				Button {
+					onpress: save,
+					label { "Save Themes" }
+				}
+				Button {
+					onpress: move |_| platform.close_window(),
+					label { "Close" }
+				}
+			}
+		}
+	})
+}
@@ -2,7 +2,7 @@
 use crate::data::io_utils::deinitialise_app;
 use crate::{
 	APP_ICON,
-	data::stores::{CURRENT_TAB, TABS, THEME_STORE, close_tab, new_tab, switch_tab},
+	data::stores::{CURRENT_TAB, SETTINGS, TABS, THEME_STORE, close_tab, new_tab, switch_tab},
 };
 use freya::hooks::Window;
 use freya::prelude::*;
@@ -10,11 +10,12 @@ use freya::prelude::*;
 #[component]
 pub fn top_nav_bar() -> Element {
 	let theme = THEME_STORE().current_theme.colors;
+	let appearance = SETTINGS().appearance;
 
 	rsx!(
 		rect {
 			width: "100%",
-			height: "40",
+			height: "{appearance.ui_size(40.0)}",
 			direction: "horizontal",
 			main_align: "space-between",
 			cross_align: "center",
@@ -40,6 +41,7 @@ pub fn top_nav_bar() -> Element {
 #[component]
 fn active_tabs() -> Element {
 	let theme = THEME_STORE().current_theme.colors;
+	let appearance = SETTINGS().appearance;
 
 	let mut is_hovered = use_signal(|| false);
 
@@ -114,8 +116,8 @@ fn active_tabs() -> Element {
 					onclick: move |_| new_tab(),
 					label {
 						color: "{ theme.text }",
-						font_size: "17",
-						font_family: "JetBrains Mono",
+						font_size: "{appearance.ui_size(17.0)}",
+						font_family: "{appearance.font_family}",
 						"+"
 					}
 				}
@@ -127,6 +129,7 @@ fn active_tabs() -> Element {
 #[component]
 fn tab_button(index: usize, on_click: EventHandler<()>, children: Element) -> Element {
 	let theme = THEME_STORE().current_theme.colors;
+	let appearance = SETTINGS().appearance;
 	// TODO: Handle Unwrap
 	let title = TABS().get(index).unwrap().title.clone();
 
@@ -157,7 +160,7 @@ fn tab_button(index: usize, on_click: EventHandler<()>, children: Element) -> El
 		CursorArea {
 			icon: CursorIcon::Pointer,
 			rect {
-				width: "160",
+				width: "{appearance.ui_size(160.0)}",
 				height: "75%",
 				padding: "2 12 2 15",
 				direction: "horizontal",
@@ -171,15 +174,15 @@ fn tab_button(index: usize, on_click: EventHandler<()>, children: Element) -> El
 				onmouseleave,
 				label {
 					color: "{ theme.text }",
-					font_size: "15",
-					font_family: "JetBrains Mono",
+					font_size: "{appearance.ui_size(15.0)}",
+					font_family: "{appearance.font_family}",
 					"{title}"
 				},
 				if CURRENT_TAB() == Some(index) || is_hovered() {
 					label {
 						color: "{ theme.text }",
-						font_size: "17",
-						font_family: "JetBrains Mono",
+						font_size: "{appearance.ui_size(17.0)}",
+						font_family: "{appearance.font_family}",
 						onclick: move |_| close_tab(index),
 						"×"
 					}
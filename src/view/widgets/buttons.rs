@@ -1,4 +1,4 @@
-use crate::data::stores::THEME_STORE;
+use crate::data::stores::{SETTINGS, THEME_STORE};
 use freya::prelude::*;
 
 #[derive(PartialEq, Clone)]
@@ -50,10 +50,15 @@ pub struct DropDownButtonProps {
 	pub onmouseleave: EventHandler<()>,
 	#[props(default)]
 	pub icon: Option<&'static str>,
+	#[props(default)]
+	pub selected: bool, // Highlighted by the keyboard
+	#[props(default)]
+	pub note: Option<String>, // Shown dimmed after the label
 }
 
 #[component]
 pub fn DropDownButton(props: DropDownButtonProps) -> Element {
+	let appearance = SETTINGS().appearance;
 	let theme = THEME_STORE().current_theme.colors;
 	let mut hovered = use_signal(|| false);
 
@@ -68,6 +73,11 @@ pub fn DropDownButton(props: DropDownButtonProps) -> Element {
 	});
 
 	let bg_color = &*animation.get().read_unchecked();
+	let background = if props.selected {
+		theme.surface1.clone()
+	} else {
+		bg_color.read().to_string()
+	};
 
 	rsx!(
 		CursorArea {
@@ -75,7 +85,7 @@ pub fn DropDownButton(props: DropDownButtonProps) -> Element {
 			rect {
 				width: "fill",
 				height: "auto",
-				background: "{bg_color.read()}",
+				background: "{background}",
 				corner_radius: "10",
 				padding: "5 6",
 				direction: "horizontal",
@@ -108,10 +118,19 @@ pub fn DropDownButton(props: DropDownButtonProps) -> Element {
 
 				label {
 					color:"{ theme.text }",
-					font_size: "14",
-					font_family: "JetBrains Mono",
+					font_size: "{appearance.ui_size(14.0)}",
+					font_family: "{appearance.font_family}",
 					"{props.label}"
 				}
+
+				if let Some(note) = props.note {
+					label {
+						color:"{ theme.subtext0 }",
+						font_size: "{appearance.ui_size(12.0)}",
+						font_family: "{appearance.font_family}",
+						"{note}"
+					}
+				}
 			}
 		}
 	)